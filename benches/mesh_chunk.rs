@@ -0,0 +1,16 @@
+use cairn::bench::HeadlessWorld;
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+fn mesh_chunk(criterion: &mut Criterion) {
+    let mut world = HeadlessWorld::new();
+    world.update_chunks(0, 0, 0);
+
+    // The surface chunk has exposed faces on every side, unlike the buried chunks below it
+    criterion.bench_function("mesh_chunk/surface", |bencher| {
+        bencher.iter(|| black_box(world.mesh_chunk(0, 0, 0)));
+    });
+}
+
+criterion_group!(benches, mesh_chunk);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+use cairn::bench::HeadlessWorld;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+fn update_chunks(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("update_chunks");
+    group.sample_size(10);
+
+    group.bench_function("initial_ring", |bencher| {
+        bencher.iter_batched(
+            HeadlessWorld::new,
+            |mut world| {
+                world.update_chunks(0, 0, 0);
+                black_box(world.update_mesh());
+                world
+            },
+            BatchSize::LargeInput,
+        );
+    });
+
+    group.bench_function("chunk_crossing", |bencher| {
+        bencher.iter_batched(
+            || {
+                let mut world = HeadlessWorld::new();
+                world.update_chunks(0, 0, 0);
+                world.update_mesh();
+                world
+            },
+            |mut world| {
+                world.update_chunks(1, 0, 0);
+                black_box(world.update_mesh());
+                world
+            },
+            BatchSize::LargeInput,
+        );
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, update_chunks);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+@group(0) @binding(0)
+var t_input: texture_2d<f32>;
+@group(0) @binding(1)
+var s_input: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) texture_coordinates: vec2<f32>,
+};
+
+// One triangle that covers the whole screen, with no vertex buffer
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let texture_coordinates = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: VertexOutput;
+    out.texture_coordinates = texture_coordinates;
+    out.clip_position = vec4<f32>(texture_coordinates * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_input, s_input, in.texture_coordinates);
+}
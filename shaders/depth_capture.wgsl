@@ -0,0 +1,15 @@
+@group(0) @binding(0)
+var t_depth: texture_depth_2d;
+
+// One triangle that covers the whole screen, with no vertex buffer
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let texture_coordinates = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(texture_coordinates * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 0.0, 1.0);
+}
+
+// Copies the stored depth as is; it is linearized once read back
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) f32 {
+    return textureLoad(t_depth, vec2<i32>(position.xy), 0);
+}
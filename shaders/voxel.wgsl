@@ -5,30 +5,183 @@ struct CameraUniform {
 @group(1) @binding(0)
 var<uniform> camera: CameraUniform;
 
+struct ShadingUniform {
+    alpha_cutoff: f32,
+    time: f32,
+    fade_duration: f32,
+    wave_amplitude: f32,
+    // Lower bound of the combined ambient occlusion and light factor, 0.15 by default
+    min_brightness: f32,
+    // 1 to apply each vertex's fixed per-face brightness, 0 to ignore it
+    face_shading: f32,
+    // Exponential squared fog per voxel of distance, 0 for none
+    fog_density: f32,
+    fog_color: vec4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> shading: ShadingUniform;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) texture_coordinates: vec2<f32>,
+    @location(2) emissive_texture_coordinates: vec2<f32>,
+    @location(3) emissive_strength: f32,
+    @location(4) fade_start: f32,
+    @location(5) wave_strength: f32,
+    @location(6) ambient_occlusion: f32,
+    @location(7) light: f32,
+    @location(8) normal: vec3<f32>,
+    @location(9) color: vec3<f32>,
+    @location(10) face_brightness: f32,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) texture_coordinates: vec2<f32>,
+    @location(1) emissive_texture_coordinates: vec2<f32>,
+    @location(2) emissive_strength: f32,
+    @location(3) fade_start: f32,
+    @location(4) brightness: f32,
+    @location(5) color: vec3<f32>,
+    // Distance in front of the camera, for fog
+    @location(6) view_depth: f32,
 };
 
 @vertex
 fn vs_main(model: VertexInput) -> VertexOutput {
     var out: VertexOutput;
     out.texture_coordinates = model.texture_coordinates;
-    out.clip_position = camera.view_projection * vec4<f32>(model.position, 1.0);
+    out.emissive_texture_coordinates = model.emissive_texture_coordinates;
+    out.emissive_strength = model.emissive_strength;
+    out.fade_start = model.fade_start;
+    let face_brightness = mix(1.0, model.face_brightness, shading.face_shading);
+    out.brightness = model.ambient_occlusion * model.light * face_brightness;
+    out.color = model.color;
+    var position = model.position;
+    position.y -= wave_depth(position) * model.wave_strength;
+    out.clip_position = camera.view_projection * vec4<f32>(position, 1.0);
+    out.view_depth = out.clip_position.w;
+    return out;
+}
+
+struct FaceInstanceInput {
+    @location(0) position: vec3<f32>,
+    @location(1) face: u32,
+    // u_min, u_max, v_min, v_max
+    @location(2) texture_coordinates: vec4<f32>,
+    @location(3) emissive_texture_coordinates: vec4<f32>,
+    @location(4) emissive_strength: f32,
+    // One value per corner, in `FACE_CORNERS` order
+    @location(5) ambient_occlusion: vec4<f32>,
+    @location(6) light: f32,
+};
+
+// Corner offsets for each face in `VoxelFace` order, wound like the expanded mesher's quads
+const FACE_CORNERS = array<vec3<f32>, 24>(
+    vec3(-0.5, -0.5, 0.5), vec3(0.5, -0.5, 0.5), vec3(0.5, 0.5, 0.5), vec3(-0.5, 0.5, 0.5),
+    vec3(-0.5, -0.5, -0.5), vec3(-0.5, 0.5, -0.5), vec3(0.5, 0.5, -0.5), vec3(0.5, -0.5, -0.5),
+    vec3(0.5, -0.5, -0.5), vec3(0.5, 0.5, -0.5), vec3(0.5, 0.5, 0.5), vec3(0.5, -0.5, 0.5),
+    vec3(-0.5, -0.5, -0.5), vec3(-0.5, -0.5, 0.5), vec3(-0.5, 0.5, 0.5), vec3(-0.5, 0.5, -0.5),
+    vec3(-0.5, 0.5, -0.5), vec3(-0.5, 0.5, 0.5), vec3(0.5, 0.5, 0.5), vec3(0.5, 0.5, -0.5),
+    vec3(-0.5, -0.5, -0.5), vec3(0.5, -0.5, -0.5), vec3(0.5, -0.5, 0.5), vec3(-0.5, -0.5, 0.5),
+);
+
+// Per-face brightness in `VoxelFace` order, matching `VoxelFace::brightness`
+const FACE_BRIGHTNESS = array<f32, 6>(0.65, 0.65, 0.8, 0.8, 1.0, 0.5);
+
+// Whether each corner takes the maximum u and v of the face's texture
+const FACE_CORNER_UVS = array<vec2<f32>, 24>(
+    vec2(0.0, 1.0), vec2(1.0, 1.0), vec2(1.0, 0.0), vec2(0.0, 0.0),
+    vec2(1.0, 1.0), vec2(1.0, 0.0), vec2(0.0, 0.0), vec2(0.0, 1.0),
+    vec2(1.0, 1.0), vec2(1.0, 0.0), vec2(0.0, 0.0), vec2(0.0, 1.0),
+    vec2(0.0, 1.0), vec2(1.0, 1.0), vec2(1.0, 0.0), vec2(0.0, 0.0),
+    vec2(0.0, 0.0), vec2(0.0, 1.0), vec2(1.0, 1.0), vec2(1.0, 0.0),
+    vec2(0.0, 1.0), vec2(1.0, 1.0), vec2(1.0, 0.0), vec2(0.0, 0.0),
+);
+
+const QUAD_INDICES = array<u32, 6>(0u, 1u, 2u, 2u, 3u, 0u);
+
+@vertex
+fn vs_instanced(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: FaceInstanceInput,
+) -> VertexOutput {
+    let corner_index = QUAD_INDICES[vertex_index];
+    let corner = instance.face * 4u + corner_index;
+    let select_max = FACE_CORNER_UVS[corner];
+    let coordinates = instance.texture_coordinates;
+
+    var out: VertexOutput;
+    out.texture_coordinates = mix(coordinates.xz, coordinates.yw, select_max);
+    let emissive = instance.emissive_texture_coordinates;
+    out.emissive_texture_coordinates = mix(emissive.xz, emissive.yw, select_max);
+    out.emissive_strength = instance.emissive_strength;
+    out.fade_start = 0.0;
+    let face_brightness = mix(1.0, FACE_BRIGHTNESS[instance.face], shading.face_shading);
+    out.brightness = instance.ambient_occlusion[corner_index] * instance.light * face_brightness;
+    out.color = vec3(1.0);
+    let position = instance.position + FACE_CORNERS[corner];
+    out.clip_position = camera.view_projection * vec4<f32>(position, 1.0);
+    out.view_depth = out.clip_position.w;
     return out;
 }
 
+// Two crossing sine waves in world space, so adjacent water voxels agree at shared corners.
+// The surface only dips below its resting height and never rises into the voxel above
+fn wave_depth(position: vec3<f32>) -> f32 {
+    let along = sin(position.x * 0.9 + position.z * 0.4 + shading.time * 1.6);
+    let across = sin(position.z * 1.1 - position.x * 0.5 + shading.time * 1.1);
+    return (1.0 - (along + across) * 0.5) * shading.wave_amplitude;
+}
+
 @group(0) @binding(0)
 var t_diffuse: texture_2d<f32>;
 @group(0) @binding(1)
 var s_diffuse: sampler;
 
+// Ambient occlusion and light darken the texel together, down to the configured floor. The
+// emissive overlay is blended over the lit base color so it stays at full brightness, and
+// fog over both so that distant faces fade into the sky
+fn shade(in: VertexOutput, base: vec4<f32>) -> vec4<f32> {
+    let lit = base.rgb * in.color * clamp(in.brightness, shading.min_brightness, 1.0);
+    let emissive = textureSample(t_diffuse, s_diffuse, in.emissive_texture_coordinates);
+    let color = mix(lit, emissive.rgb, emissive.a * in.emissive_strength);
+    let fog = 1.0 - exp(-pow(shading.fog_density * in.view_depth, 2.0));
+    return vec4<f32>(mix(color, shading.fog_color.rgb, fog), base.a);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.texture_coordinates);
-}
\ No newline at end of file
+    let base = textureSample(t_diffuse, s_diffuse, in.texture_coordinates);
+    return shade(in, base);
+}
+
+@fragment
+fn fs_cutout(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base = textureSample(t_diffuse, s_diffuse, in.texture_coordinates);
+    let color = shade(in, base);
+    if base.a < shading.alpha_cutoff {
+        discard;
+    }
+    return color;
+}
+
+// Newly loaded chunks are drawn through this pass and blend in from fully transparent to
+// opaque over the fade window
+@fragment
+fn fs_transparent(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base = textureSample(t_diffuse, s_diffuse, in.texture_coordinates);
+    let color = shade(in, base);
+    return vec4<f32>(color.rgb, color.a * fade(in));
+}
+
+fn fade(in: VertexOutput) -> f32 {
+    return clamp((shading.time - in.fade_start) / shading.fade_duration, 0.0, 1.0);
+}
+
+// Each layer adds a little warm light, so heavily overdrawn areas saturate toward white
+@fragment
+fn fs_overdraw(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.12, 0.06, 0.03, 1.0);
+}
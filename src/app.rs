@@ -1,46 +1,322 @@
-use crate::game::render::Renderer;
-use crate::game::world::World;
-use log::error;
+use crate::error::CairnError;
+use crate::game::chunk::CHUNK_SIZE;
+use crate::game::mesh::MeshCache;
+use crate::game::render::{Renderer, TextureAtlas};
+use crate::game::voxel::{VoxelRegistry, VoxelType};
+use crate::game::world::{ChunkEvent, ChunkPosition, World, WorldPosition};
+use crate::options::LaunchOptions;
+use crate::settings::Settings;
+use glam::Vec3;
+use log::{debug, error, info, warn};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use winit::application::ApplicationHandler;
-use winit::event::{DeviceEvent, DeviceId, ElementState, KeyEvent, WindowEvent};
-use winit::event_loop::ActiveEventLoop;
-use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::event::{DeviceEvent, DeviceId, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
 use winit::window::{CursorGrabMode, Icon, Window};
 
+const WINDOW_TITLE: &str = "Cairn";
 const WINDOW_ICON: &[u8] = include_bytes!("../assets/icon.png");
+const ATLAS_DUMP_PATH_VARIABLE: &str = "CAIRN_DUMP_ATLAS";
+const MESH_CACHE_PATH_VARIABLE: &str = "CAIRN_MESH_CACHE";
+const TELEPORT_VARIABLE: &str = "CAIRN_TELEPORT";
+const DEPTH_CAPTURE_PATH: &str = "depth.png";
+const SETTINGS_PATH: &str = "settings.toml";
+// Read when no definition directories are given on the command line
+const VOXEL_DEFINITIONS_PATH: &str = "assets/data/definitions/voxels";
+const REACH_DISTANCE: f32 = 8.0;
+// Half the width of the square or cube that the terraforming keys edit around the target
+const TERRAFORM_RADIUS: i32 = 4;
+// The simulation advances in steps of this length, 20 per second, whatever the frame rate
+const TICK_DURATION: Duration = Duration::from_millis(50);
+// After a stall the remaining backlog is dropped rather than simulated in one long frame
+const MAX_TICKS_PER_FRAME: u32 = 5;
+// The debug HUD text is rebuilt at this rate rather than every frame
+const DEBUG_HUD_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+// How long the camera takes to fly back to a bookmark
+const BOOKMARK_FLIGHT_DURATION: Duration = Duration::from_secs(1);
+const SPECTATOR_SPEED_MULTIPLIER: f32 = 2.5;
+// Degrees added or removed by each press of `]` or `[`
+const FOV_STEP: f32 = 5.0;
+// Pixels of touchpad scrolling that make one wheel step, about one line's worth
+const SCROLL_PIXELS_PER_STEP: f64 = 40.0;
+const BOOKMARK_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+const PRESENT_MODE_CYCLE: [wgpu::PresentMode; 3] = [
+    wgpu::PresentMode::AutoVsync,
+    wgpu::PresentMode::AutoNoVsync,
+    wgpu::PresentMode::Immediate,
+];
+
+/// What the player is allowed to do, switched as a whole rather than feature by feature.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum GameMode {
+    /// Edits the world and flies at the configured speeds.
+    #[default]
+    Creative,
+    /// Flies faster and through terrain, but cannot edit.
+    Spectator,
+}
+
+impl GameMode {
+    fn next(self) -> Self {
+        match self {
+            Self::Creative => Self::Spectator,
+            Self::Spectator => Self::Creative,
+        }
+    }
+
+    fn can_edit(self) -> bool {
+        self == Self::Creative
+    }
+}
 
 pub struct App {
+    launch_options: LaunchOptions,
+    settings: Settings,
+    saved_settings: Settings,
     renderer: Option<Renderer>,
-    world: Option<World>,
+    /// Independent worlds, such as dimensions reached through a portal, each with its own seed
+    /// and chunks. Nothing is loaded in them until the window exists.
+    worlds: Vec<World>,
+    active_world: usize,
     mouse_captured: bool,
+    mouse_capture_enabled: bool,
+    is_mouse_look_held: bool,
+    /// Where the cursor last was in the window, in physical pixels.
+    cursor_position: Option<(f32, f32)>,
+    /// Touchpad scrolling not yet large enough to make a whole step.
+    scroll_pixels: f64,
+    debug_hud_enabled: bool,
+    last_debug_hud_update: Option<Instant>,
+    pending_resize: Option<(u32, u32)>,
+    modifiers: ModifiersState,
+    bookmarks: [Option<Vec3>; BOOKMARK_KEYS.len()],
+    selected_voxel: VoxelType,
+    game_mode: GameMode,
+    error: Option<CairnError>,
+    /// Real time not yet consumed by world ticks.
+    tick_accumulator: Duration,
+    last_frame: Instant,
 }
 
-impl Default for App {
-    fn default() -> Self {
-        Self::new()
+impl App {
+    /// An app with the saved settings and no command-line overrides.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the world cannot be created, see `with_launch_options`.
+    pub fn new() -> Result<Self, CairnError> {
+        Self::with_launch_options(LaunchOptions::default())
     }
-}
 
-impl App {
-    pub fn new() -> Self {
-        Self {
+    /// Like `new`, with command-line overrides for the seed, render distance and so on.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the voxel definitions or the texture pack named by the launch
+    /// options cannot be loaded.
+    pub fn with_launch_options(launch_options: LaunchOptions) -> Result<Self, CairnError> {
+        let settings = Settings::load(Path::new(SETTINGS_PATH));
+        let mut app = Self {
+            launch_options,
+            saved_settings: settings.clone(),
+            settings,
             renderer: None,
-            world: None,
+            worlds: Vec::new(),
+            active_world: 0,
             mouse_captured: false,
+            mouse_capture_enabled: true,
+            is_mouse_look_held: false,
+            cursor_position: None,
+            scroll_pixels: 0.0,
+            debug_hud_enabled: false,
+            last_debug_hud_update: None,
+            pending_resize: None,
+            modifiers: ModifiersState::empty(),
+            bookmarks: [None; BOOKMARK_KEYS.len()],
+            selected_voxel: VoxelType::Stone,
+            game_mode: GameMode::default(),
+            error: None,
+            tick_accumulator: Duration::ZERO,
+            last_frame: Instant::now(),
+        };
+
+        let seed = app
+            .launch_options
+            .seed()
+            .unwrap_or_else(|| app.settings.seed());
+        let world = app.create_world(seed)?;
+        if let Ok(atlas_dump_path) = std::env::var(ATLAS_DUMP_PATH_VARIABLE) {
+            match world
+                .texture_atlas()
+                .save_debug_png(Path::new(&atlas_dump_path))
+            {
+                Ok(()) => info!("Saved texture atlas to {atlas_dump_path}"),
+                Err(error) => error!("Failed to save texture atlas: {error}"),
+            }
+            for (texture, (u_min, u_max, v_min, v_max)) in world.texture_atlas().layout() {
+                info!("Atlas slot {texture:?}: u {u_min}..{u_max}, v {v_min}..{v_max}");
+            }
         }
+        app.worlds.push(world);
+        Ok(app)
+    }
+
+    /// Opens the window and runs the game until it is closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the event loop, the window or the GPU device could not be set up.
+    pub fn run(mut self) -> Result<(), CairnError> {
+        let event_loop =
+            EventLoop::new().map_err(|error| CairnError::SurfaceCreation(error.into()))?;
+        event_loop
+            .run_app(&mut self)
+            .map_err(|error| CairnError::SurfaceCreation(error.into()))?;
+        self.error.map_or(Ok(()), Err)
+    }
+
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: CairnError) {
+        error!("{error}");
+        self.error = Some(error);
+        event_loop.exit();
     }
 
     fn update(&mut self) {
-        if let (Some(renderer), Some(world)) = (&mut self.renderer, &mut self.world) {
-            renderer.update();
+        if let (Some(renderer), Some(world)) =
+            (&mut self.renderer, self.worlds.get_mut(self.active_world))
+        {
+            let now = Instant::now();
+            let frame_time = now.duration_since(self.last_frame);
+            self.last_frame = now;
+            renderer.update(world, frame_time);
+
+            self.tick_accumulator += frame_time;
+            let mut tick_count = 0;
+            while self.tick_accumulator >= TICK_DURATION {
+                if tick_count == MAX_TICKS_PER_FRAME {
+                    self.tick_accumulator = Duration::ZERO;
+                    break;
+                }
+                world.tick();
+                self.tick_accumulator -= TICK_DURATION;
+                tick_count += 1;
+            }
+
             let chunk_position = renderer.camera().position().chunk_position();
 
             if world.last_update_position() != Some(chunk_position) {
                 world.update_chunks(chunk_position);
                 renderer.update_mesh(world);
+            } else if world.has_pending_chunk_loads() {
+                world.load_pending_chunks();
+                renderer.update_mesh(world);
+            } else if world.has_pending_meshes() {
+                renderer.update_mesh(world);
+            }
+
+            for chunk_event in world.drain_events() {
+                if let ChunkEvent::VoxelBroken(world_position, voxel_type, _) = chunk_event {
+                    renderer.spawn_break_particles(world, world_position, voxel_type);
+                }
+                debug!("{chunk_event:?}");
             }
         }
+
+        self.update_debug_hud();
+    }
+
+    /// The icon at the configured path, or the embedded one when none is set or it cannot be
+    /// read.
+    fn window_icon(&self) -> Icon {
+        if let Some(icon_path) = self.launch_options.icon() {
+            match std::fs::read(icon_path)
+                .map_err(anyhow::Error::from)
+                .and_then(|bytes| decode_icon(&bytes))
+            {
+                Ok(icon) => return icon,
+                Err(error) => warn!(
+                    "Using the default window icon, could not load {}: {error}",
+                    icon_path.display()
+                ),
+            }
+        }
+        decode_icon(WINDOW_ICON).expect("Embedded icon image should decode")
+    }
+
+    /// The voxel the camera is inside, or `None` before the world exists.
+    pub(crate) fn camera_voxel_type(&self) -> Option<VoxelType> {
+        let (Some(renderer), Some(world)) = (&self.renderer, self.worlds.get(self.active_world))
+        else {
+            return None;
+        };
+        Some(world.get_voxel_type(renderer.camera().position()))
+    }
+
+    /// Refreshes the F3 overlay at most every `DEBUG_HUD_REFRESH_INTERVAL` while it is shown.
+    fn update_debug_hud(&mut self) {
+        if !self.debug_hud_enabled
+            || self
+                .last_debug_hud_update
+                .is_some_and(|last_update| last_update.elapsed() < DEBUG_HUD_REFRESH_INTERVAL)
+        {
+            return;
+        }
+        let inside = self.camera_voxel_type().unwrap_or(VoxelType::Air);
+        let (Some(renderer), Some(world)) =
+            (&mut self.renderer, self.worlds.get(self.active_world))
+        else {
+            return;
+        };
+
+        let camera = renderer.camera();
+        let position = camera.exact_position();
+        let (chunk_x, chunk_y, chunk_z) = camera.position().chunk_position().get();
+        let facing = camera.forward();
+        let debug_hud_text = format!(
+            "XYZ: {:.2} / {:.2} / {:.2}\n\
+             Chunk: {chunk_x} {chunk_y} {chunk_z}\n\
+             Facing: {:.2} / {:.2} / {:.2}\n\
+             Loaded chunks: {}\n\
+             Pending remesh: {}\n\
+             Mesh queue: {}\n\
+             Inside: {inside:?}\n\
+             Memory: {}",
+            position.x,
+            position.y,
+            position.z,
+            facing.x,
+            facing.y,
+            facing.z,
+            world.chunk_data().len(),
+            world.pending_remesh_count(),
+            world.mesh_queue_depth(),
+            world.memory_report(),
+        );
+        renderer.set_debug_hud_text(Some(&debug_hud_text));
+        self.last_debug_hud_update = Some(Instant::now());
+    }
+
+    fn toggle_debug_hud(&mut self) {
+        self.debug_hud_enabled = !self.debug_hud_enabled;
+        self.last_debug_hud_update = None;
+        if !self.debug_hud_enabled
+            && let Some(renderer) = &mut self.renderer
+        {
+            renderer.set_debug_hud_text(None);
+        }
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -56,11 +332,531 @@ impl App {
         }
     }
 
+    /// Ctrl+number saves the camera position to a slot and the number alone flies back to it,
+    /// keeping the current facing.
+    fn use_bookmark(&mut self, slot: usize) {
+        let (Some(renderer), Some(bookmark)) = (&mut self.renderer, self.bookmarks.get_mut(slot))
+        else {
+            return;
+        };
+
+        if self.modifiers.control_key() {
+            let position = renderer.camera().exact_position();
+            *bookmark = Some(position);
+            info!("Saved bookmark {} at {position}", slot + 1);
+        } else if let Some(position) = *bookmark {
+            let camera = renderer.camera_mut();
+            let look = position + camera.forward();
+            camera.move_to(position, look, BOOKMARK_FLIGHT_DURATION);
+            info!("Returned to bookmark {} at {position}", slot + 1);
+        }
+    }
+
+    /// Lifts or drops the camera to `spawn_height` above the terrain under it, so that it never
+    /// starts inside solid ground. Chunks around the starting position should be loaded first.
+    fn place_camera_above_surface(&self, renderer: &mut Renderer, world: &mut World) {
+        let position = renderer.camera().exact_position();
+        let surface_height =
+            world.surface_height(position.x.round() as i32, position.z.round() as i32);
+        let spawn_position = position.with_y(surface_height as f32 + self.settings.spawn_height());
+        renderer.camera_mut().set_position(spawn_position);
+
+        let chunk_position = renderer.camera().position().chunk_position();
+        if world.last_update_position() != Some(chunk_position) {
+            world.update_chunks(chunk_position);
+        }
+        info!("Spawned at {spawn_position} over a surface at height {surface_height}");
+    }
+
+    /// Moves the camera to the center of the chunk named by `CAIRN_TELEPORT`, e.g. `3,0,-2`.
+    /// Streaming follows on the next update since the camera changed chunks.
+    fn teleport_to_chunk(&mut self) {
+        let Some(renderer) = &mut self.renderer else {
+            return;
+        };
+        let Ok(value) = std::env::var(TELEPORT_VARIABLE) else {
+            warn!("Set {TELEPORT_VARIABLE} to a chunk position such as 3,0,-2 to teleport");
+            return;
+        };
+        let coordinates = value
+            .split(',')
+            .map(|coordinate| coordinate.trim().parse::<i32>())
+            .collect::<Result<Vec<i32>, _>>();
+        let Ok(&[x, y, z]) = coordinates.as_deref() else {
+            warn!("Invalid chunk position in {TELEPORT_VARIABLE}: {value}");
+            return;
+        };
+
+        let half_chunk = CHUNK_SIZE as i32 / 2;
+        let center = ChunkPosition::new(x, y, z)
+            .min_corner()
+            .offset(half_chunk, half_chunk, half_chunk);
+        renderer
+            .camera_mut()
+            .set_position(Vec3::from(center.get_f32()));
+        info!("Teleported to chunk {x} {y} {z}");
+    }
+
+    /// Steps through the registry's placeable voxel definitions, wrapping at either end.
+    fn scroll_selected_voxel(&mut self, steps: i32) {
+        if !self.game_mode.can_edit() {
+            return;
+        }
+        let (Some(renderer), Some(world)) =
+            (&mut self.renderer, self.worlds.get(self.active_world))
+        else {
+            return;
+        };
+
+        let definitions = world.voxel_registry().definitions().collect::<Vec<_>>();
+        let index = definitions
+            .iter()
+            .position(|(voxel_type, _, _)| *voxel_type == self.selected_voxel)
+            .unwrap_or(0);
+        let index = (index as i32 + steps).rem_euclid(definitions.len().max(1) as i32) as usize;
+        if let Some((voxel_type, display_name, texture)) = definitions.get(index) {
+            self.selected_voxel = *voxel_type;
+            debug!("Selected {display_name} ({texture:?})");
+        }
+        renderer.set_held_voxel(world, self.selected_voxel);
+    }
+
+    fn handle_key(&mut self, code: KeyCode, is_pressed: bool, is_repeat: bool) {
+        if code == KeyCode::Escape && is_pressed {
+            self.release_mouse();
+        }
+        if code == KeyCode::KeyV && is_pressed && !is_repeat {
+            self.cycle_present_mode();
+        }
+        if is_pressed && !is_repeat {
+            self.handle_debug_key(code);
+        }
+        if let Some(fov_step) = match code {
+            KeyCode::BracketLeft => Some(-FOV_STEP),
+            KeyCode::BracketRight => Some(FOV_STEP),
+            _ => None,
+        } && is_pressed
+            && let Some(renderer) = &mut self.renderer
+        {
+            let fov = renderer.set_fov(renderer.camera().fov_y() + fov_step);
+            info!("Field of view: {fov}°");
+        }
+        if code == KeyCode::F8 && is_pressed && !is_repeat {
+            self.switch_world();
+        }
+        if matches!(code, KeyCode::Minus | KeyCode::Equal) && is_pressed {
+            self.step_chunk_draw_limit(code == KeyCode::Equal);
+        }
+        if matches!(code, KeyCode::PageUp | KeyCode::PageDown) && is_pressed {
+            self.step_render_distance(code == KeyCode::PageUp);
+        }
+        if code == KeyCode::KeyO && is_pressed && !is_repeat {
+            self.toggle_orbit_mode();
+        }
+        if code == KeyCode::KeyG && is_pressed && !is_repeat {
+            self.set_game_mode(self.game_mode.next());
+        }
+        if code == KeyCode::KeyT && is_pressed && !is_repeat {
+            self.teleport_to_chunk();
+        }
+        if code == KeyCode::KeyF && is_pressed && !is_repeat {
+            self.flatten_around_target();
+        }
+        if code == KeyCode::KeyR && is_pressed && !is_repeat {
+            self.replace_around_target();
+        }
+        if code == KeyCode::KeyP && is_pressed && !is_repeat {
+            self.paint_target();
+        }
+        if is_pressed
+            && !is_repeat
+            && let Some(slot) = BOOKMARK_KEYS.iter().position(|key| *key == code)
+        {
+            self.use_bookmark(slot);
+        }
+        if code == KeyCode::F6 && is_pressed && !is_repeat {
+            self.set_mouse_capture_enabled(!self.mouse_capture_enabled);
+            info!("Mouse capture enabled: {}", self.mouse_capture_enabled);
+        }
+        if let Some(renderer) = &mut self.renderer {
+            renderer
+                .camera_controller()
+                .handle_keyboard_input(code, is_pressed);
+        }
+    }
+
+    /// Toggles and captures for debugging, on the F keys.
+    fn handle_debug_key(&mut self, code: KeyCode) {
+        if code == KeyCode::F2
+            && let (Some(renderer), Some(world)) =
+                (&mut self.renderer, self.worlds.get_mut(self.active_world))
+        {
+            let is_chunk_flashing_enabled = world.toggle_chunk_flashes();
+            renderer.update_mesh(world);
+            info!("Chunk load flashes enabled: {is_chunk_flashing_enabled}");
+        }
+        if code == KeyCode::F3 {
+            self.toggle_debug_hud();
+        }
+        if code == KeyCode::F4
+            && let (Some(renderer), Some(world)) =
+                (&mut self.renderer, self.worlds.get(self.active_world))
+        {
+            renderer.toggle_chunk_bounds(world);
+        }
+        if code == KeyCode::F5
+            && let Some(renderer) = &mut self.renderer
+        {
+            let is_face_culling_enabled = renderer.toggle_face_culling();
+            info!("Face culling enabled: {is_face_culling_enabled}");
+        }
+        // Shift+F7 rebuilds only the chunk the camera is in
+        if code == KeyCode::F7
+            && let (Some(renderer), Some(world)) =
+                (&mut self.renderer, self.worlds.get_mut(self.active_world))
+        {
+            let chunk_position = renderer.camera().position().chunk_position();
+            if !self.modifiers.shift_key() {
+                world.invalidate_all_meshes();
+                renderer.update_mesh(world);
+                info!("Remeshed {} loaded chunks", world.chunk_meshes().len());
+            } else if world.chunk_data().contains_key(&chunk_position) {
+                let index_count = world.remesh_chunk(chunk_position).index_count();
+                renderer.update_mesh(world);
+                let (x, y, z) = chunk_position.get();
+                info!("Remeshed chunk {x} {y} {z} with {index_count} indices");
+            }
+        }
+        if code == KeyCode::F9
+            && let Some(renderer) = &mut self.renderer
+        {
+            let is_solid_clamp_enabled = renderer.camera_controller().toggle_solid_clamp();
+            info!("Solid clamp enabled: {is_solid_clamp_enabled}");
+        }
+        if code == KeyCode::F10
+            && let Some(renderer) = &mut self.renderer
+        {
+            let is_overdraw_enabled = renderer.toggle_overdraw();
+            info!("Overdraw view enabled: {is_overdraw_enabled}");
+        }
+        if code == KeyCode::F11
+            && let (Some(renderer), Some(world)) =
+                (&mut self.renderer, self.worlds.get(self.active_world))
+        {
+            let is_frustum_frozen = renderer.toggle_frozen_frustum(world);
+            info!("Culling frustum frozen: {is_frustum_frozen}");
+        }
+        if code == KeyCode::F12
+            && let Some(renderer) = &mut self.renderer
+        {
+            renderer.capture_depth(Path::new(DEPTH_CAPTURE_PATH));
+        }
+    }
+
+    fn set_game_mode(&mut self, game_mode: GameMode) {
+        self.game_mode = game_mode;
+        let Some(renderer) = &mut self.renderer else {
+            return;
+        };
+
+        let speed_multiplier = match game_mode {
+            GameMode::Creative => 1.0,
+            GameMode::Spectator => SPECTATOR_SPEED_MULTIPLIER,
+        };
+        let camera_controller = renderer.camera_controller();
+        camera_controller.set_move_speed(self.settings.move_speed() * speed_multiplier);
+        camera_controller
+            .set_vertical_move_speed(self.settings.vertical_move_speed() * speed_multiplier);
+        camera_controller.set_solid_clamp_enabled(
+            game_mode == GameMode::Creative && self.settings.solid_clamp(),
+        );
+        info!("Game mode: {game_mode:?}");
+    }
+
+    /// Halves or doubles how many of the nearest chunks are drawn. Halving starts from all
+    /// loaded chunks, and doubling past them lifts the limit.
+    fn step_chunk_draw_limit(&mut self, is_increase: bool) {
+        let (Some(renderer), Some(world)) =
+            (&mut self.renderer, self.worlds.get_mut(self.active_world))
+        else {
+            return;
+        };
+        let loaded_count = world.chunk_data().len();
+        let current = renderer.chunk_draw_limit().unwrap_or(loaded_count);
+        let chunk_draw_limit = if is_increase {
+            Some(current.saturating_mul(2)).filter(|limit| *limit < loaded_count)
+        } else {
+            Some((current / 2).max(1))
+        };
+        renderer.set_chunk_draw_limit(world, chunk_draw_limit);
+        match chunk_draw_limit {
+            Some(limit) => info!("Drawing the {limit} nearest chunks"),
+            None => info!("Drawing all loaded chunks"),
+        }
+    }
+
+    /// Widens or narrows the horizontal render distance by one chunk, keeping at least one, and
+    /// saves it to the settings.
+    fn step_render_distance(&mut self, is_increase: bool) {
+        let (Some(renderer), Some(world)) =
+            (&mut self.renderer, self.worlds.get_mut(self.active_world))
+        else {
+            return;
+        };
+        let step = if is_increase { 1 } else { -1 };
+        let render_distance_xz = (world.render_distance_xz() + step).max(1);
+        renderer.set_render_distance(world, render_distance_xz);
+        self.settings.set_render_distance_xz(render_distance_xz);
+        info!("Render distance: {render_distance_xz} chunks");
+    }
+
+    /// Orbits the targeted voxel, or the origin when nothing is in reach.
+    fn toggle_orbit_mode(&mut self) {
+        let (Some(renderer), Some(world)) =
+            (&mut self.renderer, self.worlds.get(self.active_world))
+        else {
+            return;
+        };
+        let (origin, direction) = renderer.camera().ray();
+        let focus = world
+            .raycast(origin, direction, REACH_DISTANCE)
+            .map_or(Vec3::ZERO, |world_position| {
+                Vec3::from(world_position.get_f32())
+            });
+        let is_orbiting = renderer.toggle_orbit_mode(focus);
+        info!("Orbit camera enabled: {is_orbiting}");
+    }
+
+    /// The voxel in the middle of the view while the mouse is captured, or the one under the
+    /// cursor otherwise, if the game mode allows editing it.
+    fn targeted_voxel(&self) -> Option<WorldPosition> {
+        let (Some(renderer), Some(world)) = (&self.renderer, self.worlds.get(self.active_world))
+        else {
+            return None;
+        };
+        if !self.game_mode.can_edit() {
+            return None;
+        }
+        let camera = renderer.camera();
+        let (origin, direction) = match self.cursor_position {
+            Some((x, y)) if !self.mouse_captured => camera.screen_ray(x, y),
+            _ => camera.ray(),
+        };
+        world.raycast(origin, direction, REACH_DISTANCE)
+    }
+
+    fn break_targeted_voxel(&mut self) {
+        if let Some(world_position) = self.targeted_voxel()
+            && let Some(world) = self.worlds.get_mut(self.active_world)
+        {
+            world.break_voxel(world_position);
+        }
+    }
+
+    /// Levels the square around the targeted voxel to its height with the selected voxel.
+    fn flatten_around_target(&mut self) {
+        if let Some(world_position) = self.targeted_voxel()
+            && let Some(world) = self.worlds.get_mut(self.active_world)
+        {
+            let (x, y, z) = world_position.get();
+            world.flatten(
+                (x - TERRAFORM_RADIUS, z - TERRAFORM_RADIUS),
+                (x + TERRAFORM_RADIUS, z + TERRAFORM_RADIUS),
+                y,
+                self.selected_voxel,
+            );
+            info!("Flattened around {x} {y} {z}");
+        }
+    }
+
+    /// Swaps every voxel of the targeted type in the cube around it for the selected voxel.
+    fn replace_around_target(&mut self) {
+        if let Some(world_position) = self.targeted_voxel()
+            && let Some(world) = self.worlds.get_mut(self.active_world)
+        {
+            let from = world.get_voxel_type(world_position);
+            let (x, y, z) = world_position.get();
+            world.replace_all(
+                WorldPosition::new(
+                    x - TERRAFORM_RADIUS,
+                    y - TERRAFORM_RADIUS,
+                    z - TERRAFORM_RADIUS,
+                ),
+                WorldPosition::new(
+                    x + TERRAFORM_RADIUS,
+                    y + TERRAFORM_RADIUS,
+                    z + TERRAFORM_RADIUS,
+                ),
+                from,
+                self.selected_voxel,
+            );
+            info!(
+                "Replaced {from:?} with {:?} around {x} {y} {z}",
+                self.selected_voxel
+            );
+        }
+    }
+
+    /// Places the targeted voxel again with the selected voxel's textures on its faces, keeping
+    /// its type.
+    fn paint_target(&mut self) {
+        if let Some(world_position) = self.targeted_voxel()
+            && let Some(world) = self.worlds.get_mut(self.active_world)
+        {
+            let voxel_type = world.get_voxel_type(world_position);
+            let textures = world
+                .voxel_registry()
+                .get_properties(&self.selected_voxel)
+                .textures()
+                .clone();
+            world.place_voxel_with_textures(world_position, voxel_type, textures);
+            let (x, y, z) = world_position.get();
+            info!(
+                "Painted {voxel_type:?} at {x} {y} {z} with {:?} textures",
+                self.selected_voxel
+            );
+        }
+    }
+
+    /// A world configured from the settings and launch options, with nothing loaded yet.
+    fn create_world(&self, seed: u64) -> Result<World, CairnError> {
+        let mut world = World::new_with_seed(seed);
+        info!("Generating world with seed {}", world.seed());
+        let (render_distance_xz, render_distance_y) = match self.launch_options.render_distance() {
+            Some((render_distance_xz, render_distance_y)) => (
+                render_distance_xz,
+                render_distance_y.unwrap_or_else(|| self.settings.render_distance_y()),
+            ),
+            None => (
+                self.settings.render_distance_xz(),
+                self.settings.render_distance_y(),
+            ),
+        };
+        world.set_render_distance(render_distance_xz, render_distance_y);
+        // An explicit vertical distance on the command line wins over the saved asymmetry
+        let (render_distance_up, render_distance_down) = match self.launch_options.render_distance()
+        {
+            Some((_, Some(render_distance_y))) => (render_distance_y, render_distance_y),
+            _ => self.settings.vertical_render_distance(render_distance_y),
+        };
+        world.set_vertical_render_distance(render_distance_up, render_distance_down);
+        world.set_render_shape(self.settings.render_shape());
+        if let Some(chunk_load_budget) = self.settings.chunk_load_budget() {
+            world.set_chunk_load_budget(chunk_load_budget);
+        }
+        world.set_background_meshing(self.settings.background_meshing());
+
+        let voxel_definitions = self.launch_options.voxel_definitions();
+        let voxel_registry = if voxel_definitions.is_empty() {
+            VoxelRegistry::load_from_directory(Path::new(VOXEL_DEFINITIONS_PATH))
+        } else {
+            VoxelRegistry::load_from_directories(&voxel_definitions)
+        }?;
+        world.set_voxel_registry(voxel_registry);
+
+        if let Some(texture_pack_path) = self.launch_options.texture_pack() {
+            match TextureAtlas::from_pack(texture_pack_path) {
+                Ok(texture_atlas) => world.set_texture_atlas(texture_atlas),
+                Err(error) => {
+                    return Err(CairnError::AssetLoad {
+                        path: texture_pack_path.to_path_buf(),
+                        source: error.into(),
+                    });
+                }
+            }
+        }
+
+        if let Ok(mesh_cache_path) = std::env::var(MESH_CACHE_PATH_VARIABLE) {
+            match MeshCache::new(PathBuf::from(&mesh_cache_path)) {
+                Ok(mesh_cache) => world.set_mesh_cache(mesh_cache),
+                Err(error) => error!("Failed to open mesh cache at {mesh_cache_path}: {error}"),
+            }
+        }
+
+        Ok(world)
+    }
+
+    /// Moves to the next world, creating a second one from the following seed the first time.
+    /// The camera keeps its position, and the renderer's buffers are rebuilt from the newly
+    /// active world's chunk meshes.
+    fn switch_world(&mut self) {
+        if let [world] = self.worlds.as_slice() {
+            match self.create_world(world.seed().wrapping_add(1)) {
+                Ok(world) => self.worlds.push(world),
+                Err(error) => {
+                    error!("Failed to create another world: {error}");
+                    return;
+                }
+            }
+        }
+        if self.worlds.is_empty() {
+            return;
+        }
+
+        self.active_world = (self.active_world + 1) % self.worlds.len();
+        if let (Some(renderer), Some(world)) =
+            (&mut self.renderer, self.worlds.get_mut(self.active_world))
+        {
+            info!(
+                "Switched to world {} with seed {}",
+                self.active_world,
+                world.seed()
+            );
+            world.update_chunks(renderer.camera().position().chunk_position());
+            renderer.update_mesh(world);
+        }
+    }
+
+    fn save_settings(&mut self) {
+        if self.settings == self.saved_settings {
+            return;
+        }
+
+        match self.settings.save(Path::new(SETTINGS_PATH)) {
+            Ok(()) => self.saved_settings = self.settings.clone(),
+            Err(error) => error!("Failed to save settings: {error}"),
+        }
+    }
+
+    fn cycle_present_mode(&mut self) {
+        let Some(renderer) = &mut self.renderer else {
+            return;
+        };
+
+        let current_index = PRESENT_MODE_CYCLE
+            .iter()
+            .position(|present_mode| *present_mode == renderer.present_mode())
+            .unwrap_or(0);
+
+        for present_mode in PRESENT_MODE_CYCLE
+            .iter()
+            .cycle()
+            .skip(current_index + 1)
+            .take(PRESENT_MODE_CYCLE.len() - 1)
+        {
+            if renderer.supports_present_mode(*present_mode) {
+                renderer.set_present_mode(*present_mode);
+                info!("Switched present mode to {present_mode:?}");
+                return;
+            }
+            info!("Skipping unsupported present mode {present_mode:?}");
+        }
+    }
+
+    /// With capture disabled the cursor stays free and the camera only turns while a mouse
+    /// button is held.
+    pub fn set_mouse_capture_enabled(&mut self, mouse_capture_enabled: bool) {
+        self.mouse_capture_enabled = mouse_capture_enabled;
+        if !mouse_capture_enabled {
+            self.release_mouse();
+        }
+    }
+
     fn grab_mouse(&mut self) {
         if let Some(renderer) = &self.renderer {
             let window = renderer.window();
 
-            if !self.mouse_captured {
+            if self.mouse_capture_enabled && !self.mouse_captured {
                 window
                     .set_cursor_grab(CursorGrabMode::Confined)
                     .expect("Window should grab cursor");
@@ -91,45 +887,45 @@ impl ApplicationHandler for App {
             return;
         }
 
-        let mut world = World::new();
-
-        let (icon_rgba, icon_width, icon_height) = {
-            let image = image::load_from_memory(WINDOW_ICON)
-                .expect("Icon image should load")
-                .into_rgba8();
-            let (width, height) = image.dimensions();
-            let rgba = image.into_raw();
-            (rgba, width, height)
+        let Some(mut world) = self.worlds.pop() else {
+            return;
         };
-        let icon = Icon::from_rgba(icon_rgba, icon_width, icon_height)
-            .expect("Icon image should build from rgba");
 
+        let icon = self.window_icon();
         let window_attributes = Window::default_attributes()
-            .with_title("Cairn")
+            .with_title(WINDOW_TITLE)
             .with_window_icon(Some(icon))
             .with_maximized(true);
 
-        let window = Arc::new(
-            event_loop
-                .create_window(window_attributes)
-                .expect("Window should be created"),
-        );
+        let window = match event_loop.create_window(window_attributes) {
+            Ok(window) => Arc::new(window),
+            Err(error) => {
+                self.fail(event_loop, CairnError::SurfaceCreation(error.into()));
+                return;
+            }
+        };
 
-        let renderer = match pollster::block_on(Renderer::new(window, &mut world)) {
+        let renderer = match pollster::block_on(Renderer::new(window, &mut world, &self.settings)) {
             Ok(mut renderer) => {
+                renderer.apply_settings(&self.settings);
+                if let Some(present_mode) = self.launch_options.present_mode() {
+                    renderer.set_present_mode(present_mode);
+                }
                 let chunk_position = renderer.camera().position().chunk_position();
                 world.update_chunks(chunk_position);
+                self.place_camera_above_surface(&mut renderer, &mut world);
                 renderer.update_mesh(&mut world);
+                renderer.set_held_voxel(&world, self.selected_voxel);
                 renderer
             }
             Err(error) => {
-                error!("Failed to create renderer: {error}");
-                event_loop.exit();
+                self.fail(event_loop, error);
                 return;
             }
         };
 
-        self.world = Some(world);
+        self.worlds = vec![world];
+        self.active_world = 0;
         self.renderer = Some(renderer);
     }
 
@@ -140,9 +936,16 @@ impl ApplicationHandler for App {
         event: WindowEvent,
     ) {
         match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
-            WindowEvent::Resized(size) => self.resize(size.width, size.height),
+            WindowEvent::CloseRequested => {
+                self.save_settings();
+                event_loop.exit();
+            }
+            // A drag-resize sends many events per frame, so only the latest size is applied
+            WindowEvent::Resized(size) => self.pending_resize = Some((size.width, size.height)),
             WindowEvent::RedrawRequested => {
+                if let Some((width, height)) = self.pending_resize.take() {
+                    self.resize(width, height);
+                }
                 self.update();
                 match self.render() {
                     Ok(_) => {}
@@ -162,25 +965,42 @@ impl ApplicationHandler for App {
                     KeyEvent {
                         physical_key: PhysicalKey::Code(code),
                         state: key_state,
+                        repeat: is_repeat,
                         ..
                     },
                 ..
             } => {
-                let is_pressed = key_state.is_pressed();
-                if code == KeyCode::Escape && is_pressed {
-                    self.release_mouse();
+                self.handle_key(code, key_state.is_pressed(), is_repeat);
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let steps = scroll_steps(delta, &mut self.scroll_pixels);
+                if steps == 0 {
+                    return;
                 }
-                if let Some(renderer) = &mut self.renderer {
-                    renderer
-                        .camera_controller()
-                        .handle_keyboard_input(code, is_pressed);
+                let is_zooming = self
+                    .renderer
+                    .as_mut()
+                    .is_some_and(|renderer| renderer.camera_controller().zoom_orbit(steps));
+                if !is_zooming {
+                    self.scroll_selected_voxel(steps);
                 }
             }
-            WindowEvent::MouseInput {
-                state: ElementState::Pressed,
-                ..
-            } => {
-                self.grab_mouse();
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = Some((position.x as f32, position.y as f32));
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor_position = None,
+            WindowEvent::MouseInput { state, button, .. } => {
+                if button == MouseButton::Left
+                    && state.is_pressed()
+                    && (self.mouse_captured || !self.mouse_capture_enabled)
+                {
+                    self.break_targeted_voxel();
+                }
+                self.is_mouse_look_held = !self.mouse_capture_enabled && state.is_pressed();
+                if state.is_pressed() {
+                    self.grab_mouse();
+                }
             }
             _ => {}
         }
@@ -192,7 +1012,7 @@ impl ApplicationHandler for App {
         _device_id: DeviceId,
         event: DeviceEvent,
     ) {
-        if !self.mouse_captured {
+        if !self.mouse_captured && !self.is_mouse_look_held {
             return;
         }
 
@@ -209,3 +1029,63 @@ impl ApplicationHandler for App {
             .handle_mouse_input(delta.0 as f32, delta.1 as f32);
     }
 }
+
+/// Whole steps to scroll by, down being positive. Touchpad pixel deltas add up in
+/// `scroll_pixels` until they make a step, keeping the remainder for the next event.
+fn scroll_steps(delta: MouseScrollDelta, scroll_pixels: &mut f64) -> i32 {
+    match delta {
+        MouseScrollDelta::LineDelta(_, y) => -y.round() as i32,
+        MouseScrollDelta::PixelDelta(position) => {
+            *scroll_pixels += position.y;
+            let steps = (*scroll_pixels / SCROLL_PIXELS_PER_STEP).trunc();
+            *scroll_pixels -= steps * SCROLL_PIXELS_PER_STEP;
+            -steps as i32
+        }
+    }
+}
+
+fn decode_icon(bytes: &[u8]) -> anyhow::Result<Icon> {
+    let image = image::load_from_memory(bytes)?.into_rgba8();
+    let (width, height) = image.dimensions();
+    Ok(Icon::from_rgba(image.into_raw(), width, height)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::dpi::PhysicalPosition;
+
+    #[test]
+    fn scrolling_only_steps_once_a_whole_step_has_been_scrolled() {
+        let mut scroll_pixels = 0.0;
+        assert_eq!(
+            scroll_steps(MouseScrollDelta::LineDelta(3.0, 0.0), &mut scroll_pixels),
+            0,
+            "Horizontal scrolling should not step"
+        );
+        assert_eq!(
+            scroll_steps(MouseScrollDelta::LineDelta(0.0, -2.0), &mut scroll_pixels),
+            2,
+            "Each wheel line down should be one step"
+        );
+
+        let pixels = |y: f64| MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, y));
+        let steps =
+            [5.0, 10.0, 15.0, 20.0, 25.0].map(|y| scroll_steps(pixels(y), &mut scroll_pixels));
+        assert_eq!(
+            steps,
+            [0, 0, 0, -1, 0],
+            "Small touchpad deltas should add up to a single step"
+        );
+        assert_eq!(
+            scroll_steps(pixels(-50.0), &mut scroll_pixels),
+            0,
+            "Scrolling back should first use up the remainder"
+        );
+        assert_eq!(
+            scroll_steps(pixels(-40.0), &mut scroll_pixels),
+            1,
+            "Scrolling back a whole step further should step the other way"
+        );
+    }
+}
@@ -0,0 +1,38 @@
+//! Entry points for the benchmarks in `benches/`. Not a stable API.
+
+use crate::game::mesh::Mesh;
+use crate::game::world::{ChunkPosition, WORLD_SEED, World};
+
+/// A world that streams and meshes chunks on the CPU only, without a window or GPU.
+pub struct HeadlessWorld {
+    world: World,
+}
+
+impl Default for HeadlessWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeadlessWorld {
+    pub fn new() -> Self {
+        Self {
+            world: World::new_with_seed(WORLD_SEED),
+        }
+    }
+
+    pub fn update_chunks(&mut self, x: i32, y: i32, z: i32) {
+        self.world.update_chunks(ChunkPosition::new(x, y, z));
+    }
+
+    /// Meshes every loaded chunk that has no mesh yet and returns the merged index count.
+    pub fn update_mesh(&mut self) -> u32 {
+        Mesh::world(&mut self.world, None, false).index_count()
+    }
+
+    /// Meshes one loaded chunk from scratch, bypassing the mesh caches, and returns its index
+    /// count.
+    pub fn mesh_chunk(&self, x: i32, y: i32, z: i32) -> u32 {
+        Mesh::build_chunk(&self.world, ChunkPosition::new(x, y, z), None).index_count()
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+use std::path::PathBuf;
+
+type Source = Box<dyn std::error::Error + Send + Sync>;
+
+/// Failures that can stop the game from starting, returned from creating or running the `App`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CairnError {
+    /// No adapter or device could be acquired for the window's surface.
+    GpuUnavailable(Source),
+    /// The window or its rendering surface could not be created.
+    SurfaceCreation(Source),
+    /// An asset such as a texture pack or a voxel definitions directory at `path` could not be
+    /// read or parsed.
+    AssetLoad { path: PathBuf, source: Source },
+    /// Generating the world failed. Generation cannot fail yet, so this is reserved for
+    /// generators that can.
+    WorldGen(Source),
+}
+
+impl fmt::Display for CairnError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GpuUnavailable(source) => write!(formatter, "No usable GPU found: {source}"),
+            Self::SurfaceCreation(source) => {
+                write!(formatter, "Failed to create window surface: {source}")
+            }
+            Self::AssetLoad { path, source } => {
+                write!(formatter, "Failed to load {}: {source}", path.display())
+            }
+            Self::WorldGen(source) => write!(formatter, "Failed to generate world: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for CairnError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::GpuUnavailable(source)
+            | Self::SurfaceCreation(source)
+            | Self::AssetLoad { source, .. }
+            | Self::WorldGen(source) => Some(source.as_ref()),
+        }
+    }
+}
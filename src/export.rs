@@ -0,0 +1,63 @@
+use crate::game::chunk::Chunk;
+use crate::game::world::ChunkPosition;
+use log::info;
+use std::path::PathBuf;
+
+/// Generates an inclusive box of chunks and writes each one to disk without opening a window,
+/// for producing deterministic world fixtures.
+#[derive(Clone, Debug)]
+pub struct RegionExport {
+    seed: u64,
+    min: ChunkPosition,
+    max: ChunkPosition,
+    directory: PathBuf,
+}
+
+impl RegionExport {
+    pub(crate) fn new(
+        seed: u64,
+        min: ChunkPosition,
+        max: ChunkPosition,
+        directory: PathBuf,
+    ) -> Self {
+        Self {
+            seed,
+            min,
+            max,
+            directory,
+        }
+    }
+
+    /// Returns the number of chunks written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output directory cannot be created or a chunk file cannot be
+    /// written.
+    pub fn run(&self) -> anyhow::Result<usize> {
+        std::fs::create_dir_all(&self.directory)?;
+
+        let ((min_x, min_y, min_z), (max_x, max_y, max_z)) = (self.min.get(), self.max.get());
+        let mut chunk_count = 0;
+        for x in min_x.min(max_x)..=min_x.max(max_x) {
+            for y in min_y.min(max_y)..=min_y.max(max_y) {
+                for z in min_z.min(max_z)..=min_z.max(max_z) {
+                    let chunk_position = ChunkPosition::new(x, y, z);
+                    let chunk = Chunk::generate(chunk_position, self.seed);
+                    std::fs::write(
+                        Chunk::file_path(&self.directory, chunk_position),
+                        chunk.to_bytes(),
+                    )?;
+                    chunk_count += 1;
+                }
+            }
+        }
+
+        info!(
+            "Exported {chunk_count} chunks with seed {} to {}",
+            self.seed,
+            self.directory.display()
+        );
+        Ok(chunk_count)
+    }
+}
@@ -1,9 +1,20 @@
-use crate::game::voxel::VoxelType;
-use crate::game::world::{ChunkPosition, LocalChunkPosition};
+mod storage;
+
+use crate::game::voxel::{VoxelOrientation, VoxelRegistry, VoxelType};
+use crate::game::world::{ChunkPosition, LocalChunkPosition, hash_to_rng};
 use log::warn;
-use std::ops::RangeInclusive;
 
 pub(crate) const CHUNK_SIZE: usize = 32;
+/// Voxels filled per column of the ground chunks. The terrain is flat, so anything that needs its
+/// height without chunk data, such as the horizon skirt, can use this instead of a heightmap.
+pub(crate) const TERRAIN_HEIGHT: usize = CHUNK_SIZE;
+const MIN_DIRT_DEPTH: usize = 3;
+
+// Stored voxels are laid out as `rooo tttt tttt tttt`: the low 12 bits hold the voxel type id,
+// the next 3 bits hold the orientation, and the top bit is reserved.
+const VOXEL_TYPE_MASK: u16 = 0x0FFF;
+const VOXEL_ORIENTATION_SHIFT: u16 = 12;
+const VOXEL_ORIENTATION_MASK: u16 = 0b111;
 
 #[derive(Clone)]
 pub(crate) struct Chunk {
@@ -19,67 +30,224 @@ impl Chunk {
         }
     }
 
-    pub(crate) fn dev_chunk(chunk_position: ChunkPosition) -> Self {
+    /// A chunk holding `voxel_types` in their default orientation, in the same x-fastest,
+    /// then y, then z order as `voxels`.
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) fn from_voxels(
+        position: ChunkPosition,
+        voxel_types: Vec<VoxelType>,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            voxel_types.len() == CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE,
+            "Chunk {position:?} needs {} voxels, got {}",
+            CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE,
+            voxel_types.len()
+        );
+        Ok(Self {
+            position,
+            voxels: voxel_types
+                .into_iter()
+                .map(|voxel_type| Self::pack_voxel(voxel_type, VoxelOrientation::default()))
+                .collect(),
+        })
+    }
+
+    /// The dev terrain is flat, with a dirt layer whose depth varies per column with the seed.
+    pub(crate) fn generate(chunk_position: ChunkPosition, seed: u64) -> Self {
         let (_, chunk_y, _) = chunk_position.get();
         let mut chunk = Self::empty(chunk_position);
 
         if chunk_y != 0 {
             return chunk;
-        } else {
-            chunk.set_y_slice(31, VoxelType::Grass);
-            chunk.set_y_range(27..=30, VoxelType::Dirt);
-            chunk.set_y_range(0..=26, VoxelType::Stone);
+        }
+
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let local_position = LocalChunkPosition::new(x, 0, z);
+                let (world_x, _, world_z) = local_position.world_position(chunk_position).get();
+                let dirt_depth =
+                    MIN_DIRT_DEPTH + hash_to_rng(seed, world_x, 0, world_z) as usize % 3;
+                let surface_height = Self::generated_surface_height(world_x, world_z) as usize;
+                let dirt_start = surface_height - dirt_depth;
+                for y in 0..=surface_height {
+                    let voxel_type = match y {
+                        _ if y == surface_height => VoxelType::Grass,
+                        _ if y >= dirt_start => VoxelType::Dirt,
+                        _ => VoxelType::Stone,
+                    };
+                    chunk.set_voxel(LocalChunkPosition::new(x, y, z), voxel_type);
+                }
+            }
         }
         chunk
     }
 
+    /// The world y of the grass voxel that `generate` places on top of the column at `world_x`,
+    /// `world_z`, for anything that needs the ground height without loading the chunk.
+    pub(crate) fn generated_surface_height(_world_x: i32, _world_z: i32) -> i32 {
+        TERRAIN_HEIGHT as i32 - 1
+    }
+
     pub(crate) fn position(&self) -> ChunkPosition {
         self.position
     }
 
+    pub(crate) fn voxels(&self) -> &[u16] {
+        &self.voxels
+    }
+
+    /// Heap bytes held by the voxel storage.
+    pub(crate) fn voxel_bytes(&self) -> usize {
+        self.voxels.capacity() * size_of::<u16>()
+    }
+
+    pub(crate) fn is_fully_occluding(&self, voxel_registry: &VoxelRegistry) -> bool {
+        self.voxels.iter().all(|voxel| {
+            let voxel_type = VoxelType::try_from(voxel & VOXEL_TYPE_MASK)
+                .expect("Chunks should not store invalid voxel types");
+            voxel_registry.get_properties(&voxel_type).is_occluding()
+        })
+    }
+
+    /// Every non-air voxel in storage order. Air is recognized from the packed id alone, so
+    /// mostly empty chunks cost a scan of the raw array and nothing more.
+    pub(crate) fn iter_solid(&self) -> impl Iterator<Item = (LocalChunkPosition, VoxelType)> {
+        let air = u16::from(VoxelType::Air);
+        self.voxels
+            .iter()
+            .enumerate()
+            .filter(move |(_, voxel)| *voxel & VOXEL_TYPE_MASK != air)
+            .map(|(index, voxel)| {
+                let local_position = LocalChunkPosition::new(
+                    index % CHUNK_SIZE,
+                    index / CHUNK_SIZE % CHUNK_SIZE,
+                    index / (CHUNK_SIZE * CHUNK_SIZE),
+                );
+                let voxel_type = VoxelType::try_from(voxel & VOXEL_TYPE_MASK)
+                    .expect("Chunks should not store invalid voxel types");
+                (local_position, voxel_type)
+            })
+    }
+
     fn index(x: usize, y: usize, z: usize) -> usize {
         x + y * CHUNK_SIZE + z * CHUNK_SIZE * CHUNK_SIZE
     }
 
+    fn pack_voxel(voxel_type: VoxelType, orientation: VoxelOrientation) -> u16 {
+        let voxel_type_id: u16 = voxel_type.into();
+        let orientation_id: u16 = orientation.into();
+        (voxel_type_id & VOXEL_TYPE_MASK)
+            | ((orientation_id & VOXEL_ORIENTATION_MASK) << VOXEL_ORIENTATION_SHIFT)
+    }
+
+    pub(crate) fn set_voxel(&mut self, local_position: LocalChunkPosition, voxel_type: VoxelType) {
+        self.set_oriented_voxel(local_position, voxel_type, VoxelOrientation::default());
+    }
+
     #[expect(clippy::indexing_slicing)]
-    fn set_voxel(&mut self, local_position: LocalChunkPosition, voxel_type: VoxelType) {
+    pub(crate) fn set_oriented_voxel(
+        &mut self,
+        local_position: LocalChunkPosition,
+        voxel_type: VoxelType,
+        orientation: VoxelOrientation,
+    ) {
         let (x, y, z) = local_position.get();
         if x >= CHUNK_SIZE || y >= CHUNK_SIZE || z >= CHUNK_SIZE {
             warn!("Attempted to set voxel outside chunk bounds: ({x}, {y}, {z})");
             return;
         }
 
-        self.voxels[Self::index(x, y, z)] = voxel_type.into();
+        self.voxels[Self::index(x, y, z)] = Self::pack_voxel(voxel_type, orientation);
     }
 
-    fn set_y_slice(&mut self, y: usize, voxel_type: VoxelType) {
-        if y >= CHUNK_SIZE {
-            warn!("Attempted to set y-slice higher than chunk height: ({y})");
-            return;
-        }
-
-        for x in 0..CHUNK_SIZE {
-            for z in 0..CHUNK_SIZE {
-                let local_position = LocalChunkPosition::new(x, y, z);
-                self.set_voxel(local_position, voxel_type);
-            }
+    #[expect(clippy::indexing_slicing)]
+    pub(crate) fn get_voxel_type(&self, local_position: LocalChunkPosition) -> VoxelType {
+        let (x, y, z) = local_position.get();
+        if x >= CHUNK_SIZE || y >= CHUNK_SIZE || z >= CHUNK_SIZE {
+            return VoxelType::Air;
         }
-    }
 
-    fn set_y_range(&mut self, y_range: RangeInclusive<usize>, voxel_type: VoxelType) {
-        for y in y_range {
-            self.set_y_slice(y, voxel_type);
-        }
+        VoxelType::try_from(self.voxels[Self::index(x, y, z)] & VOXEL_TYPE_MASK)
+            .expect("Chunks should not store invalid voxel types")
     }
 
     #[expect(clippy::indexing_slicing)]
-    pub(crate) fn get_voxel_type(&self, local_position: LocalChunkPosition) -> VoxelType {
+    pub(crate) fn get_voxel_orientation(
+        &self,
+        local_position: LocalChunkPosition,
+    ) -> VoxelOrientation {
         let (x, y, z) = local_position.get();
         if x >= CHUNK_SIZE || y >= CHUNK_SIZE || z >= CHUNK_SIZE {
-            return VoxelType::Air;
+            return VoxelOrientation::default();
         }
 
-        VoxelType::try_from(self.voxels[Self::index(x, y, z)])
-            .expect("Chunks should not store invalid voxel types")
+        let voxel = self.voxels[Self::index(x, y, z)];
+        VoxelOrientation::try_from((voxel >> VOXEL_ORIENTATION_SHIFT) & VOXEL_ORIENTATION_MASK)
+            .expect("Chunks should not store invalid voxel orientations")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_voxels_places_each_voxel_and_rejects_the_wrong_count() {
+        let chunk_position = ChunkPosition::new(1, -1, 2);
+        let mut voxel_types = vec![VoxelType::Air; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE];
+        let middle = CHUNK_SIZE / 2;
+        if let Some(voxel_type) = voxel_types.get_mut(Chunk::index(middle, middle, middle)) {
+            *voxel_type = VoxelType::Stone;
+        }
+        let chunk = Chunk::from_voxels(chunk_position, voxel_types.clone())
+            .expect("A full chunk of voxels should be accepted");
+        assert_eq!(
+            chunk.get_voxel_type(LocalChunkPosition::new(middle, middle, middle)),
+            VoxelType::Stone,
+            "The voxel in the middle should be the one that was given"
+        );
+        assert_eq!(
+            chunk
+                .voxels()
+                .iter()
+                .filter(|voxel| **voxel != u16::from(VoxelType::Air))
+                .count(),
+            1,
+            "Every other voxel should be air"
+        );
+
+        voxel_types.pop();
+        assert!(
+            Chunk::from_voxels(chunk_position, voxel_types).is_err(),
+            "A chunk missing a voxel should be rejected"
+        );
+    }
+
+    #[test]
+    fn generated_surface_height_is_the_top_of_each_generated_column() {
+        for chunk_position in [
+            ChunkPosition::new(0, 0, 0),
+            ChunkPosition::new(-3, 0, 5),
+            ChunkPosition::new(7, 0, -2),
+        ] {
+            let chunk = Chunk::generate(chunk_position, 42);
+            for (x, z) in [(0, 0), (5, 17), (CHUNK_SIZE - 1, CHUNK_SIZE - 1)] {
+                let (world_x, _, world_z) = LocalChunkPosition::new(x, 0, z)
+                    .world_position(chunk_position)
+                    .get();
+                let topmost = (0..CHUNK_SIZE)
+                    .rev()
+                    .find(|&y| {
+                        chunk.get_voxel_type(LocalChunkPosition::new(x, y, z)) != VoxelType::Air
+                    })
+                    .map(|y| y as i32);
+                assert_eq!(
+                    topmost,
+                    Some(Chunk::generated_surface_height(world_x, world_z)),
+                    "The generated surface height at ({world_x}, {world_z}) should match the \
+                    topmost voxel of the generated chunk"
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,127 @@
+use crate::game::chunk::{
+    CHUNK_SIZE, Chunk, VOXEL_ORIENTATION_MASK, VOXEL_ORIENTATION_SHIFT, VOXEL_TYPE_MASK,
+};
+use crate::game::voxel::{VoxelOrientation, VoxelType};
+use crate::game::world::ChunkPosition;
+use std::path::{Path, PathBuf};
+
+const CHUNK_FILE_MAGIC: [u8; 4] = *b"CCHK";
+const CHUNK_FILE_VERSION: u32 = 1;
+const CHUNK_FILE_HEADER_SIZE: usize = 4 + 4 + 3 * 4;
+const CHUNK_VOXEL_COUNT: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+impl Chunk {
+    pub(crate) fn file_path(directory: &Path, chunk_position: ChunkPosition) -> PathBuf {
+        let (x, y, z) = chunk_position.get();
+        directory.join(format!("{x}_{y}_{z}.chunk"))
+    }
+
+    /// Layout: magic, version, chunk position, then the packed voxels, all little-endian.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(CHUNK_FILE_HEADER_SIZE + CHUNK_VOXEL_COUNT * 2);
+        bytes.extend_from_slice(&CHUNK_FILE_MAGIC);
+        bytes.extend_from_slice(&CHUNK_FILE_VERSION.to_le_bytes());
+        for coordinate in <[i32; 3]>::from(self.position.get()) {
+            bytes.extend_from_slice(&coordinate.to_le_bytes());
+        }
+        for voxel in &self.voxels {
+            bytes.extend_from_slice(&voxel.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Reads a chunk written by `to_bytes`, or `None` when the bytes are from another version,
+    /// truncated, or hold an unknown voxel type or orientation.
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (header, data) = bytes.split_at_checked(CHUNK_FILE_HEADER_SIZE)?;
+        let (magic, header) = header.split_first_chunk::<4>()?;
+        let (version, header) = header.split_first_chunk::<4>()?;
+        let (x, header) = header.split_first_chunk::<4>()?;
+        let (y, header) = header.split_first_chunk::<4>()?;
+        let (z, _) = header.split_first_chunk::<4>()?;
+
+        if *magic != CHUNK_FILE_MAGIC
+            || u32::from_le_bytes(*version) != CHUNK_FILE_VERSION
+            || data.len() != CHUNK_VOXEL_COUNT * 2
+        {
+            return None;
+        }
+
+        let voxels = data
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|voxel| u16::from_le_bytes(*voxel))
+            .collect::<Vec<u16>>();
+        let is_valid = voxels.iter().all(|voxel| {
+            VoxelType::try_from(voxel & VOXEL_TYPE_MASK).is_ok()
+                && VoxelOrientation::try_from(
+                    (voxel >> VOXEL_ORIENTATION_SHIFT) & VOXEL_ORIENTATION_MASK,
+                )
+                .is_ok()
+        });
+        if !is_valid {
+            return None;
+        }
+
+        Some(Self {
+            position: ChunkPosition::new(
+                i32::from_le_bytes(*x),
+                i32::from_le_bytes(*y),
+                i32::from_le_bytes(*z),
+            ),
+            voxels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exported_chunks_read_back_unchanged() {
+        let chunk_position = ChunkPosition::new(3, -1, -7);
+        let chunk = Chunk::generate(chunk_position, 42);
+
+        let read_chunk = Chunk::from_bytes(&chunk.to_bytes()).expect("The chunk should read back");
+        assert_eq!(
+            read_chunk.position(),
+            chunk_position,
+            "The position should survive the round trip"
+        );
+        assert_eq!(
+            read_chunk.voxels(),
+            chunk.voxels(),
+            "The voxels should survive the round trip"
+        );
+    }
+
+    #[test]
+    fn damaged_chunk_files_are_rejected() {
+        let bytes = Chunk::generate(ChunkPosition::new(0, 0, 0), 42).to_bytes();
+        assert!(
+            Chunk::from_bytes(bytes.split_last().expect("Bytes should not be empty").1).is_none(),
+            "A truncated file should be rejected"
+        );
+
+        let mut wrong_magic = bytes.clone();
+        if let Some(byte) = wrong_magic.first_mut() {
+            *byte = b'X';
+        }
+        assert!(
+            Chunk::from_bytes(&wrong_magic).is_none(),
+            "A file with the wrong magic should be rejected"
+        );
+
+        let mut unknown_voxel = bytes;
+        if let Some(byte) = unknown_voxel.last_mut() {
+            *byte = u8::MAX;
+        }
+        assert!(
+            Chunk::from_bytes(&unknown_voxel).is_none(),
+            "A file with an unknown voxel type should be rejected"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crate::game::mesh::{LayeredMesh, Mesh, RenderLayer, Vertex};
+use log::warn;
+use std::path::PathBuf;
+
+const MESH_CACHE_MAGIC: [u8; 4] = *b"CMSH";
+const MESH_CACHE_VERSION: u32 = 10;
+const CACHED_LAYERS: [RenderLayer; 3] = [
+    RenderLayer::Opaque,
+    RenderLayer::Cutout,
+    RenderLayer::Transparent,
+];
+const MESH_CACHE_HEADER_SIZE: usize = 4 + 4 + 8 + CACHED_LAYERS.len() * (4 + 4);
+
+#[derive(Clone)]
+pub(crate) struct MeshCache {
+    directory: PathBuf,
+}
+
+impl MeshCache {
+    pub(crate) fn new(directory: PathBuf) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    fn path(&self, content_hash: u64) -> PathBuf {
+        self.directory.join(format!("{content_hash:016x}.mesh"))
+    }
+
+    pub(crate) fn load(&self, content_hash: u64) -> Option<LayeredMesh> {
+        let bytes = std::fs::read(self.path(content_hash)).ok()?;
+        let mesh = LayeredMesh::from_cache_bytes(&bytes, content_hash);
+        if mesh.is_none() {
+            warn!("Ignoring stale or corrupt mesh cache entry: {content_hash:016x}");
+        }
+        mesh
+    }
+
+    pub(crate) fn store(&self, content_hash: u64, mesh: &LayeredMesh) {
+        if let Err(error) =
+            std::fs::write(self.path(content_hash), mesh.to_cache_bytes(content_hash))
+        {
+            warn!("Failed to write mesh cache entry {content_hash:016x}: {error}");
+        }
+    }
+}
+
+impl LayeredMesh {
+    /// Layout: magic, version, content hash, then the vertex and index count of each
+    /// layer in `RenderLayer` order, followed by each layer's raw vertex and index data,
+    /// all little-endian.
+    fn to_cache_bytes(&self, content_hash: u64) -> Vec<u8> {
+        let layers = CACHED_LAYERS.map(|render_layer| self.layer(render_layer));
+        let data_size = layers
+            .iter()
+            .map(|mesh| mesh.vertices_u8().len() + mesh.indices_u8().len())
+            .sum::<usize>();
+        let mut bytes = Vec::with_capacity(MESH_CACHE_HEADER_SIZE + data_size);
+        bytes.extend_from_slice(&MESH_CACHE_MAGIC);
+        bytes.extend_from_slice(&MESH_CACHE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&content_hash.to_le_bytes());
+        for mesh in layers {
+            bytes.extend_from_slice(&(mesh.vertices.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&(mesh.indices.len() as u32).to_le_bytes());
+        }
+        for mesh in layers {
+            bytes.extend_from_slice(mesh.vertices_u8());
+            bytes.extend_from_slice(mesh.indices_u8());
+        }
+        bytes
+    }
+
+    fn from_cache_bytes(bytes: &[u8], content_hash: u64) -> Option<Self> {
+        let (header, mut data) = bytes.split_at_checked(MESH_CACHE_HEADER_SIZE)?;
+        let (magic, header) = header.split_first_chunk::<4>()?;
+        let (version, header) = header.split_first_chunk::<4>()?;
+        let (hash, mut header) = header.split_first_chunk::<8>()?;
+
+        if *magic != MESH_CACHE_MAGIC
+            || u32::from_le_bytes(*version) != MESH_CACHE_VERSION
+            || u64::from_le_bytes(*hash) != content_hash
+        {
+            return None;
+        }
+
+        let mut layers = Vec::with_capacity(CACHED_LAYERS.len());
+        for _ in CACHED_LAYERS {
+            let (vertex_count, rest) = header.split_first_chunk::<4>()?;
+            let (index_count, rest) = rest.split_first_chunk::<4>()?;
+            header = rest;
+
+            let vertex_bytes = u32::from_le_bytes(*vertex_count) as usize * size_of::<Vertex>();
+            let index_bytes = u32::from_le_bytes(*index_count) as usize * size_of::<u32>();
+            let (vertex_data, rest) = data.split_at_checked(vertex_bytes)?;
+            let (index_data, rest) = rest.split_at_checked(index_bytes)?;
+            data = rest;
+
+            layers.push(Mesh::new(
+                bytemuck::pod_collect_to_vec(vertex_data),
+                bytemuck::pod_collect_to_vec(index_data),
+            ));
+        }
+        if !data.is_empty() {
+            return None;
+        }
+
+        let [opaque, cutout, transparent] = <[Mesh; 3]>::try_from(layers).ok()?;
+        Some(Self::new(opaque, cutout, transparent))
+    }
+}
@@ -0,0 +1,61 @@
+use crate::game::world::Aabb;
+use std::ops::Range;
+
+/// The indices or instances that one chunk occupies in a merged buffer, with its bounds, so
+/// drawing can skip the chunks that culling rejects.
+#[derive(Clone, Debug)]
+pub(crate) struct DrawRange {
+    range: Range<u32>,
+    aabb: Aabb,
+}
+
+impl DrawRange {
+    pub(crate) fn new(range: Range<u32>, aabb: Aabb) -> Self {
+        Self { range, aabb }
+    }
+}
+
+/// The ranges whose bounds pass `is_visible`, with neighbors joined so that a run of visible
+/// chunks still costs a single draw call.
+pub(crate) fn visible_ranges(
+    draw_ranges: &[DrawRange],
+    is_visible: impl Fn(&Aabb) -> bool,
+) -> Vec<Range<u32>> {
+    let mut visible_ranges: Vec<Range<u32>> = Vec::new();
+    for draw_range in draw_ranges
+        .iter()
+        .filter(|draw_range| is_visible(&draw_range.aabb))
+    {
+        match visible_ranges.last_mut() {
+            Some(last) if last.end == draw_range.range.start => last.end = draw_range.range.end,
+            _ => visible_ranges.push(draw_range.range.clone()),
+        }
+    }
+    visible_ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    #[test]
+    fn visible_neighbors_are_joined_and_hidden_ranges_split_them() {
+        let aabb = |x: f32| Aabb::new(Vec3::new(x, 0.0, 0.0), Vec3::new(x + 1.0, 1.0, 1.0));
+        let draw_ranges = [
+            DrawRange::new(0..6, aabb(0.0)),
+            DrawRange::new(6..18, aabb(1.0)),
+            DrawRange::new(18..24, aabb(-5.0)),
+            DrawRange::new(24..30, aabb(2.0)),
+        ];
+        assert_eq!(
+            visible_ranges(&draw_ranges, |aabb| aabb.min().x >= 0.0),
+            vec![0..18, 24..30],
+            "Adjacent visible ranges should merge around the hidden one"
+        );
+        assert!(
+            visible_ranges(&draw_ranges, |_| false).is_empty(),
+            "Nothing should be drawn when every range is hidden"
+        );
+    }
+}
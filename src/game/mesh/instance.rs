@@ -0,0 +1,143 @@
+use crate::game::mesh::{
+    DrawRange, RenderLayer, corner_ambient_occlusion, face_light, texture_variant_seed,
+};
+use crate::game::voxel::VoxelFace;
+use crate::game::world::{Aabb, ChunkPosition, World};
+use glam::Vec3;
+use rayon::iter::{IntoParallelIterator as _, ParallelIterator as _};
+
+const FACES: [VoxelFace; 6] = [
+    VoxelFace::Front,
+    VoxelFace::Back,
+    VoxelFace::Right,
+    VoxelFace::Left,
+    VoxelFace::Top,
+    VoxelFace::Bottom,
+];
+
+/// One exposed voxel face, expanded into a quad by the instanced vertex shader. A face costs
+/// 72 bytes this way against 328 bytes of vertices and indices in an expanded mesh.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct FaceInstance {
+    position: [f32; 3],
+    /// Index into the shader's corner table, in `VoxelFace` declaration order.
+    face: u32,
+    texture_coordinates: [f32; 4],
+    emissive_texture_coordinates: [f32; 4],
+    /// 1 to draw the emissive overlay, 0 for a face without one.
+    emissive_strength: f32,
+    /// One value per corner, in the order of the shader's corner table.
+    ambient_occlusion: [f32; 4],
+    light: f32,
+}
+
+/// Exposed faces of every opaque voxel in the loaded chunks that are not fading in or tinted as
+/// about to unload, including their emissive overlays. Opaque voxels never have surface waves,
+/// and the expanded mesher keeps the other chunks, since their vertices carry the fade and tint.
+#[derive(Default)]
+pub(crate) struct FaceInstances {
+    instances: Vec<FaceInstance>,
+    /// Where each chunk's faces lie among the instances.
+    draw_ranges: Vec<DrawRange>,
+}
+
+impl FaceInstances {
+    pub(crate) fn world(world: &World, chunk_draw_limit: Option<usize>) -> Self {
+        let chunk_positions = world
+            .nearest_chunk_positions(chunk_draw_limit)
+            .into_iter()
+            .filter(|&chunk_position| {
+                world.chunk_fade_start(chunk_position).is_none()
+                    && !world.is_chunk_about_to_unload(chunk_position)
+                    && !world.is_chunk_buried(chunk_position)
+            })
+            .collect::<Vec<ChunkPosition>>();
+
+        let chunk_instances = chunk_positions
+            .into_par_iter()
+            .map(|chunk_position| (chunk_position, Self::chunk(world, chunk_position)))
+            .collect::<Vec<(ChunkPosition, Vec<FaceInstance>)>>();
+
+        let mut instances = Vec::new();
+        let mut draw_ranges = Vec::with_capacity(chunk_instances.len());
+        for (chunk_position, chunk_instances) in chunk_instances {
+            let start = instances.len() as u32;
+            instances.extend(chunk_instances);
+            draw_ranges.push(DrawRange::new(
+                start..instances.len() as u32,
+                Aabb::from_chunk(chunk_position),
+            ));
+        }
+        Self {
+            instances,
+            draw_ranges,
+        }
+    }
+
+    fn chunk(world: &World, chunk_position: ChunkPosition) -> Vec<FaceInstance> {
+        let Some(chunk) = world.chunk_data().get(&chunk_position) else {
+            return Vec::new();
+        };
+        let voxel_registry = world.voxel_registry();
+        let texture_atlas = world.texture_atlas();
+        let mut instances = Vec::new();
+
+        for (local_position, voxel_type) in chunk.iter_solid() {
+            let voxel_properties = voxel_registry.get_properties(&voxel_type);
+            if voxel_properties.is_invisible()
+                || voxel_properties.render_layer() != RenderLayer::Opaque
+            {
+                continue;
+            }
+            let world_position = local_position.clone().world_position(chunk_position);
+            let textures = world
+                .texture_override(chunk_position, local_position)
+                .unwrap_or_else(|| voxel_properties.textures());
+            let orientation = chunk.get_voxel_orientation(local_position);
+            let occluding_neighbors = world.get_occluding_neighbors(world_position);
+
+            for (face_index, face) in FACES.into_iter().enumerate() {
+                if occluding_neighbors.is_occluded(face) {
+                    continue;
+                }
+                let local_face = orientation.local_face(face);
+                let texture_coordinates = texture_atlas
+                    .get_variant_coordinates(
+                        textures.texture(local_face),
+                        texture_variant_seed(world_position),
+                    )
+                    .get();
+                let emissive = voxel_properties
+                    .emissive_texture(local_face)
+                    .map(|emissive_texture| texture_atlas.get_coordinates(emissive_texture).get());
+                let normal = Vec3::from(face.normal()).as_ivec3();
+                let ambient_occlusion = face.corners().map(|corner| {
+                    corner_ambient_occlusion(world, world_position, normal, Vec3::from(corner))
+                });
+                instances.push(FaceInstance {
+                    position: world_position.get_f32().into(),
+                    face: face_index as u32,
+                    texture_coordinates: texture_coordinates.into(),
+                    emissive_texture_coordinates: emissive.unwrap_or_default().into(),
+                    emissive_strength: if emissive.is_some() { 1.0 } else { 0.0 },
+                    ambient_occlusion,
+                    light: face_light(world, world_position, normal),
+                });
+            }
+        }
+        instances
+    }
+
+    pub(crate) fn instance_count(&self) -> u32 {
+        self.instances.len() as u32
+    }
+
+    pub(crate) fn draw_ranges(&self) -> &[DrawRange] {
+        &self.draw_ranges
+    }
+
+    pub(crate) fn instances_u8(&self) -> &[u8] {
+        bytemuck::cast_slice(self.instances.as_slice())
+    }
+}
@@ -0,0 +1,94 @@
+use crate::game::mesh::Mesh;
+use glam::Vec3;
+
+/// Which pipeline a voxel's faces are drawn with.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum RenderLayer {
+    Opaque,
+    /// Drawn after opaque geometry; fragments below the alpha cutoff are discarded.
+    Cutout,
+    /// Drawn last and blended over everything else without writing depth.
+    Transparent,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct LayeredMesh {
+    opaque: Mesh,
+    cutout: Mesh,
+    transparent: Mesh,
+}
+
+impl LayeredMesh {
+    pub(crate) fn new(opaque: Mesh, cutout: Mesh, transparent: Mesh) -> Self {
+        Self {
+            opaque,
+            cutout,
+            transparent,
+        }
+    }
+
+    pub(crate) fn merged(meshes: Vec<Self>) -> Self {
+        let mut opaque = Vec::with_capacity(meshes.len());
+        let mut cutout = Vec::with_capacity(meshes.len());
+        let mut transparent = Vec::with_capacity(meshes.len());
+        for mesh in meshes {
+            opaque.push(mesh.opaque);
+            cutout.push(mesh.cutout);
+            transparent.push(mesh.transparent);
+        }
+        Self::new(
+            Mesh::merged(opaque),
+            Mesh::merged(cutout),
+            Mesh::merged(transparent),
+        )
+    }
+
+    /// Moves every layer into the transparent one, whose blended pass fades it in from fully
+    /// transparent. Until the fade ends, cutout faces are only drawn from the front since that
+    /// pass culls back faces.
+    pub(crate) fn fading(self, fade_start: f32) -> Self {
+        let mut transparent = Mesh::merged(vec![self.opaque, self.cutout, self.transparent]);
+        transparent.set_fade_start(fade_start);
+        Self::new(Mesh::default(), Mesh::default(), transparent)
+    }
+
+    /// A copy of the cutout and transparent layers alone.
+    pub(crate) fn without_opaque(&self) -> Self {
+        Self::new(
+            Mesh::default(),
+            self.cutout.clone(),
+            self.transparent.clone(),
+        )
+    }
+
+    pub(crate) fn tinted(mut self, tint: [f32; 3]) -> Self {
+        for mesh in [&mut self.opaque, &mut self.cutout, &mut self.transparent] {
+            mesh.tint(tint);
+        }
+        self
+    }
+
+    pub(crate) fn layer(&self, render_layer: RenderLayer) -> &Mesh {
+        match render_layer {
+            RenderLayer::Opaque => &self.opaque,
+            RenderLayer::Cutout => &self.cutout,
+            RenderLayer::Transparent => &self.transparent,
+        }
+    }
+
+    pub(crate) fn sort_transparent_faces(&mut self, eye: Vec3) {
+        self.transparent.sort_faces_by_distance(eye);
+    }
+
+    /// Bytes of vertex and index data across all layers, as uploaded to the GPU.
+    pub(crate) fn byte_len(&self) -> usize {
+        [&self.opaque, &self.cutout, &self.transparent]
+            .iter()
+            .map(|mesh| mesh.vertices_u8().len() + mesh.indices_u8().len())
+            .sum()
+    }
+
+    pub(crate) fn index_count(&self) -> u32 {
+        self.opaque.index_count() + self.cutout.index_count() + self.transparent.index_count()
+    }
+}
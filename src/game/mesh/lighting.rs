@@ -0,0 +1,139 @@
+use crate::game::mesh::Mesh;
+use crate::game::world::{World, WorldPosition};
+use glam::{IVec3, Vec3};
+
+// Brightness of a corner with none, one, two or all three of its surrounding voxels occluding
+const AMBIENT_OCCLUSION_LEVELS: [f32; 4] = [1.0, 0.8, 0.62, 0.45];
+// Light on a face that looks out under an overhang
+const OVERHANG_LIGHT: f32 = 0.7;
+
+impl Mesh {
+    /// Shades a voxel's faces from the voxels in front of them, which all lie in the 3×3×3
+    /// block around the voxel, so a mesh stays correct as long as the chunks around it do.
+    pub(crate) fn apply_lighting(&mut self, world: &World, world_position: WorldPosition) {
+        let center = Vec3::from(world_position.get_f32());
+        for face_vertices in self.vertices.chunks_exact_mut(4) {
+            let Some(normal) = face_vertices
+                .first()
+                .map(|vertex| vertex.normal().as_ivec3())
+            else {
+                continue;
+            };
+            let light = face_light(world, world_position, normal);
+            for vertex in face_vertices {
+                let corner = vertex.position() - center;
+                vertex.set_ambient_occlusion(corner_ambient_occlusion(
+                    world,
+                    world_position,
+                    normal,
+                    corner,
+                ));
+                vertex.set_light(light);
+            }
+        }
+    }
+}
+
+/// Darkens the corner of a face, at `corner` from the voxel's center, by how many of the two
+/// voxels beside it and the one diagonal to it are occluding, in the layer the face looks out
+/// on. Two occluding sides darken it fully, since they hide the diagonal.
+pub(crate) fn corner_ambient_occlusion(
+    world: &World,
+    world_position: WorldPosition,
+    normal: IVec3,
+    corner: Vec3,
+) -> f32 {
+    let is_occluding =
+        |offset: IVec3| world.get_is_occluding(world_position.offset(offset.x, offset.y, offset.z));
+    let toward_corner = (corner * 2.0).round().as_ivec3() * (IVec3::ONE - normal.abs());
+    let mut sides = [IVec3::X, IVec3::Y, IVec3::Z]
+        .into_iter()
+        .map(|axis| axis * toward_corner)
+        .filter(|side| *side != IVec3::ZERO);
+    let (side_a, side_b) = (
+        sides.next().unwrap_or_default(),
+        sides.next().unwrap_or_default(),
+    );
+
+    let occluding_count = match (is_occluding(normal + side_a), is_occluding(normal + side_b)) {
+        (true, true) => 3,
+        (side_a, side_b) => {
+            usize::from(side_a)
+                + usize::from(side_b)
+                + usize::from(is_occluding(normal + toward_corner))
+        }
+    };
+    AMBIENT_OCCLUSION_LEVELS
+        .get(occluding_count)
+        .copied()
+        .unwrap_or(1.0)
+}
+
+/// Dims a face when the voxel above the one it looks out on is occluding, as under a ledge or
+/// a cave ceiling. Bottom faces look out below the voxel itself and keep full light.
+pub(crate) fn face_light(world: &World, world_position: WorldPosition, normal: IVec3) -> f32 {
+    let above = normal + IVec3::Y;
+    if above != IVec3::ZERO
+        && world.get_is_occluding(world_position.offset(above.x, above.y, above.z))
+    {
+        OVERHANG_LIGHT
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::chunk::TERRAIN_HEIGHT;
+    use crate::game::mesh::RenderLayer;
+    use crate::game::voxel::VoxelType;
+    use crate::game::world::ChunkPosition;
+
+    #[test]
+    fn corners_beside_a_wall_and_faces_under_a_ledge_are_darkened() {
+        let mut world = World::new_with_seed(0);
+        world.set_render_distance(1, 1);
+        world.update_chunks(ChunkPosition::new(0, 1, 0));
+        while world.has_pending_chunk_loads() {
+            world.load_pending_chunks();
+        }
+        // A voxel resting on the generated ground, with a wall to its right and a ledge two
+        // voxels above it
+        let y = TERRAIN_HEIGHT as i32;
+        world.edit_batch(|editor| {
+            editor.set_voxel(WorldPosition::new(4, y, 4), VoxelType::Stone);
+            editor.set_voxel(WorldPosition::new(5, y + 1, 4), VoxelType::Stone);
+            editor.set_voxel(WorldPosition::new(4, y + 2, 4), VoxelType::Stone);
+        });
+
+        let chunk_mesh = Mesh::build_chunk(&world, ChunkPosition::new(0, 1, 0), None);
+        let top_face = chunk_mesh
+            .layer(RenderLayer::Opaque)
+            .vertices()
+            .iter()
+            .filter(|vertex| {
+                let position = vertex.position();
+                vertex.normal() == Vec3::Y
+                    && position.y == y as f32 + 0.5
+                    && (3.5..=4.5).contains(&position.x)
+                    && (3.5..=4.5).contains(&position.z)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(top_face.len(), 4, "The voxel's top face should be meshed");
+        for vertex in top_face {
+            let expected = if vertex.position().x == 4.5 { 0.8 } else { 1.0 };
+            assert_eq!(
+                vertex.ambient_occlusion(),
+                expected,
+                "Only the corners beside the wall should be darkened, got {}",
+                vertex.position()
+            );
+            assert_eq!(
+                vertex.light(),
+                OVERHANG_LIGHT,
+                "The face under the ledge should be dimmed"
+            );
+        }
+    }
+}
@@ -1,119 +1,208 @@
+mod cache;
+mod draw_range;
+mod instance;
+mod layer;
+mod lighting;
+mod skirt;
 mod vertex;
 
+pub(crate) use cache::*;
+pub(crate) use draw_range::*;
+pub(crate) use instance::*;
+pub(crate) use layer::*;
+pub(crate) use lighting::*;
 pub(crate) use vertex::*;
 
-use crate::game::chunk::{Chunk, CHUNK_SIZE};
+use crate::game::chunk::Chunk;
 use crate::game::render::TextureAtlas;
-use crate::game::voxel::{VoxelProperties, VoxelRegistry};
-use crate::game::world::{ChunkPosition, LocalChunkPosition, World, WorldPosition};
+use crate::game::voxel::{
+    VoxelFace, VoxelOrientation, VoxelProperties, VoxelRegistry, VoxelTextures,
+};
+use crate::game::world::{Aabb, ChunkPosition, World, WorldPosition, hash_to_rng};
+use glam::Vec3;
 use rayon::iter::{IntoParallelIterator as _, ParallelIterator as _};
+use std::collections::HashSet;
 
-#[derive(Clone)]
+/// Keeps the hash that picks texture variants independent of the one terrain generation uses.
+const TEXTURE_VARIANT_SALT: u64 = 0x7E47_0AE5_5EED_0001;
+// Chunk flashes, for watching streaming: green for chunks just loaded, red for those at the
+// edge of the range that unload next
+const LOAD_FLASH_TINT: [f32; 3] = [0.4, 1.0, 0.4];
+const ABOUT_TO_UNLOAD_TINT: [f32; 3] = [1.0, 0.35, 0.35];
+
+/// Picks which of a texture's variants a voxel shows. It depends only on the position, so
+/// remeshing never reshuffles them.
+pub(crate) fn texture_variant_seed(world_position: WorldPosition) -> u32 {
+    let (x, y, z) = world_position.get();
+    hash_to_rng(TEXTURE_VARIANT_SALT, x, y, z)
+}
+
+#[derive(Clone, Default)]
 pub(crate) struct Mesh {
     vertices: Vec<Vertex>,
     indices: Vec<u32>,
+    aabb: Option<Aabb>,
+    /// Where each mesh merged into this one lies among the indices. Empty for meshes that were
+    /// built directly or whose faces were reordered, which are drawn whole.
+    draw_ranges: Vec<DrawRange>,
 }
 
 impl Mesh {
+    fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+        let aabb = Aabb::from_points(vertices.iter().map(Vertex::position));
+        Self {
+            vertices,
+            indices,
+            aabb,
+            draw_ranges: Vec::new(),
+        }
+    }
+
+    /// Indexes each consecutive run of four vertices as a quad.
+    pub(crate) fn quads(vertices: Vec<Vertex>) -> Self {
+        let indices = (0..vertices.len() as u32 / 4)
+            .flat_map(|quad| quad_indices(quad * 4))
+            .collect();
+        Self::new(vertices, indices)
+    }
+
     fn merged(meshes: Vec<Self>) -> Self {
         let mut vertices = Vec::new();
         let mut indices = Vec::new();
+        let mut aabb: Option<Aabb> = None;
+        let mut draw_ranges = Vec::with_capacity(meshes.len());
         let mut vertex_offset: u32 = 0;
 
         for mesh in meshes {
+            let index_offset = indices.len() as u32;
             vertices.extend_from_slice(mesh.vertices());
             indices.extend(mesh.indices().iter().map(|i| i + vertex_offset));
             vertex_offset += mesh.vertices().len() as u32;
+            if let Some(mesh_aabb) = mesh.aabb() {
+                draw_ranges.push(DrawRange::new(
+                    index_offset..indices.len() as u32,
+                    mesh_aabb,
+                ));
+            }
+            aabb = match (aabb, mesh.aabb()) {
+                (Some(aabb), Some(mesh_aabb)) => Some(aabb.union(&mesh_aabb)),
+                (aabb, mesh_aabb) => aabb.or(mesh_aabb),
+            };
         }
 
-        Self { vertices, indices }
+        Self {
+            vertices,
+            indices,
+            aabb,
+            draw_ranges,
+        }
     }
 
     pub(crate) fn voxel(
         world_position: WorldPosition,
         voxel_properties: &VoxelProperties,
+        textures: &VoxelTextures,
+        orientation: VoxelOrientation,
         texture_atlas: &TextureAtlas,
         occluding_neighbors: &OccludingVoxelNeighbors,
     ) -> Self {
-        let mut vertices = Vec::new();
-        let mut indices = Vec::new();
+        let mut vertices = Vec::with_capacity(6 * 4);
+        let mut indices = Vec::with_capacity(6 * 6);
 
         let (x, y, z) = world_position.get_f32();
+        let variant_seed = texture_variant_seed(world_position);
+        let base_coordinates = |face| {
+            texture_atlas.get_variant_coordinates(
+                textures.texture(orientation.local_face(face)),
+                variant_seed,
+            )
+        };
+        let apply_material = |vertices: &mut Vec<Vertex>, face| {
+            let local_face = orientation.local_face(face);
+            if let Some(emissive_texture) = voxel_properties.emissive_texture(local_face) {
+                let emissive = texture_atlas.get_coordinates(emissive_texture);
+                for vertex in vertices.iter_mut().rev().take(4) {
+                    vertex.set_emissive(base_coordinates(face), emissive);
+                }
+            }
+            Self::apply_surface_waves(vertices, voxel_properties, face, occluding_neighbors);
+        };
+        let face_coordinates = |face| base_coordinates(face).get();
 
         if !occluding_neighbors.front {
-            let (u_min, u_max, v_min, v_max) = texture_atlas
-                .get_coordinates(voxel_properties.front_texture())
-                .get();
-            vertices.extend(vec![
-                Vertex::new([x - 0.5, y - 0.5, z + 0.5], [u_min, v_max]),
-                Vertex::new([x + 0.5, y - 0.5, z + 0.5], [u_max, v_max]),
-                Vertex::new([x + 0.5, y + 0.5, z + 0.5], [u_max, v_min]),
-                Vertex::new([x - 0.5, y + 0.5, z + 0.5], [u_min, v_min]),
+            let (u_min, u_max, v_min, v_max) = face_coordinates(VoxelFace::Front);
+            let vertex = Self::face_vertex(VoxelFace::Front);
+            vertices.extend_from_slice(&[
+                vertex([x - 0.5, y - 0.5, z + 0.5], [u_min, v_max]),
+                vertex([x + 0.5, y - 0.5, z + 0.5], [u_max, v_max]),
+                vertex([x + 0.5, y + 0.5, z + 0.5], [u_max, v_min]),
+                vertex([x - 0.5, y + 0.5, z + 0.5], [u_min, v_min]),
             ]);
+            apply_material(&mut vertices, VoxelFace::Front);
             Self::extend_indices(&vertices, &mut indices);
         }
         if !occluding_neighbors.back {
-            let (u_min, u_max, v_min, v_max) = texture_atlas
-                .get_coordinates(voxel_properties.back_texture())
-                .get();
-            vertices.extend(vec![
-                Vertex::new([x - 0.5, y - 0.5, z - 0.5], [u_max, v_max]),
-                Vertex::new([x - 0.5, y + 0.5, z - 0.5], [u_max, v_min]),
-                Vertex::new([x + 0.5, y + 0.5, z - 0.5], [u_min, v_min]),
-                Vertex::new([x + 0.5, y - 0.5, z - 0.5], [u_min, v_max]),
+            let (u_min, u_max, v_min, v_max) = face_coordinates(VoxelFace::Back);
+            let vertex = Self::face_vertex(VoxelFace::Back);
+            vertices.extend_from_slice(&[
+                vertex([x - 0.5, y - 0.5, z - 0.5], [u_max, v_max]),
+                vertex([x - 0.5, y + 0.5, z - 0.5], [u_max, v_min]),
+                vertex([x + 0.5, y + 0.5, z - 0.5], [u_min, v_min]),
+                vertex([x + 0.5, y - 0.5, z - 0.5], [u_min, v_max]),
             ]);
+            apply_material(&mut vertices, VoxelFace::Back);
             Self::extend_indices(&vertices, &mut indices);
         }
         if !occluding_neighbors.right {
-            let (u_min, u_max, v_min, v_max) = texture_atlas
-                .get_coordinates(voxel_properties.right_texture())
-                .get();
-            vertices.extend(vec![
-                Vertex::new([x + 0.5, y - 0.5, z - 0.5], [u_max, v_max]),
-                Vertex::new([x + 0.5, y + 0.5, z - 0.5], [u_max, v_min]),
-                Vertex::new([x + 0.5, y + 0.5, z + 0.5], [u_min, v_min]),
-                Vertex::new([x + 0.5, y - 0.5, z + 0.5], [u_min, v_max]),
+            let (u_min, u_max, v_min, v_max) = face_coordinates(VoxelFace::Right);
+            let vertex = Self::face_vertex(VoxelFace::Right);
+            vertices.extend_from_slice(&[
+                vertex([x + 0.5, y - 0.5, z - 0.5], [u_max, v_max]),
+                vertex([x + 0.5, y + 0.5, z - 0.5], [u_max, v_min]),
+                vertex([x + 0.5, y + 0.5, z + 0.5], [u_min, v_min]),
+                vertex([x + 0.5, y - 0.5, z + 0.5], [u_min, v_max]),
             ]);
+            apply_material(&mut vertices, VoxelFace::Right);
             Self::extend_indices(&vertices, &mut indices);
         }
         if !occluding_neighbors.left {
-            let (u_min, u_max, v_min, v_max) = texture_atlas
-                .get_coordinates(voxel_properties.left_texture())
-                .get();
-            vertices.extend(vec![
-                Vertex::new([x - 0.5, y - 0.5, z - 0.5], [u_min, v_max]),
-                Vertex::new([x - 0.5, y - 0.5, z + 0.5], [u_max, v_max]),
-                Vertex::new([x - 0.5, y + 0.5, z + 0.5], [u_max, v_min]),
-                Vertex::new([x - 0.5, y + 0.5, z - 0.5], [u_min, v_min]),
+            let (u_min, u_max, v_min, v_max) = face_coordinates(VoxelFace::Left);
+            let vertex = Self::face_vertex(VoxelFace::Left);
+            vertices.extend_from_slice(&[
+                vertex([x - 0.5, y - 0.5, z - 0.5], [u_min, v_max]),
+                vertex([x - 0.5, y - 0.5, z + 0.5], [u_max, v_max]),
+                vertex([x - 0.5, y + 0.5, z + 0.5], [u_max, v_min]),
+                vertex([x - 0.5, y + 0.5, z - 0.5], [u_min, v_min]),
             ]);
+            apply_material(&mut vertices, VoxelFace::Left);
             Self::extend_indices(&vertices, &mut indices);
         }
         if !occluding_neighbors.top {
-            let (u_min, u_max, v_min, v_max) = texture_atlas
-                .get_coordinates(voxel_properties.top_texture())
-                .get();
-            vertices.extend(vec![
-                Vertex::new([x - 0.5, y + 0.5, z - 0.5], [u_min, v_min]),
-                Vertex::new([x - 0.5, y + 0.5, z + 0.5], [u_min, v_max]),
-                Vertex::new([x + 0.5, y + 0.5, z + 0.5], [u_max, v_max]),
-                Vertex::new([x + 0.5, y + 0.5, z - 0.5], [u_max, v_min]),
+            let (u_min, u_max, v_min, v_max) = face_coordinates(VoxelFace::Top);
+            let vertex = Self::face_vertex(VoxelFace::Top);
+            vertices.extend_from_slice(&[
+                vertex([x - 0.5, y + 0.5, z - 0.5], [u_min, v_min]),
+                vertex([x - 0.5, y + 0.5, z + 0.5], [u_min, v_max]),
+                vertex([x + 0.5, y + 0.5, z + 0.5], [u_max, v_max]),
+                vertex([x + 0.5, y + 0.5, z - 0.5], [u_max, v_min]),
             ]);
+            apply_material(&mut vertices, VoxelFace::Top);
             Self::extend_indices(&vertices, &mut indices);
         }
         if !occluding_neighbors.bottom {
-            let (u_min, u_max, v_min, v_max) = texture_atlas
-                .get_coordinates(voxel_properties.bottom_texture())
-                .get();
-            vertices.extend(vec![
-                Vertex::new([x - 0.5, y - 0.5, z - 0.5], [u_min, v_max]),
-                Vertex::new([x + 0.5, y - 0.5, z - 0.5], [u_max, v_max]),
-                Vertex::new([x + 0.5, y - 0.5, z + 0.5], [u_max, v_min]),
-                Vertex::new([x - 0.5, y - 0.5, z + 0.5], [u_min, v_min]),
+            let (u_min, u_max, v_min, v_max) = face_coordinates(VoxelFace::Bottom);
+            let vertex = Self::face_vertex(VoxelFace::Bottom);
+            vertices.extend_from_slice(&[
+                vertex([x - 0.5, y - 0.5, z - 0.5], [u_min, v_max]),
+                vertex([x + 0.5, y - 0.5, z - 0.5], [u_max, v_max]),
+                vertex([x + 0.5, y - 0.5, z + 0.5], [u_max, v_min]),
+                vertex([x - 0.5, y - 0.5, z + 0.5], [u_min, v_min]),
             ]);
+            apply_material(&mut vertices, VoxelFace::Bottom);
             Self::extend_indices(&vertices, &mut indices);
         }
 
-        Self { vertices, indices }
+        Self::new(vertices, indices)
     }
 
     pub(crate) fn chunk(
@@ -121,77 +210,187 @@ impl Mesh {
         chunk: &Chunk,
         voxel_registry: &VoxelRegistry,
         texture_atlas: &TextureAtlas,
-    ) -> Self {
-        let mut voxel_meshes = Vec::new();
-
-        for x in 0..CHUNK_SIZE {
-            for y in 0..CHUNK_SIZE {
-                for z in 0..CHUNK_SIZE {
-                    let local_position = LocalChunkPosition::new(x, y, z);
-                    let world_position = local_position.clone().world_position(chunk.position());
-                    let voxel_type = chunk.get_voxel_type(local_position);
-                    let voxel_properties = voxel_registry.get_properties(&voxel_type);
-                    if voxel_properties.is_invisible() {
-                        continue;
-                    }
-                    let occluding_neighbors = world.get_occluding_neighbors(world_position);
-                    voxel_meshes.push(Self::voxel(
-                        world_position,
-                        voxel_properties,
-                        texture_atlas,
-                        &occluding_neighbors,
-                    ));
-                }
+    ) -> LayeredMesh {
+        let mut opaque_meshes = Vec::new();
+        let mut cutout_meshes = Vec::new();
+        let mut transparent_meshes = Vec::new();
+
+        for (local_position, voxel_type) in chunk.iter_solid() {
+            let voxel_properties = voxel_registry.get_properties(&voxel_type);
+            if voxel_properties.is_invisible() {
+                continue;
             }
+            let world_position = local_position.clone().world_position(chunk.position());
+            let textures = world
+                .texture_override(chunk.position(), local_position)
+                .unwrap_or_else(|| voxel_properties.textures());
+            let orientation = chunk.get_voxel_orientation(local_position);
+            let occluding_neighbors = world.get_occluding_neighbors(world_position);
+            let layer_meshes = match voxel_properties.render_layer() {
+                RenderLayer::Opaque => &mut opaque_meshes,
+                RenderLayer::Cutout => &mut cutout_meshes,
+                RenderLayer::Transparent => &mut transparent_meshes,
+            };
+            let mut voxel_mesh = Self::voxel(
+                world_position,
+                voxel_properties,
+                textures,
+                orientation,
+                texture_atlas,
+                &occluding_neighbors,
+            );
+            voxel_mesh.apply_lighting(world, world_position);
+            layer_meshes.push(voxel_mesh);
+        }
+
+        LayeredMesh::new(
+            Self::merged(opaque_meshes),
+            Self::merged(cutout_meshes),
+            Self::merged(transparent_meshes),
+        )
+    }
+
+    /// The chunk's mesh from the mesh cache when present, otherwise built from scratch. Buried
+    /// chunks get an empty mesh.
+    pub(crate) fn load_or_build_chunk(world: &World, chunk_position: ChunkPosition) -> LayeredMesh {
+        if world.is_chunk_buried(chunk_position) {
+            return LayeredMesh::default();
+        }
+
+        let content_hash = world
+            .mesh_cache()
+            .map(|_| world.chunk_content_hash(chunk_position));
+        if let (Some(mesh_cache), Some(content_hash)) = (world.mesh_cache(), content_hash)
+            && let Some(chunk_mesh) = mesh_cache.load(content_hash)
+        {
+            return chunk_mesh;
         }
 
-        Self::merged(voxel_meshes)
+        Self::build_chunk(world, chunk_position, content_hash)
     }
 
-    pub(crate) fn world(world: &mut World) -> Self {
-        let voxel_registry = world.voxel_registry();
-        let texture_atlas = world.texture_atlas();
+    /// Meshes a chunk from its voxels and its neighbors' border voxels, storing the result in
+    /// the mesh cache under `content_hash` when one is given.
+    pub(crate) fn build_chunk(
+        world: &World,
+        chunk_position: ChunkPosition,
+        content_hash: Option<u64>,
+    ) -> LayeredMesh {
+        let chunk = match world.chunk_data().get(&chunk_position) {
+            Some(chunk) => chunk,
+            None => &Chunk::empty(chunk_position),
+        };
+        let chunk_mesh = Self::chunk(world, chunk, world.voxel_registry(), world.texture_atlas());
+        if let (Some(mesh_cache), Some(content_hash)) = (world.mesh_cache(), content_hash) {
+            mesh_cache.store(content_hash, &chunk_mesh);
+        }
+        chunk_mesh
+    }
 
+    /// Every loaded chunk is meshed, but only the `chunk_draw_limit` nearest are merged in.
+    /// With `is_opaque_instanced` the opaque layer of chunks that are neither fading in nor
+    /// about to unload is left out, since `FaceInstances` draws those faces.
+    pub(crate) fn world(
+        world: &mut World,
+        chunk_draw_limit: Option<usize>,
+        is_opaque_instanced: bool,
+    ) -> LayeredMesh {
         let chunk_positions = world
             .chunk_data()
             .keys()
             .copied()
             .collect::<Vec<ChunkPosition>>();
 
-        let uncached_chunks = chunk_positions
-            .iter()
-            .filter(|chunk_position| world.chunk_meshes().get(chunk_position).is_none())
-            .copied()
-            .collect::<Vec<ChunkPosition>>();
+        if world.is_background_meshing_enabled() {
+            world.update_mesh_queue();
+        } else {
+            let new_chunk_meshes = chunk_positions
+                .iter()
+                .filter(|chunk_position| world.chunk_meshes().get(chunk_position).is_none())
+                .copied()
+                .collect::<Vec<ChunkPosition>>()
+                .into_par_iter()
+                .map(|chunk_position| {
+                    (
+                        chunk_position,
+                        Self::load_or_build_chunk(world, chunk_position),
+                    )
+                })
+                .collect::<Vec<(ChunkPosition, LayeredMesh)>>();
 
-        let new_chunk_meshes = uncached_chunks
-            .into_par_iter()
-            .map(|chunk_position| {
-                let chunk = match world.chunk_data().get(&chunk_position) {
-                    Some(chunk) => chunk,
-                    None => &Chunk::empty(chunk_position),
-                };
-                let chunk_mesh = Self::chunk(world, chunk, voxel_registry, texture_atlas);
-                (chunk_position, chunk_mesh)
-            })
-            .collect::<Vec<(ChunkPosition, Self)>>();
-
-        for (chunk_position, chunk_mesh) in &new_chunk_meshes {
-            world.insert_chunk_mesh(chunk_position, chunk_mesh.clone());
+            for (chunk_position, chunk_mesh) in &new_chunk_meshes {
+                world.insert_chunk_mesh(chunk_position, chunk_mesh.clone());
+            }
         }
 
-        let all_chunk_meshes = chunk_positions
+        let is_chunk_flashing_enabled = world.is_chunk_flashing_enabled();
+        let mut fading_chunks = HashSet::new();
+        let all_chunk_meshes = world
+            .nearest_chunk_positions(chunk_draw_limit)
             .into_iter()
-            .map(|chunk_position| {
-                world
-                    .chunk_meshes()
-                    .get(&chunk_position)
-                    .expect("Should have generated or cached each chunk mesh")
-                    .clone()
+            // Chunks still meshing in the background are left out until their mesh arrives
+            .filter_map(|chunk_position| {
+                let chunk_mesh = world.chunk_meshes().get(&chunk_position)?;
+                Some(match world.chunk_fade_start(chunk_position) {
+                    Some(fade_start) => {
+                        fading_chunks.insert(chunk_position);
+                        let chunk_mesh = chunk_mesh.clone().fading(fade_start);
+                        if is_chunk_flashing_enabled {
+                            chunk_mesh.tinted(LOAD_FLASH_TINT)
+                        } else {
+                            chunk_mesh
+                        }
+                    }
+                    None if world.is_chunk_about_to_unload(chunk_position) => {
+                        chunk_mesh.clone().tinted(ABOUT_TO_UNLOAD_TINT)
+                    }
+                    None if is_opaque_instanced => chunk_mesh.without_opaque(),
+                    None => chunk_mesh.clone(),
+                })
             })
-            .collect();
+            .collect::<Vec<LayeredMesh>>();
+        world.set_fading_chunks(fading_chunks);
+
+        LayeredMesh::merged(all_chunk_meshes)
+    }
+
+    fn set_fade_start(&mut self, fade_start: f32) {
+        for vertex in &mut self.vertices {
+            vertex.set_fade_start(fade_start);
+        }
+    }
 
-        Self::merged(all_chunk_meshes)
+    fn tint(&mut self, tint: [f32; 3]) {
+        for vertex in &mut self.vertices {
+            vertex.tint(tint);
+        }
+    }
+
+    /// Reorders the quads back-to-front as seen from `eye` so blended faces composite correctly.
+    /// This costs an O(n log n) sort of every quad plus a new index upload, so it is meant only
+    /// for the transparent layer and only when the eye has moved; opaque and cutout geometry
+    /// rely on the depth buffer instead.
+    pub(crate) fn sort_faces_by_distance(&mut self, eye: Vec3) {
+        let corner = |index: u32| {
+            self.vertices
+                .get(index as usize)
+                .map_or(eye, Vertex::position)
+        };
+        let mut quads = self
+            .indices
+            .as_chunks::<6>()
+            .0
+            .iter()
+            .map(|&quad| {
+                let [first, _, opposite, ..] = quad;
+                let centroid = (corner(first) + corner(opposite)) / 2.0;
+                (centroid.distance_squared(eye), quad)
+            })
+            .collect::<Vec<(f32, [u32; 6])>>();
+        quads.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        self.indices = quads.into_iter().flat_map(|(_, quad)| quad).collect();
+        // Faces from different chunks are now interleaved
+        self.draw_ranges.clear();
     }
 
     pub(crate) fn vertices(&self) -> &Vec<Vertex> {
@@ -214,19 +413,76 @@ impl Mesh {
         self.indices.len() as u32
     }
 
+    pub(crate) fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) fn vertex_count(&self) -> u32 {
+        self.vertices.len() as u32
+    }
+
+    /// Bounds of the vertex positions, computed when the mesh is built. Empty meshes have none.
+    pub(crate) fn aabb(&self) -> Option<Aabb> {
+        self.aabb
+    }
+
+    pub(crate) fn draw_ranges(&self) -> &[DrawRange] {
+        &self.draw_ranges
+    }
+
+    /// Marks a just-pushed face for the shader's wave animation: the whole top face, and the
+    /// upper edge of each side face while the top is open, so the sides follow the surface down
+    /// instead of leaving a lip above it.
+    fn apply_surface_waves(
+        vertices: &mut [Vertex],
+        voxel_properties: &VoxelProperties,
+        face: VoxelFace,
+        occluding_neighbors: &OccludingVoxelNeighbors,
+    ) {
+        if !voxel_properties.has_surface_waves() || occluding_neighbors.top {
+            return;
+        }
+        let face_vertices = vertices.iter_mut().rev().take(4);
+        match face {
+            VoxelFace::Top => face_vertices.for_each(|vertex| vertex.set_wave_strength(1.0)),
+            VoxelFace::Bottom => {}
+            VoxelFace::Front | VoxelFace::Back | VoxelFace::Right | VoxelFace::Left => {
+                let mut face_vertices = face_vertices.collect::<Vec<&mut Vertex>>();
+                let top = face_vertices
+                    .iter()
+                    .map(|vertex| vertex.position().y)
+                    .fold(f32::NEG_INFINITY, f32::max);
+                for vertex in &mut face_vertices {
+                    if vertex.position().y >= top {
+                        vertex.set_wave_strength(1.0);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Builds the vertices of one face, all sharing its normal.
+    fn face_vertex(face: VoxelFace) -> impl Fn([f32; 3], [f32; 2]) -> Vertex {
+        move |position, texture_coordinates| {
+            let mut vertex = Vertex::with(position, texture_coordinates, face.normal(), [1.0; 3]);
+            vertex.set_face_brightness(face.brightness());
+            vertex
+        }
+    }
+
+    /// Indexes the quad whose four vertices were just pushed. Quads are listed
+    /// counter-clockwise as seen from outside the voxel, matching the pipeline's front face.
     fn extend_indices(vertices: &[Vertex], indices: &mut Vec<u32>) {
-        let vertex_count = vertices.len() as u32;
-        indices.extend(vec![
-            vertex_count,
-            vertex_count + 1,
-            vertex_count + 2,
-            vertex_count + 2,
-            vertex_count + 3,
-            vertex_count,
-        ]);
+        indices.extend_from_slice(&quad_indices(vertices.len() as u32 - 4));
     }
 }
 
+/// The two triangles of the quad whose four vertices start at `base`.
+fn quad_indices(base: u32) -> [u32; 6] {
+    [base, base + 1, base + 2, base + 2, base + 3, base]
+}
+
 pub(crate) struct OccludingVoxelNeighbors {
     front: bool,
     back: bool,
@@ -237,6 +493,7 @@ pub(crate) struct OccludingVoxelNeighbors {
 }
 
 impl OccludingVoxelNeighbors {
+    #[expect(clippy::fn_params_excessive_bools)]
     pub(crate) fn new(
         front: bool,
         back: bool,
@@ -254,4 +511,191 @@ impl OccludingVoxelNeighbors {
             bottom,
         }
     }
+
+    pub(crate) fn is_occluded(&self, face: VoxelFace) -> bool {
+        match face {
+            VoxelFace::Front => self.front,
+            VoxelFace::Back => self.back,
+            VoxelFace::Right => self.right,
+            VoxelFace::Left => self.left,
+            VoxelFace::Top => self.top,
+            VoxelFace::Bottom => self.bottom,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::chunk::CHUNK_SIZE;
+    use crate::game::voxel::VoxelType;
+    use crate::game::world::LocalChunkPosition;
+    use glam::Vec3;
+
+    #[test]
+    fn far_corner_voxel_is_meshed_at_its_world_position() {
+        let world = World::new_with_seed(0);
+        let chunk_position = ChunkPosition::new(-2, 1, 3);
+        let last = CHUNK_SIZE - 1;
+        let mut chunk = Chunk::empty(chunk_position);
+        chunk.set_voxel(LocalChunkPosition::new(last, last, last), VoxelType::Stone);
+
+        let chunk_mesh = Mesh::chunk(
+            &world,
+            &chunk,
+            world.voxel_registry(),
+            world.texture_atlas(),
+        );
+        let (min, max) = chunk_mesh
+            .layer(RenderLayer::Opaque)
+            .vertices()
+            .iter()
+            .map(Vertex::position)
+            .fold(
+                (Vec3::INFINITY, Vec3::NEG_INFINITY),
+                |(min, max), position| (min.min(position), max.max(position)),
+            );
+
+        let (x, y, z) = chunk_position.min_corner().get_f32();
+        let center = Vec3::new(x, y, z) + Vec3::splat(last as f32);
+        assert_eq!(
+            min,
+            center - 0.5,
+            "The voxel should start half a unit before the chunk's last voxel center"
+        );
+        assert_eq!(
+            max,
+            center + 0.5,
+            "The voxel should end on the chunk's far boundary"
+        );
+    }
+
+    #[test]
+    fn chunk_mesh_has_four_vertices_and_six_indices_per_face() {
+        let world = World::new_with_seed(0);
+        // Laid out x fastest, then y, then z
+        let stone_indices = [4, 8].map(|x| x + 4 * CHUNK_SIZE + 4 * CHUNK_SIZE * CHUNK_SIZE);
+        let voxel_types = (0..CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE)
+            .map(|index| {
+                if stone_indices.contains(&index) {
+                    VoxelType::Stone
+                } else {
+                    VoxelType::Air
+                }
+            })
+            .collect();
+        let chunk = Chunk::from_voxels(ChunkPosition::new(0, 0, 0), voxel_types)
+            .expect("A full chunk of voxels should be accepted");
+
+        let chunk_mesh = Mesh::chunk(
+            &world,
+            &chunk,
+            world.voxel_registry(),
+            world.texture_atlas(),
+        );
+        let mesh = chunk_mesh.layer(RenderLayer::Opaque);
+        assert_eq!(
+            mesh.vertices().len(),
+            2 * 6 * 4,
+            "Each face of both lone voxels should have four vertices"
+        );
+        assert_eq!(
+            mesh.indices().len(),
+            2 * 6 * 6,
+            "Each face of both lone voxels should have six indices"
+        );
+        assert!(
+            mesh.indices()
+                .iter()
+                .all(|index| (*index as usize) < mesh.vertices().len()),
+            "Every index of the merged mesh should refer to one of its vertices"
+        );
+        assert!(
+            chunk_mesh.layer(RenderLayer::Cutout).vertices().is_empty()
+                && chunk_mesh
+                    .layer(RenderLayer::Transparent)
+                    .vertices()
+                    .is_empty(),
+            "Stone should only be meshed into the opaque layer"
+        );
+    }
+
+    #[test]
+    fn fading_chunks_are_drawn_entirely_in_the_blended_layer() {
+        let world = World::new_with_seed(0);
+        let mut chunk = Chunk::empty(ChunkPosition::new(0, 0, 0));
+        chunk.set_voxel(LocalChunkPosition::new(4, 4, 4), VoxelType::Stone);
+        chunk.set_voxel(LocalChunkPosition::new(8, 4, 4), VoxelType::Leaves);
+        chunk.set_voxel(LocalChunkPosition::new(12, 4, 4), VoxelType::Water);
+        let chunk_mesh = Mesh::chunk(
+            &world,
+            &chunk,
+            world.voxel_registry(),
+            world.texture_atlas(),
+        );
+        let index_count = chunk_mesh.index_count();
+
+        let fading = chunk_mesh.fading(1.0);
+        assert!(
+            fading.layer(RenderLayer::Opaque).is_empty()
+                && fading.layer(RenderLayer::Cutout).is_empty(),
+            "A fading chunk should leave nothing for the unblended pipelines"
+        );
+        assert_eq!(
+            fading.layer(RenderLayer::Transparent).index_count(),
+            index_count,
+            "Every face of a fading chunk should be blended in"
+        );
+    }
+
+    #[test]
+    fn single_voxel_mesh_spans_one_voxel() {
+        let voxel_registry = VoxelRegistry::init();
+        let texture_atlas = TextureAtlas::init();
+        let voxel_properties = voxel_registry.get_properties(&VoxelType::Stone);
+        let mesh = Mesh::voxel(
+            WorldPosition::new(-3, 7, 2),
+            voxel_properties,
+            voxel_properties.textures(),
+            VoxelOrientation::default(),
+            &texture_atlas,
+            &OccludingVoxelNeighbors::new(false, false, false, false, false, false),
+        );
+
+        assert_eq!(
+            mesh.vertex_count(),
+            6 * 4,
+            "A lone voxel should have four vertices on each of its six faces"
+        );
+        let aabb = mesh.aabb().expect("A lone voxel's mesh should have bounds");
+        assert_eq!(
+            (aabb.min(), aabb.max()),
+            (Vec3::new(-3.5, 6.5, 1.5), Vec3::new(-2.5, 7.5, 2.5)),
+            "The bounds should be the 1×1×1 box centred on the voxel"
+        );
+        assert!(
+            Mesh::default().aabb().is_none(),
+            "An empty mesh should have no bounds"
+        );
+    }
+
+    #[test]
+    fn occluded_faces_are_not_meshed() {
+        let voxel_registry = VoxelRegistry::init();
+        let texture_atlas = TextureAtlas::init();
+        let voxel_properties = voxel_registry.get_properties(&VoxelType::Stone);
+        let mesh = Mesh::voxel(
+            WorldPosition::new(0, 0, 0),
+            voxel_properties,
+            voxel_properties.textures(),
+            VoxelOrientation::default(),
+            &texture_atlas,
+            &OccludingVoxelNeighbors::new(true, false, true, false, true, false),
+        );
+        assert_eq!(
+            (mesh.vertices().len(), mesh.indices().len()),
+            (3 * 4, 3 * 6),
+            "Only the three unoccluded faces should be meshed"
+        );
+    }
 }
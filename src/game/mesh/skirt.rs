@@ -0,0 +1,64 @@
+use crate::game::chunk::{CHUNK_SIZE, Chunk};
+use crate::game::mesh::{Mesh, Vertex};
+use crate::game::render::TextureType;
+use crate::game::world::World;
+use std::f32::consts::TAU;
+
+const SKIRT_SEGMENTS: u32 = 64;
+// Radial steps from the inner to the outer edge, each vertex following the generated ground
+const SKIRT_RINGS: u32 = 8;
+// Sunk below the terrain surface so that loaded chunks always win the depth test where they
+// overlap
+const SKIRT_DEPTH: f32 = 0.25;
+
+impl Mesh {
+    /// A ring from just inside the loaded area out to the far plane that follows the height the
+    /// generator would give the ground, colored with a single texel of grass so the
+    /// render-distance edge reads as distant land. Fog fades it out with the rest of the world.
+    pub(crate) fn horizon_skirt(world: &World) -> Option<Self> {
+        let (chunk_x, _, chunk_z) = world.last_update_position()?.get();
+        let half_chunk = (CHUNK_SIZE as f32 - 1.0) / 2.0;
+        let (center_x, center_z) = (
+            (chunk_x * CHUNK_SIZE as i32) as f32 + half_chunk,
+            (chunk_z * CHUNK_SIZE as i32) as f32 + half_chunk,
+        );
+        let inner_radius = ((world.render_distance_xz() - 1).max(0) as usize * CHUNK_SIZE) as f32;
+        let outer_radius = world.view_distance();
+
+        let (u_min, u_max, v_min, v_max) = world
+            .texture_atlas()
+            .get_coordinates(TextureType::GrassTop)
+            .get();
+        let texture_coordinates = [f32::midpoint(u_min, u_max), f32::midpoint(v_min, v_max)];
+        let point = |angle: f32, radius: f32| {
+            let (sin, cos) = angle.sin_cos();
+            let (x, z) = (center_x + cos * radius, center_z + sin * radius);
+            let surface_height =
+                Chunk::generated_surface_height(x.round() as i32, z.round() as i32) as f32;
+            Vertex::new(
+                [x, surface_height + 0.5 - SKIRT_DEPTH, z],
+                texture_coordinates,
+            )
+        };
+        let ring_radius = |ring: u32| {
+            inner_radius + (outer_radius - inner_radius) * ring as f32 / SKIRT_RINGS as f32
+        };
+
+        let vertices = (0..SKIRT_SEGMENTS)
+            .flat_map(|segment| {
+                let start = segment as f32 / SKIRT_SEGMENTS as f32 * TAU;
+                let end = (segment + 1) as f32 / SKIRT_SEGMENTS as f32 * TAU;
+                (0..SKIRT_RINGS).flat_map(move |ring| {
+                    let (near, far) = (ring_radius(ring), ring_radius(ring + 1));
+                    [
+                        point(start, near),
+                        point(end, near),
+                        point(end, far),
+                        point(start, far),
+                    ]
+                })
+            })
+            .collect();
+        Some(Self::quads(vertices))
+    }
+}
@@ -1,34 +1,127 @@
+use crate::game::render::TextureCoordinates;
+use glam::Vec3;
+
+// Far enough in the past that the fade has always finished. It must stay finite: interpolating
+// an infinity gives NaN wherever a corner's weight is zero, and the face's alpha with it
+const FADED_IN: f32 = -1.0e9;
+
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub(crate) struct Vertex {
     position: [f32; 3],
     texture_coordinates: [f32; 2],
+    emissive_texture_coordinates: [f32; 2],
+    emissive_strength: f32,
+    fade_start: f32,
+    wave_strength: f32,
+    ambient_occlusion: f32,
+    light: f32,
+    normal: [f32; 3],
+    color: [f32; 3],
+    face_brightness: f32,
 }
 
 impl Vertex {
+    /// A vertex facing nowhere in particular, untinted and fully lit.
     pub(crate) fn new(position: [f32; 3], texture_coordinates: [f32; 2]) -> Self {
+        Self::with(position, texture_coordinates, [0.0; 3], [1.0; 3])
+    }
+
+    /// `color` multiplies the texture. The vertex starts fully lit; the mesher darkens it
+    /// afterward through `set_ambient_occlusion` and `set_light` once the neighbors are known.
+    pub(crate) fn with(
+        position: [f32; 3],
+        texture_coordinates: [f32; 2],
+        normal: [f32; 3],
+        color: [f32; 3],
+    ) -> Self {
         Self {
             position,
             texture_coordinates,
+            emissive_texture_coordinates: [0.0; 2],
+            emissive_strength: 0.0,
+            fade_start: FADED_IN,
+            wave_strength: 0.0,
+            ambient_occlusion: 1.0,
+            light: 1.0,
+            normal,
+            color,
+            face_brightness: 1.0,
         }
     }
 
-    pub(crate) fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
-        wgpu::VertexBufferLayout {
-            array_stride: size_of::<Self>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    shader_location: 0,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-                wgpu::VertexAttribute {
-                    offset: size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-            ],
+    /// Points the emissive overlay at the same corner of `emissive` that the base texture
+    /// coordinates select in `base`.
+    pub(crate) fn set_emissive(
+        &mut self,
+        base: &TextureCoordinates,
+        emissive: &TextureCoordinates,
+    ) {
+        let (u_min, _, v_min, _) = base.get();
+        let (emissive_u_min, emissive_u_max, emissive_v_min, emissive_v_max) = emissive.get();
+        // The base coordinates were copied straight from `base`, so exact comparison is safe
+        let [u, v] = self.texture_coordinates;
+        self.emissive_texture_coordinates = [
+            if u == u_min {
+                emissive_u_min
+            } else {
+                emissive_u_max
+            },
+            if v == v_min {
+                emissive_v_min
+            } else {
+                emissive_v_max
+            },
+        ];
+        self.emissive_strength = 1.0;
+    }
+
+    pub(crate) fn position(&self) -> Vec3 {
+        Vec3::from(self.position)
+    }
+
+    pub(crate) fn normal(&self) -> Vec3 {
+        Vec3::from(self.normal)
+    }
+
+    pub(crate) fn set_fade_start(&mut self, fade_start: f32) {
+        self.fade_start = fade_start;
+    }
+
+    /// Scales how far the shader's wave animation displaces this vertex, zero for none.
+    pub(crate) fn set_wave_strength(&mut self, wave_strength: f32) {
+        self.wave_strength = wave_strength;
+    }
+
+    /// Fixed shading for the direction the face points, from 0 for black to 1 for unshaded.
+    pub(crate) fn set_face_brightness(&mut self, face_brightness: f32) {
+        self.face_brightness = face_brightness;
+    }
+
+    /// Darkening from nearby geometry, from 0 for fully occluded to 1 for open.
+    pub(crate) fn set_ambient_occlusion(&mut self, ambient_occlusion: f32) {
+        self.ambient_occlusion = ambient_occlusion;
+    }
+
+    /// Light reaching this vertex, from 0 for none to 1 for full.
+    pub(crate) fn set_light(&mut self, light: f32) {
+        self.light = light;
+    }
+
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) fn ambient_occlusion(&self) -> f32 {
+        self.ambient_occlusion
+    }
+
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) fn light(&self) -> f32 {
+        self.light
+    }
+
+    /// Multiplies the color by `tint`.
+    pub(crate) fn tint(&mut self, tint: [f32; 3]) {
+        for (channel, factor) in self.color.iter_mut().zip(tint) {
+            *channel *= factor;
         }
     }
 }
@@ -1,6 +1,10 @@
+use anyhow::Context as _;
+use log::warn;
 use std::collections::HashMap;
+use std::io::Read as _;
+use std::path::Path;
 
-#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
 pub(crate) enum TextureType {
     Error,
     Air,
@@ -8,6 +12,35 @@ pub(crate) enum TextureType {
     Dirt,
     GrassSide,
     GrassTop,
+    Sand,
+    Leaves,
+    Water,
+    GlowingOreOverlay,
+}
+
+impl TextureType {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "error" => Some(Self::Error),
+            "air" => Some(Self::Air),
+            "stone" => Some(Self::Stone),
+            "dirt" => Some(Self::Dirt),
+            "grass_side" => Some(Self::GrassSide),
+            "grass_top" => Some(Self::GrassTop),
+            "sand" => Some(Self::Sand),
+            "leaves" => Some(Self::Leaves),
+            "water" => Some(Self::Water),
+            "glowing_ore_overlay" => Some(Self::GlowingOreOverlay),
+            _ => None,
+        }
+    }
+
+    /// A texture pack entry named like `stone_2`: another look for `stone`, numbered from 1.
+    fn from_variant_name(name: &str) -> Option<(Self, u32)> {
+        let (base_name, number) = name.rsplit_once('_')?;
+        let number = number.parse().ok().filter(|number| *number > 0)?;
+        Some((Self::from_name(base_name)?, number))
+    }
 }
 
 pub(crate) struct TextureCoordinates {
@@ -35,11 +68,73 @@ impl TextureCoordinates {
 pub(crate) struct TextureAtlas {
     image: image::DynamicImage,
     coordinates: HashMap<TextureType, TextureCoordinates>,
+    /// Alternatives to each texture beyond the base one, picked per voxel to break up tiling.
+    variant_coordinates: HashMap<TextureType, Vec<TextureCoordinates>>,
+    /// Every slot's texture type and `(u_min, u_max, v_min, v_max)`, in slot order.
+    slots: Vec<(TextureType, (f32, f32, f32, f32))>,
 }
 
 impl TextureAtlas {
     pub(crate) fn init() -> Self {
-        let textures = vec![
+        Self::build(Self::embedded_textures()).expect("Built-in textures should decode")
+    }
+
+    /// Builds the atlas from a zip texture pack, matching each entry's file stem against the
+    /// texture names, e.g. `voxels/stone.png`. Textures the pack does not provide fall back to
+    /// the built-in ones. Entries numbered like `voxels/stone_1.png` are packed as variants of
+    /// the texture they are named after.
+    pub(crate) fn from_pack(path: &Path) -> anyhow::Result<Self> {
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+
+        let mut pack_textures = HashMap::new();
+        let mut pack_variants = Vec::new();
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index)?;
+            let Some(stem) = entry
+                .enclosed_name()
+                .filter(|_| entry.is_file())
+                .as_deref()
+                .and_then(Path::file_stem)
+                .and_then(|stem| stem.to_str())
+                .map(str::to_owned)
+            else {
+                continue;
+            };
+            let texture_type = TextureType::from_name(&stem);
+            let variant = TextureType::from_variant_name(&stem);
+            if texture_type.is_none() && variant.is_none() {
+                continue;
+            }
+
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            match (texture_type, variant) {
+                (Some(texture_type), _) => {
+                    pack_textures.insert(texture_type, bytes);
+                }
+                (None, Some(variant)) => pack_variants.push((variant, bytes)),
+                (None, None) => {}
+            }
+        }
+        // Zip entries come in no particular order, so variants are sorted for stable UVs
+        pack_variants.sort_by_key(|((texture_type, number), _)| (*texture_type as u8, *number));
+
+        let textures = Self::embedded_textures()
+            .into_iter()
+            .map(|(texture_type, bytes)| {
+                let pack_bytes = pack_textures.get(&texture_type).map(Vec::as_slice);
+                (texture_type, pack_bytes.or(bytes))
+            })
+            .collect();
+        let variants = pack_variants
+            .iter()
+            .map(|((texture_type, _), bytes)| (*texture_type, bytes.as_slice()))
+            .collect();
+        Self::build_with_variants(textures, variants)
+    }
+
+    fn embedded_textures() -> Vec<(TextureType, Option<&'static [u8]>)> {
+        vec![
             (TextureType::Air, None),
             (
                 TextureType::GrassTop,
@@ -57,34 +152,75 @@ impl TextureAtlas {
                 TextureType::Stone,
                 Some(include_bytes!("../../../assets/textures/voxels/stone.png").as_slice()),
             ),
-        ];
-
-        Self::build(textures)
+            (
+                TextureType::Sand,
+                Some(include_bytes!("../../../assets/textures/voxels/sand.png").as_slice()),
+            ),
+            (
+                TextureType::Leaves,
+                Some(include_bytes!("../../../assets/textures/voxels/leaves.png").as_slice()),
+            ),
+            (
+                TextureType::Water,
+                Some(include_bytes!("../../../assets/textures/voxels/water.png").as_slice()),
+            ),
+            (
+                TextureType::GlowingOreOverlay,
+                Some(
+                    include_bytes!("../../../assets/textures/voxels/glowing_ore_overlay.png")
+                        .as_slice(),
+                ),
+            ),
+        ]
     }
 
-    fn build(textures: Vec<(TextureType, Option<&[u8]>)>) -> Self {
-        let mut loaded_textures = Vec::new();
-        let mut texture_size = 0u32;
-
-        for (texture_type, bytes) in textures {
-            if let Some(bytes) = bytes {
-                let image = image::load_from_memory(bytes)
-                    .expect("Failed to load texture")
-                    .to_rgba8();
-
-                if texture_size == 0 {
-                    texture_size = image.width().min(image.height());
-                }
+    fn build(textures: Vec<(TextureType, Option<&[u8]>)>) -> anyhow::Result<Self> {
+        Self::build_with_variants(textures, Vec::new())
+    }
 
-                loaded_textures.push((texture_type, image));
-            }
-        }
+    fn build_with_variants(
+        textures: Vec<(TextureType, Option<&[u8]>)>,
+        variants: Vec<(TextureType, &[u8])>,
+    ) -> anyhow::Result<Self> {
+        let load = |texture_type: TextureType, bytes| {
+            let image = image::load_from_memory(bytes)
+                .with_context(|| format!("Failed to decode the {texture_type:?} texture"))?;
+            anyhow::Ok((texture_type, image.to_rgba8()))
+        };
+        let loaded_textures = textures
+            .into_iter()
+            .filter_map(|(texture_type, bytes)| Some(load(texture_type, bytes?)))
+            .collect::<anyhow::Result<_>>()?;
+        let loaded_variants = variants
+            .into_iter()
+            .map(|(texture_type, bytes)| load(texture_type, bytes))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self::from_textures(loaded_textures, loaded_variants))
+    }
 
-        if texture_size == 0 {
-            texture_size = 16;
-        }
+    /// Packs already decoded textures, e.g. solid-color stand-ins for deterministic rendering.
+    /// Tiles are square and sized to the largest texture side, and any texture that does not
+    /// match is scaled to the tile rather than cropped.
+    ///
+    /// Slot 0 holds the error texture and the given textures follow in order, filling a square
+    /// grid row by row from the top left, so slot `i` sits at column `i % n` and row `i / n` of
+    /// an `n`-by-`n` grid with `n = ceil(sqrt(len + 1))`. `variants` take the slots after
+    /// them, in order. The same textures in the same order therefore always get the same
+    /// coordinates. Hand the result to `World::set_texture_atlas`
+    /// before the renderer is created so it uploads this atlas instead of the built-in one.
+    pub(crate) fn from_textures(
+        loaded_textures: Vec<(TextureType, image::RgbaImage)>,
+        variants: Vec<(TextureType, image::RgbaImage)>,
+    ) -> Self {
+        let texture_size = loaded_textures
+            .iter()
+            .chain(&variants)
+            .map(|(_, image)| image.width().max(image.height()))
+            .max()
+            .unwrap_or(16);
 
-        let texture_count = loaded_textures.len() + 1;
+        let base_texture_count = loaded_textures.len();
+        let texture_count = base_texture_count + variants.len() + 1;
         let textures_per_row = (texture_count as f32).sqrt().ceil() as u32;
         let atlas_width = textures_per_row * texture_size;
         let atlas_height = textures_per_row * texture_size;
@@ -92,29 +228,43 @@ impl TextureAtlas {
         let mut atlas = image::RgbaImage::new(atlas_width, atlas_height);
 
         let mut coordinates = HashMap::new();
+        let mut variant_coordinates = HashMap::<TextureType, Vec<TextureCoordinates>>::new();
+        let mut slots = Vec::with_capacity(texture_count);
 
         let error_texture = Self::create_error_texture(texture_size);
-        Self::copy_texture_to_atlas(&mut atlas, &error_texture, 0, 0, texture_size);
-        coordinates.insert(
-            TextureType::Error,
-            Self::calculate_coordinates(0, 0, texture_size, atlas_width, atlas_height),
-        );
-
-        for (index, (texture_type, texture)) in loaded_textures.iter().enumerate() {
-            let index = index + 1;
+        let textures = std::iter::once((TextureType::Error, error_texture))
+            .chain(loaded_textures)
+            .chain(variants);
+        for (index, (texture_type, texture)) in textures.enumerate() {
             let x = (index as u32 % textures_per_row) * texture_size;
             let y = (index as u32 / textures_per_row) * texture_size;
 
-            Self::copy_texture_to_atlas(&mut atlas, texture, x, y, texture_size);
-            coordinates.insert(
-                *texture_type,
-                Self::calculate_coordinates(x, y, texture_size, atlas_width, atlas_height),
-            );
+            if texture.dimensions() != (texture_size, texture_size) {
+                warn!(
+                    "Scaling the {}x{} {texture_type:?} texture to the {texture_size}x{texture_size} atlas tile",
+                    texture.width(),
+                    texture.height()
+                );
+            }
+            Self::copy_texture_to_atlas(&mut atlas, &texture, x, y, texture_size);
+            let texture_coordinates =
+                Self::calculate_coordinates(x, y, texture_size, atlas_width, atlas_height);
+            slots.push((texture_type, texture_coordinates.get()));
+            if index > base_texture_count {
+                variant_coordinates
+                    .entry(texture_type)
+                    .or_default()
+                    .push(texture_coordinates);
+            } else {
+                coordinates.insert(texture_type, texture_coordinates);
+            }
         }
 
         Self {
             image: image::DynamicImage::ImageRgba8(atlas),
             coordinates,
+            variant_coordinates,
+            slots,
         }
     }
 
@@ -122,12 +272,50 @@ impl TextureAtlas {
         self.image.clone()
     }
 
+    pub(crate) fn save_debug_png(&self, path: &Path) -> anyhow::Result<()> {
+        self.image.save_with_format(path, image::ImageFormat::Png)?;
+        Ok(())
+    }
+
     pub(crate) fn get_coordinates(&self, texture: TextureType) -> &TextureCoordinates {
         self.coordinates
             .get(&texture)
             .expect("Should not request coordinates for a texture that is not in the atlas")
     }
 
+    /// The base texture or one of its variants, chosen by `variant_seed`, so the same seed
+    /// always picks the same look.
+    pub(crate) fn get_variant_coordinates(
+        &self,
+        texture: TextureType,
+        variant_seed: u32,
+    ) -> &TextureCoordinates {
+        let variants = self
+            .variant_coordinates
+            .get(&texture)
+            .map_or(&[][..], Vec::as_slice);
+        match variant_seed as usize % (variants.len() + 1) {
+            0 => self.get_coordinates(texture),
+            index => variants
+                .get(index - 1)
+                .unwrap_or_else(|| self.get_coordinates(texture)),
+        }
+    }
+
+    /// Every packed texture with its `(u_min, u_max, v_min, v_max)`, in slot order. Variants
+    /// are listed under the texture they vary.
+    pub(crate) fn layout(&self) -> Vec<(TextureType, (f32, f32, f32, f32))> {
+        self.slots.clone()
+    }
+
+    /// The UV rect of the texture with the given definition name, e.g. `grass_top`, for tools
+    /// and tests that refer to textures by name.
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) fn texture_coordinates_for(&self, name: &str) -> Option<(f32, f32, f32, f32)> {
+        let texture = TextureType::from_name(name)?;
+        self.coordinates.get(&texture).map(TextureCoordinates::get)
+    }
+
     fn create_error_texture(size: u32) -> image::RgbaImage {
         let mut texture = image::RgbaImage::new(size, size);
         let checker_size = size / 2;
@@ -155,12 +343,13 @@ impl TextureAtlas {
         y: u32,
         size: u32,
     ) {
-        for texture_y in 0..size.min(texture.height()) {
-            for texture_x in 0..size.min(texture.width()) {
-                let pixel = texture.get_pixel(texture_x, texture_y);
-                atlas.put_pixel(x + texture_x, y + texture_y, *pixel);
-            }
-        }
+        // Nearest filtering keeps pixel art crisp when a texture is scaled up to the tile
+        let texture = if texture.dimensions() == (size, size) {
+            texture
+        } else {
+            &image::imageops::resize(texture, size, size, image::imageops::FilterType::Nearest)
+        };
+        image::imageops::replace(atlas, texture, i64::from(x), i64::from(y));
     }
 
     fn calculate_coordinates(
@@ -178,3 +367,93 @@ impl TextureAtlas {
         TextureCoordinates::new(u_min, u_max, v_min, v_max)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::voxel::{VoxelFace, VoxelRegistry, VoxelType};
+
+    #[test]
+    fn texture_coordinates_for_matches_the_texture_type() {
+        let texture_atlas = TextureAtlas::init();
+        assert_eq!(
+            texture_atlas.texture_coordinates_for("grass_top"),
+            Some(texture_atlas.get_coordinates(TextureType::GrassTop).get()),
+            "Looking a texture up by name should give its coordinates"
+        );
+        assert_eq!(
+            texture_atlas.texture_coordinates_for("no_such_texture"),
+            None,
+            "Unknown names should have no coordinates"
+        );
+    }
+
+    #[test]
+    fn voxel_types_sharing_a_texture_share_coordinates() {
+        let texture_atlas = TextureAtlas::init();
+        let voxel_registry = VoxelRegistry::init();
+        let [stone, glowing_ore] = [VoxelType::Stone, VoxelType::GlowingOre].map(|voxel_type| {
+            let texture = voxel_registry
+                .get_properties(&voxel_type)
+                .textures()
+                .texture(VoxelFace::Front);
+            texture_atlas.get_coordinates(texture).get()
+        });
+        assert_eq!(
+            stone,
+            texture_atlas
+                .texture_coordinates_for("stone")
+                .expect("The built-in atlas should have a stone texture"),
+            "Stone should use the stone texture"
+        );
+        assert_eq!(
+            stone, glowing_ore,
+            "Glowing ore is stone underneath its overlay"
+        );
+    }
+
+    #[test]
+    fn oversized_textures_grow_the_tile_and_smaller_ones_are_scaled_to_fill_it() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let blue = image::Rgba([0, 0, 255, 255]);
+        let texture_atlas = TextureAtlas::from_textures(
+            vec![
+                (
+                    TextureType::Stone,
+                    image::RgbaImage::from_pixel(16, 16, red),
+                ),
+                (
+                    TextureType::Dirt,
+                    image::RgbaImage::from_pixel(16, 40, blue),
+                ),
+            ],
+            Vec::new(),
+        );
+        let image = texture_atlas.image().to_rgba8();
+        assert_eq!(
+            image.dimensions(),
+            (80, 80),
+            "The tiles should grow to the tallest texture, two by two of them"
+        );
+
+        for (texture_type, color) in [(TextureType::Stone, red), (TextureType::Dirt, blue)] {
+            let (u_min, u_max, v_min, v_max) = texture_atlas.get_coordinates(texture_type).get();
+            let pixel = |u: f32, v: f32| {
+                image.get_pixel(
+                    (u * image.width() as f32) as u32,
+                    (v * image.height() as f32) as u32,
+                )
+            };
+            assert!(
+                [
+                    pixel(u_min, v_min),
+                    pixel(u_max - 0.001, v_max - 0.001),
+                    pixel(u_min, v_max - 0.001),
+                ]
+                .iter()
+                .all(|corner| **corner == color),
+                "The {texture_type:?} texture should fill its whole tile, not be cropped"
+            );
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::game::mesh::{DrawRange, Mesh, Vertex, visible_ranges};
+use crate::game::render::Frustum;
+
+pub(crate) struct MeshBuffers {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+    draw_ranges: Vec<DrawRange>,
+}
+
+impl MeshBuffers {
+    pub(crate) fn from_mesh(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mesh: &Mesh,
+    ) -> Option<Self> {
+        if mesh.is_empty() {
+            return None;
+        }
+
+        let mut mesh_buffers = Self {
+            vertex_buffer: create_vertex_buffer(device, mesh.vertices_u8().len() as u64),
+            index_buffer: create_index_buffer(device, mesh.indices_u8().len() as u64),
+            index_count: 0,
+            draw_ranges: Vec::new(),
+        };
+        mesh_buffers.write(device, queue, mesh);
+        Some(mesh_buffers)
+    }
+
+    pub(crate) fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, mesh: &Mesh) {
+        let (vertices, indices) = (mesh.vertices_u8(), mesh.indices_u8());
+        self.reserve(device, vertices.len() as u64, indices.len() as u64);
+        queue.write_buffer(&self.vertex_buffer, 0, vertices);
+        queue.write_buffer(&self.index_buffer, 0, indices);
+        self.index_count = mesh.index_count();
+        self.draw_ranges = mesh.draw_ranges().to_vec();
+    }
+
+    pub(crate) fn reserve(&mut self, device: &wgpu::Device, vertex_bytes: u64, index_bytes: u64) {
+        if vertex_bytes > self.vertex_buffer.size() {
+            self.vertex_buffer = create_vertex_buffer(device, vertex_bytes);
+        }
+        if index_bytes > self.index_buffer.size() {
+            self.index_buffer = create_index_buffer(device, index_bytes);
+        }
+    }
+
+    pub(crate) fn vertex_capacity(&self) -> u64 {
+        self.vertex_buffer.size()
+    }
+
+    pub(crate) fn index_capacity(&self) -> u64 {
+        self.index_buffer.size()
+    }
+
+    pub(crate) fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+    }
+
+    /// Draws only the merged-in meshes that touch `frustum`, or everything when the mesh has no
+    /// draw ranges to cull by.
+    pub(crate) fn draw_culled(&self, render_pass: &mut wgpu::RenderPass<'_>, frustum: &Frustum) {
+        if self.draw_ranges.is_empty() {
+            self.draw(render_pass);
+            return;
+        }
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        for indices in visible_ranges(&self.draw_ranges, |aabb| frustum.intersects(aabb)) {
+            render_pass.draw_indexed(indices, 0, 0..1);
+        }
+    }
+}
+
+fn create_vertex_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Vertex Buffer"),
+        size: buffer_capacity(size),
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_index_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Index Buffer"),
+        size: buffer_capacity(size),
+        usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn buffer_capacity(size: u64) -> u64 {
+    size.next_power_of_two().max(wgpu::COPY_BUFFER_ALIGNMENT)
+}
+
+/// Bytes per vertex as declared by `Vertex::buffer_layout`.
+const VERTEX_STRIDE: usize = size_of::<[f32; 19]>();
+const _: () = assert!(
+    size_of::<Vertex>() == VERTEX_STRIDE,
+    "Vertex fields and the declared stride should agree"
+);
+
+// Kept on the render side so that meshing never depends on wgpu
+impl Vertex {
+    pub(crate) fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: VERTEX_STRIDE as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 10]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 11]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 15]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 18]>() as wgpu::BufferAddress,
+                    shader_location: 10,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertex_attributes_tile_the_stride() {
+        let layout = Vertex::buffer_layout();
+        let mut expected_offset = 0;
+        for attribute in layout.attributes {
+            assert_eq!(
+                attribute.offset, expected_offset,
+                "Attribute {} should start where the previous one ends",
+                attribute.shader_location
+            );
+            expected_offset += attribute.format.size();
+        }
+        assert_eq!(
+            expected_offset, layout.array_stride,
+            "The attributes should cover the whole vertex"
+        );
+    }
+}
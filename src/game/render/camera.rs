@@ -1,17 +1,37 @@
-use crate::game::world::WorldPosition;
-use glam::f32::Vec3;
+use crate::game::render::Frustum;
+use crate::game::world::{World, WorldPosition};
+use glam::f32::{Mat4, Vec2, Vec3};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{PI, TAU};
+use std::time::{Duration, Instant};
 use wgpu::util::DeviceExt as _;
 use winit::keyboard::KeyCode;
 
 const CAMERA_STARTING_POSITION: Vec3 = Vec3::new(0.0, 33.0, 0.0);
-const CAMERA_FOV_Y: f32 = 90.0;
+/// Voxels above the terrain surface that the camera spawns at.
+pub(crate) const CAMERA_SPAWN_HEIGHT: f32 = 2.0;
+const CAMERA_UP: Vec3 = Vec3::Y;
+/// Vertical field of view in degrees.
+pub(crate) const CAMERA_FOV_Y: f32 = 90.0;
+const CAMERA_FOV_Y_MIN: f32 = 30.0;
+const CAMERA_FOV_Y_MAX: f32 = 120.0;
 const CAMERA_Z_NEAR: f32 = 0.1;
 const CAMERA_Z_FAR: f32 = 100.0;
-const CAMERA_MOVE_SPEED: f32 = 0.03;
-const CAMERA_MOVE_SPEED_SHIFT_MULTIPLIER: f32 = 3.5;
-const CAMERA_TURN_SPEED: f32 = 0.02;
+pub(crate) const CAMERA_MOVE_SPEED: f32 = 0.03;
+pub(crate) const CAMERA_SPRINT_MULTIPLIER: f32 = 3.5;
+/// Degrees added to the field of view while sprinting.
+pub(crate) const CAMERA_SPRINT_FOV_KICK: f32 = 4.0;
+/// How quickly the field of view kick eases toward its target, per second.
+const CAMERA_FOV_KICK_RATE: f32 = 10.0;
+/// Radians per second turned by the arrow keys.
+pub(crate) const CAMERA_TURN_SPEED: f32 = 1.2;
 const CAMERA_MAX_PITCH: f32 = f32::to_radians(89.9);
-const MOUSE_SENSITIVITY: f32 = 0.02;
+pub(crate) const MOUSE_SENSITIVITY: f32 = 0.02;
+const ORBIT_DISTANCE_MIN: f32 = 2.0;
+const ORBIT_DISTANCE_MAX: f32 = 128.0;
+/// Factor the orbit distance is scaled by per scroll step.
+const ORBIT_ZOOM_STEP: f32 = 1.15;
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
@@ -25,11 +45,127 @@ impl CameraUniform {
     }
 }
 
+/// The convention the view and projection are built in. Switching it mirrors the image, so the
+/// pipelines' front face has to switch with it for back-face culling to keep working.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Handedness {
+    #[default]
+    Right,
+    Left,
+}
+
+impl Handedness {
+    pub(crate) fn front_face(self) -> wgpu::FrontFace {
+        match self {
+            Self::Right => wgpu::FrontFace::Ccw,
+            Self::Left => wgpu::FrontFace::Cw,
+        }
+    }
+
+    /// Increasing yaw turns right in right-handed space, so it is negated when mirrored.
+    fn yaw_sign(self) -> f32 {
+        match self {
+            Self::Right => 1.0,
+            Self::Left => -1.0,
+        }
+    }
+
+    fn right(self, forward: Vec3, up: Vec3) -> Vec3 {
+        match self {
+            Self::Right => forward.cross(up).normalize(),
+            Self::Left => up.cross(forward).normalize(),
+        }
+    }
+
+    pub(crate) fn look_at(self, eye: Vec3, target: Vec3, up: Vec3) -> glam::Mat4 {
+        match self {
+            Self::Right => glam::Mat4::look_at_rh(eye, target, up),
+            Self::Left => glam::Mat4::look_at_lh(eye, target, up),
+        }
+    }
+
+    pub(crate) fn perspective(
+        self,
+        fov_y: f32,
+        aspect_ratio: f32,
+        z_near: f32,
+        z_far: f32,
+    ) -> glam::Mat4 {
+        match self {
+            Self::Right => glam::Mat4::perspective_rh(fov_y, aspect_ratio, z_near, z_far),
+            Self::Left => glam::Mat4::perspective_lh(fov_y, aspect_ratio, z_near, z_far),
+        }
+    }
+}
+
+/// How depth is laid out between the clip planes. Reversed puts the far plane at 0 and the near
+/// plane at 1, which pairs with a float depth buffer to spread precision evenly with distance and
+/// stops distant chunks from z-fighting at large render distances.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DepthMode {
+    #[default]
+    Standard,
+    Reversed,
+}
+
+impl DepthMode {
+    /// The depth of nothing drawn yet, which every fragment should pass against.
+    pub(crate) fn clear_value(self) -> f32 {
+        match self {
+            Self::Standard => 1.0,
+            Self::Reversed => 0.0,
+        }
+    }
+
+    /// Passes fragments nearer than what is stored, or also equally near with `or_equal`.
+    pub(crate) fn compare(self, or_equal: bool) -> wgpu::CompareFunction {
+        match (self, or_equal) {
+            (Self::Standard, false) => wgpu::CompareFunction::Less,
+            (Self::Standard, true) => wgpu::CompareFunction::LessEqual,
+            (Self::Reversed, false) => wgpu::CompareFunction::Greater,
+            (Self::Reversed, true) => wgpu::CompareFunction::GreaterEqual,
+        }
+    }
+
+    /// The `(near, far)` to build the projection with. Swapping them maps the near plane to 1.
+    pub(crate) fn clip_planes(self, z_near: f32, z_far: f32) -> (f32, f32) {
+        match self {
+            Self::Standard => (z_near, z_far),
+            Self::Reversed => (z_far, z_near),
+        }
+    }
+}
+
+struct CameraAnimation {
+    start_position: Vec3,
+    start_yaw: f32,
+    start_pitch: f32,
+    target_position: Vec3,
+    target_yaw: f32,
+    target_pitch: f32,
+    start_time: Instant,
+    duration: Duration,
+}
+
 pub(crate) struct Camera {
     position: Vec3,
+    up: Vec3,
     yaw: f32,
     pitch: f32,
+    viewport_size: Vec2,
     aspect_ratio: f32,
+    fov_y: f32,
+    /// Degrees added on top of `fov_y` by effects such as sprinting.
+    fov_kick: f32,
+    z_near: f32,
+    z_far: f32,
+    handedness: Handedness,
+    depth_mode: DepthMode,
+    animation: Option<CameraAnimation>,
+    /// Held in place of the live view-projection for culling while inspecting it.
+    frozen_view_projection: Option<Mat4>,
     uniform: CameraUniform,
     buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
@@ -40,12 +176,16 @@ impl Camera {
         device: &wgpu::Device,
         surface_config: &wgpu::SurfaceConfiguration,
         bind_group_layout: &wgpu::BindGroupLayout,
+        handedness: Handedness,
+        depth_mode: DepthMode,
     ) -> Self {
         let position = CAMERA_STARTING_POSITION;
+        let up = CAMERA_UP;
         let yaw: f32 = 0.0;
         let pitch: f32 = 0.0;
 
-        let aspect_ratio = surface_config.width as f32 / surface_config.height as f32;
+        let viewport_size = Vec2::new(surface_config.width as f32, surface_config.height as f32);
+        let aspect_ratio = viewport_size.x / viewport_size.y;
 
         let forward = Vec3::new(
             yaw.cos() * pitch.cos(),
@@ -54,9 +194,15 @@ impl Camera {
         )
         .normalize();
         let target = position + forward;
-        let view_matrix = glam::Mat4::look_at_rh(position, target, Vec3::Y);
-        let projection =
-            glam::Mat4::perspective_rh(CAMERA_FOV_Y, aspect_ratio, CAMERA_Z_NEAR, CAMERA_Z_FAR);
+        let view_matrix = handedness.look_at(position, target, up);
+        let (fov_y, z_near, z_far) = (CAMERA_FOV_Y, CAMERA_Z_NEAR, CAMERA_Z_FAR);
+        let (projection_near, projection_far) = depth_mode.clip_planes(z_near, z_far);
+        let projection = handedness.perspective(
+            fov_y.to_radians(),
+            aspect_ratio,
+            projection_near,
+            projection_far,
+        );
         let view_projection = projection * view_matrix;
 
         let uniform = CameraUniform::new(view_projection.to_cols_array_2d());
@@ -78,9 +224,19 @@ impl Camera {
 
         Self {
             position,
+            up,
             yaw,
             pitch,
+            viewport_size,
             aspect_ratio,
+            fov_y,
+            fov_kick: 0.0,
+            z_near,
+            z_far,
+            handedness,
+            depth_mode,
+            animation: None,
+            frozen_view_projection: None,
             uniform,
             buffer,
             bind_group,
@@ -92,20 +248,60 @@ impl Camera {
     }
 
     pub(crate) fn resize(&mut self, width: u32, height: u32) {
-        self.aspect_ratio = width as f32 / height as f32;
+        self.viewport_size = Vec2::new(width as f32, height as f32);
+        self.aspect_ratio = self.viewport_size.x / self.viewport_size.y;
         self.update_view_projection();
     }
 
     fn view_matrix(&self) -> glam::Mat4 {
         let target = self.position + self.forward();
-        glam::Mat4::look_at_rh(self.position, target, Vec3::Y)
+        self.handedness.look_at(self.position, target, self.up)
     }
 
     fn projection(&self) -> glam::Mat4 {
-        glam::Mat4::perspective_rh(CAMERA_FOV_Y, self.aspect_ratio, CAMERA_Z_NEAR, CAMERA_Z_FAR)
+        let (z_near, z_far) = self.depth_mode.clip_planes(self.z_near, self.z_far);
+        let fov_y = (self.fov_y + self.fov_kick).clamp(CAMERA_FOV_Y_MIN, CAMERA_FOV_Y_MAX);
+        self.handedness
+            .perspective(fov_y.to_radians(), self.aspect_ratio, z_near, z_far)
+    }
+
+    /// Vertical field of view in degrees.
+    pub(crate) fn fov_y(&self) -> f32 {
+        self.fov_y
+    }
+
+    /// Sets the vertical field of view in degrees, clamped to a usable range. `resize` rebuilds
+    /// the projection from this value, so it survives window size changes.
+    pub(crate) fn set_fov_y(&mut self, fov_y: f32) {
+        if !fov_y.is_finite() {
+            warn!("Ignoring non-finite camera field of view: {fov_y}");
+            return;
+        }
+
+        self.fov_y = fov_y.clamp(CAMERA_FOV_Y_MIN, CAMERA_FOV_Y_MAX);
+        self.update_view_projection();
+    }
+
+    pub(crate) fn set_clip_planes(&mut self, z_near: f32, z_far: f32) {
+        if !(z_near > 0.0 && z_near < z_far && z_far.is_finite()) {
+            warn!("Ignoring invalid camera clip planes: near {z_near}, far {z_far}");
+            return;
+        }
+
+        self.z_near = z_near;
+        self.z_far = z_far;
+        self.update_view_projection();
+    }
+
+    pub(crate) fn z_near(&self) -> f32 {
+        self.z_near
+    }
+
+    pub(crate) fn z_far(&self) -> f32 {
+        self.z_far
     }
 
-    fn forward(&self) -> Vec3 {
+    pub(crate) fn forward(&self) -> Vec3 {
         Vec3::new(
             self.yaw.cos() * self.pitch.cos(),
             self.pitch.sin(),
@@ -114,17 +310,123 @@ impl Camera {
         .normalize()
     }
 
-    fn right(&self) -> Vec3 {
-        self.forward().cross(Vec3::Y).normalize()
+    pub(crate) fn right(&self) -> Vec3 {
+        self.handedness.right(self.forward(), self.up)
+    }
+
+    /// The ray through the center of the screen as `(origin, direction)`.
+    pub(crate) fn ray(&self) -> (Vec3, Vec3) {
+        (self.position, self.forward())
+    }
+
+    /// The ray through the pixel at `(x, y)`, measured from the top-left of the viewport, as
+    /// `(origin, direction)`.
+    pub(crate) fn screen_ray(&self, x: f32, y: f32) -> (Vec3, Vec3) {
+        let ndc_x = 2.0 * x / self.viewport_size.x - 1.0;
+        let ndc_y = 1.0 - 2.0 * y / self.viewport_size.y;
+        let inverse_view_projection = (self.projection() * self.view_matrix()).inverse();
+        let far_depth = self.depth_mode.clear_value();
+        let near = inverse_view_projection.project_point3(Vec3::new(ndc_x, ndc_y, 1.0 - far_depth));
+        let far = inverse_view_projection.project_point3(Vec3::new(ndc_x, ndc_y, far_depth));
+        (self.position, (far - near).normalize_or(self.forward()))
     }
 
     pub(crate) fn position(&self) -> WorldPosition {
-        let (x, y, z) = (
-            self.position.x as i32,
-            self.position.y as i32,
-            self.position.z as i32,
-        );
-        WorldPosition::new(x, y, z)
+        WorldPosition::from_vec3(self.position)
+    }
+
+    /// Smoothly flies the camera to `target` while turning to face `look`.
+    ///
+    /// Manual controller input is ignored until the animation finishes so that scripted
+    /// flythroughs always end at the same pose.
+    pub(crate) fn move_to(&mut self, target: Vec3, look: Vec3, duration: Duration) {
+        let direction = (look - target).normalize_or(self.forward());
+        let target_pitch = direction
+            .y
+            .asin()
+            .clamp(-CAMERA_MAX_PITCH, CAMERA_MAX_PITCH);
+        let mut target_yaw = direction.z.atan2(direction.x);
+
+        // Turn the short way around
+        let yaw_difference = (target_yaw - self.yaw).rem_euclid(TAU);
+        target_yaw = if yaw_difference > PI {
+            self.yaw + yaw_difference - TAU
+        } else {
+            self.yaw + yaw_difference
+        };
+
+        self.animation = Some(CameraAnimation {
+            start_position: self.position,
+            start_yaw: self.yaw,
+            start_pitch: self.pitch,
+            target_position: target,
+            target_yaw,
+            target_pitch,
+            start_time: Instant::now(),
+            duration,
+        });
+    }
+
+    /// Jumps straight to `position`, cancelling any `move_to` animation.
+    pub(crate) fn set_position(&mut self, position: Vec3) {
+        self.position = position;
+        self.animation = None;
+        self.update_view_projection();
+    }
+
+    pub(crate) fn update_animation(&mut self) -> bool {
+        let Some(animation) = &self.animation else {
+            return false;
+        };
+
+        let elapsed = animation.start_time.elapsed();
+        let progress = if animation.duration.is_zero() {
+            1.0
+        } else {
+            (elapsed.as_secs_f32() / animation.duration.as_secs_f32()).min(1.0)
+        };
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+
+        self.position = animation
+            .start_position
+            .lerp(animation.target_position, eased);
+        self.yaw = animation.start_yaw + (animation.target_yaw - animation.start_yaw) * eased;
+        self.pitch =
+            animation.start_pitch + (animation.target_pitch - animation.start_pitch) * eased;
+
+        if progress >= 1.0 {
+            self.animation = None;
+        }
+
+        self.update_view_projection();
+        true
+    }
+
+    pub(crate) fn exact_position(&self) -> Vec3 {
+        self.position
+    }
+
+    /// What culling tests against: the frozen pose when there is one, otherwise the camera's
+    /// current view.
+    pub(crate) fn culling_frustum(&self) -> Frustum {
+        Frustum::from_view_projection(
+            self.frozen_view_projection
+                .unwrap_or_else(|| self.projection() * self.view_matrix()),
+        )
+    }
+
+    pub(crate) fn is_frustum_frozen(&self) -> bool {
+        self.frozen_view_projection.is_some()
+    }
+
+    /// Freezes the culling frustum at the current pose, or releases it, while rendering keeps
+    /// following the camera. Returns whether it is now frozen.
+    pub(crate) fn toggle_frozen_frustum(&mut self) -> bool {
+        self.frozen_view_projection = match self.frozen_view_projection {
+            Some(_) => None,
+            None => Some(self.projection() * self.view_matrix()),
+        };
+        self.frozen_view_projection.is_some()
     }
 
     fn update_view_projection(&mut self) {
@@ -136,8 +438,21 @@ impl Camera {
     }
 }
 
+/// A fixed point the camera circles at some distance, looking at it from the direction its
+/// yaw and pitch describe.
+struct Orbit {
+    focus: Vec3,
+    distance: f32,
+}
+
 pub(crate) struct CameraController {
-    mouse_sensitivity: f32,
+    sensitivity_x: f32,
+    sensitivity_y: f32,
+    move_speed: f32,
+    vertical_move_speed: f32,
+    sprint_multiplier: f32,
+    sprint_fov_kick: f32,
+    turn_speed: f32,
     mouse_delta: (f32, f32),
     is_forward_pressed: bool,
     is_backward_pressed: bool,
@@ -150,12 +465,20 @@ pub(crate) struct CameraController {
     is_turn_right_pressed: bool,
     is_turn_up_pressed: bool,
     is_turn_down_pressed: bool,
+    is_solid_clamp_enabled: bool,
+    orbit: Option<Orbit>,
 }
 
 impl CameraController {
     pub(crate) fn new() -> Self {
         Self {
-            mouse_sensitivity: MOUSE_SENSITIVITY,
+            sensitivity_x: MOUSE_SENSITIVITY,
+            sensitivity_y: MOUSE_SENSITIVITY,
+            move_speed: CAMERA_MOVE_SPEED,
+            vertical_move_speed: CAMERA_MOVE_SPEED,
+            sprint_multiplier: CAMERA_SPRINT_MULTIPLIER,
+            sprint_fov_kick: CAMERA_SPRINT_FOV_KICK,
+            turn_speed: CAMERA_TURN_SPEED,
             mouse_delta: (0.0, 0.0),
             is_forward_pressed: false,
             is_backward_pressed: false,
@@ -168,13 +491,129 @@ impl CameraController {
             is_turn_right_pressed: false,
             is_turn_up_pressed: false,
             is_turn_down_pressed: false,
+            is_solid_clamp_enabled: false,
+            orbit: None,
         }
     }
 
+    /// Radians turned per unit of horizontal mouse movement.
+    pub(crate) fn set_sensitivity_x(&mut self, sensitivity_x: f32) {
+        self.sensitivity_x = sensitivity_x;
+    }
+
+    /// Radians turned per unit of vertical mouse movement.
+    pub(crate) fn set_sensitivity_y(&mut self, sensitivity_y: f32) {
+        self.sensitivity_y = sensitivity_y;
+    }
+
+    /// Speed along the view direction and sideways.
+    pub(crate) fn set_move_speed(&mut self, move_speed: f32) {
+        self.move_speed = move_speed;
+    }
+
+    /// Speed when rising or sinking straight up and down.
+    pub(crate) fn set_vertical_move_speed(&mut self, vertical_move_speed: f32) {
+        self.vertical_move_speed = vertical_move_speed;
+    }
+
+    /// Scales both move speeds while sprint is held.
+    pub(crate) fn set_sprint_multiplier(&mut self, sprint_multiplier: f32) {
+        self.sprint_multiplier = sprint_multiplier;
+    }
+
+    /// Degrees the field of view widens by while sprint is held.
+    pub(crate) fn set_sprint_fov_kick(&mut self, sprint_fov_kick: f32) {
+        self.sprint_fov_kick = sprint_fov_kick;
+    }
+
+    /// Radians per second turned by the arrow keys.
+    pub(crate) fn set_turn_speed(&mut self, turn_speed: f32) {
+        self.turn_speed = turn_speed;
+    }
+
+    /// Keeps flying from entering solid voxels. Blocked moves slide along the surface instead.
+    pub(crate) fn set_solid_clamp_enabled(&mut self, is_solid_clamp_enabled: bool) {
+        self.is_solid_clamp_enabled = is_solid_clamp_enabled;
+    }
+
+    pub(crate) fn toggle_solid_clamp(&mut self) -> bool {
+        self.is_solid_clamp_enabled = !self.is_solid_clamp_enabled;
+        self.is_solid_clamp_enabled
+    }
+
+    /// Switches to circling `focus` from the camera's current distance, turning to face it, or
+    /// back to flying from wherever the orbit left the camera. Returns whether it now orbits.
+    pub(crate) fn toggle_orbit_mode(&mut self, camera: &mut Camera, focus: Vec3) -> bool {
+        if self.orbit.take().is_some() {
+            return false;
+        }
+
+        let offset = focus - camera.position;
+        let direction = offset.normalize_or(camera.forward());
+        camera.yaw = direction.z.atan2(direction.x).rem_euclid(TAU);
+        camera.pitch = direction
+            .y
+            .asin()
+            .clamp(-CAMERA_MAX_PITCH, CAMERA_MAX_PITCH);
+        self.orbit = Some(Orbit {
+            focus,
+            distance: offset
+                .length()
+                .clamp(ORBIT_DISTANCE_MIN, ORBIT_DISTANCE_MAX),
+        });
+        true
+    }
+
+    /// Moves an orbiting camera closer for negative steps and farther for positive ones.
+    /// Returns whether the scroll was used.
+    pub(crate) fn zoom_orbit(&mut self, steps: i32) -> bool {
+        let Some(orbit) = &mut self.orbit else {
+            return false;
+        };
+        orbit.distance = (orbit.distance * ORBIT_ZOOM_STEP.powi(steps))
+            .clamp(ORBIT_DISTANCE_MIN, ORBIT_DISTANCE_MAX);
+        true
+    }
+
     pub(crate) fn handle_mouse_input(&mut self, delta_x: f32, delta_y: f32) {
         self.mouse_delta = (delta_x, delta_y);
     }
 
+    /// Drops the mouse movement received since the last frame, for frames where the camera
+    /// is driven by something other than the controller.
+    pub(crate) fn discard_mouse_input(&mut self) {
+        self.mouse_delta = (0.0, 0.0);
+    }
+
+    /// The yaw and pitch to turn by this frame from the arrow keys and the mouse movement
+    /// since the last call, which is then cleared.
+    fn take_turn(&mut self, frame_time: f32) -> (f32, f32) {
+        let turn_angle = self.turn_speed * frame_time;
+        let mut yaw_delta = 0.0;
+        let mut pitch_delta = 0.0;
+        if self.is_turn_left_pressed {
+            yaw_delta -= turn_angle;
+        }
+        if self.is_turn_right_pressed {
+            yaw_delta += turn_angle;
+        }
+        if self.is_turn_up_pressed {
+            pitch_delta += turn_angle;
+        }
+        if self.is_turn_down_pressed {
+            pitch_delta -= turn_angle;
+        }
+
+        // Some platforms report garbage deltas while devices change, and a single NaN would
+        // poison the orientation for good
+        let (delta_x, delta_y) = std::mem::take(&mut self.mouse_delta);
+        if delta_x.is_finite() && delta_y.is_finite() {
+            yaw_delta += delta_x * self.sensitivity_x;
+            pitch_delta -= delta_y * self.sensitivity_y;
+        }
+        (yaw_delta, pitch_delta)
+    }
+
     pub(crate) fn handle_keyboard_input(&mut self, code: KeyCode, is_pressed: bool) -> bool {
         match code {
             KeyCode::KeyW => {
@@ -225,35 +664,42 @@ impl CameraController {
         }
     }
 
-    pub(crate) fn update_camera(&mut self, camera: &mut Camera) {
-        if self.is_turn_left_pressed {
-            camera.yaw -= CAMERA_TURN_SPEED;
-        }
-        if self.is_turn_right_pressed {
-            camera.yaw += CAMERA_TURN_SPEED;
-        }
-        if self.is_turn_up_pressed {
-            camera.pitch += CAMERA_TURN_SPEED;
-        }
-        if self.is_turn_down_pressed {
-            camera.pitch -= CAMERA_TURN_SPEED;
-        }
-
-        let (delta_x, delta_y) = self.mouse_delta;
-        camera.yaw += delta_x * self.mouse_sensitivity;
-        camera.pitch -= delta_y * self.mouse_sensitivity;
-        self.mouse_delta = (0.0, 0.0);
+    /// Mouse look and movement are applied per call, while arrow-key turning is scaled by
+    /// `frame_time` so it turns at the same rate at any frame rate.
+    pub(crate) fn update_camera(&mut self, camera: &mut Camera, world: &World, frame_time: f32) {
+        let start = camera.position;
+        let (yaw_delta, pitch_delta) = self.take_turn(frame_time);
+        (camera.yaw, camera.pitch) = turned(
+            (camera.yaw, camera.pitch),
+            yaw_delta * camera.handedness.yaw_sign(),
+            pitch_delta,
+        );
 
-        camera.pitch = camera.pitch.clamp(-CAMERA_MAX_PITCH, CAMERA_MAX_PITCH);
+        // Orbiting only turns; the position follows from the orientation
+        if let Some(orbit) = &self.orbit {
+            camera.position = orbit.focus - camera.forward() * orbit.distance;
+            camera.update_view_projection();
+            return;
+        }
 
         let forward = camera.forward();
         let right = camera.right();
+        let up = camera.up;
 
-        let move_speed = if self.is_sprint_pressed {
-            CAMERA_MOVE_SPEED * CAMERA_MOVE_SPEED_SHIFT_MULTIPLIER
+        let sprint_multiplier = if self.is_sprint_pressed {
+            self.sprint_multiplier
         } else {
-            CAMERA_MOVE_SPEED
+            1.0
         };
+        let target_fov_kick = if self.is_sprint_pressed {
+            self.sprint_fov_kick
+        } else {
+            0.0
+        };
+        camera.fov_kick = eased_fov_kick(camera.fov_kick, target_fov_kick, frame_time);
+
+        let move_speed = self.move_speed * sprint_multiplier;
+        let vertical_move_speed = self.vertical_move_speed * sprint_multiplier;
 
         if self.is_forward_pressed {
             camera.position += forward * move_speed;
@@ -268,12 +714,183 @@ impl CameraController {
             camera.position -= right * move_speed;
         }
         if self.is_up_pressed {
-            camera.position += Vec3::Y * move_speed;
+            camera.position += up * vertical_move_speed;
         }
         if self.is_down_pressed {
-            camera.position -= Vec3::Y * move_speed;
+            camera.position -= up * vertical_move_speed;
         }
 
+        if self.is_solid_clamp_enabled {
+            camera.position = Self::slide(world, start, camera.position);
+        }
         camera.update_view_projection();
     }
+
+    /// Applies the move one axis at a time, dropping each axis that would end inside a solid
+    /// voxel so that the rest of the move slides along the surface. A camera that already
+    /// starts inside a solid voxel moves freely so it can get out.
+    fn slide(world: &World, start: Vec3, target: Vec3) -> Vec3 {
+        let is_solid = |position| world.get_is_solid(WorldPosition::from_vec3(position));
+        if is_solid(start) {
+            return target;
+        }
+
+        let mut position = start;
+        for axis in 0..3 {
+            let mut candidate = position;
+            candidate[axis] = target[axis];
+            if !is_solid(candidate) {
+                position = candidate;
+            }
+        }
+        position
+    }
+}
+
+/// The field of view kick after easing toward `target` for `frame_time` seconds, closing the
+/// same share of the gap in the same time at any frame rate.
+fn eased_fov_kick(fov_kick: f32, target: f32, frame_time: f32) -> f32 {
+    let eased = fov_kick + (target - fov_kick) * (1.0 - (-CAMERA_FOV_KICK_RATE * frame_time).exp());
+    if (target - eased).abs() < 0.01 {
+        target
+    } else {
+        eased
+    }
+}
+
+/// `(yaw, pitch)` after turning, with yaw wrapped into `[0, 2π)` so it never grows without
+/// bound and pitch kept short of straight up or down.
+fn turned((yaw, pitch): (f32, f32), yaw_delta: f32, pitch_delta: f32) -> (f32, f32) {
+    (
+        (yaw + yaw_delta).rem_euclid(TAU),
+        (pitch + pitch_delta).clamp(-CAMERA_MAX_PITCH, CAMERA_MAX_PITCH),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_finite_mouse_deltas_are_ignored() {
+        let mut camera_controller = CameraController::new();
+        for (delta_x, delta_y) in [(f32::NAN, 1.0), (1.0, f32::INFINITY)] {
+            camera_controller.handle_mouse_input(delta_x, delta_y);
+            let (yaw_delta, pitch_delta) = camera_controller.take_turn(1.0 / 60.0);
+            assert_eq!(
+                (yaw_delta, pitch_delta),
+                (0.0, 0.0),
+                "A non-finite mouse delta should not turn the camera"
+            );
+            let (yaw, pitch) = turned((1.0, 0.5), yaw_delta, pitch_delta);
+            assert!(
+                yaw.is_finite() && pitch.is_finite(),
+                "The orientation should stay finite"
+            );
+        }
+
+        camera_controller.handle_mouse_input(2.0, 0.0);
+        let (yaw_delta, _) = camera_controller.take_turn(1.0 / 60.0);
+        assert!(
+            yaw_delta > 0.0,
+            "A finite delta after a bad one should still turn the camera"
+        );
+    }
+
+    #[test]
+    fn mouse_input_discarded_during_an_animation_does_not_turn_the_camera_afterwards() {
+        let mut camera_controller = CameraController::new();
+        camera_controller.handle_mouse_input(40.0, -25.0);
+        camera_controller.discard_mouse_input();
+        assert_eq!(
+            camera_controller.take_turn(1.0 / 60.0),
+            (0.0, 0.0),
+            "Mouse movement from an animation frame should not be applied on the next frame"
+        );
+
+        camera_controller.handle_mouse_input(2.0, 0.0);
+        let (yaw_delta, _) = camera_controller.take_turn(1.0 / 60.0);
+        assert!(
+            yaw_delta > 0.0,
+            "Mouse movement after the animation should still turn the camera"
+        );
+    }
+
+    #[test]
+    fn turning_wraps_yaw_and_clamps_pitch() {
+        let (yaw, pitch) = turned((TAU - 0.1, 0.0), 0.3, 10.0);
+        assert!(
+            (yaw - 0.2).abs() < 1e-5,
+            "Turning past a full circle should wrap back to near zero, got {yaw}"
+        );
+        assert_eq!(
+            pitch, CAMERA_MAX_PITCH,
+            "Pitch should stop short of straight up"
+        );
+
+        let (yaw, pitch) = turned((0.1, 0.0), -0.3, -10.0);
+        assert!(
+            (yaw - (TAU - 0.2)).abs() < 1e-5,
+            "Turning back past zero should wrap to near a full circle, got {yaw}"
+        );
+        assert_eq!(
+            pitch, -CAMERA_MAX_PITCH,
+            "Pitch should stop short of straight down"
+        );
+    }
+
+    #[test]
+    fn fov_kick_eases_by_elapsed_time_not_frame_rate() {
+        let kick_after = |frame_time: f32, frames: u32| {
+            (0..frames).fold(0.0, |fov_kick, _| {
+                eased_fov_kick(fov_kick, CAMERA_SPRINT_FOV_KICK, frame_time)
+            })
+        };
+
+        // A quarter of a second at 24 and at 144 frames per second
+        let slow = kick_after(1.0 / 24.0, 6);
+        let fast = kick_after(1.0 / 144.0, 36);
+        assert!(
+            slow > 0.0 && slow < CAMERA_SPRINT_FOV_KICK,
+            "The kick should still be easing in after a quarter of a second, got {slow}"
+        );
+        assert!(
+            (slow - fast).abs() < 1e-4,
+            "The kick should have grown by the same amount at 24 and 144 fps, got {slow} and {fast}"
+        );
+        assert_eq!(
+            kick_after(1.0 / 60.0, 120),
+            CAMERA_SPRINT_FOV_KICK,
+            "The kick should settle on its target"
+        );
+    }
+
+    #[test]
+    fn arrow_key_turning_depends_on_elapsed_time_not_frame_rate() {
+        let total_turn = |frame_time: f32, frames: u32| {
+            let mut camera_controller = CameraController::new();
+            camera_controller.handle_keyboard_input(KeyCode::ArrowRight, true);
+            camera_controller.handle_keyboard_input(KeyCode::ArrowUp, true);
+            (0..frames).fold((0.0, 0.0), |(yaw, pitch), _| {
+                let (yaw_delta, pitch_delta) = camera_controller.take_turn(frame_time);
+                (yaw + yaw_delta, pitch + pitch_delta)
+            })
+        };
+
+        // Half a second at 30 and at 144 frames per second
+        let (slow_yaw, slow_pitch) = total_turn(1.0 / 30.0, 15);
+        let (fast_yaw, fast_pitch) = total_turn(1.0 / 144.0, 72);
+        let expected = CAMERA_TURN_SPEED * 0.5;
+        for (turn, name) in [
+            (slow_yaw, "yaw at 30 fps"),
+            (fast_yaw, "yaw at 144 fps"),
+            (slow_pitch, "pitch at 30 fps"),
+            (fast_pitch, "pitch at 144 fps"),
+        ] {
+            assert!(
+                (turn - expected).abs() < 1e-4,
+                "The total {name} should be {expected} radians, got {turn}"
+            );
+        }
+    }
 }
@@ -0,0 +1,94 @@
+use crate::game::render::Frustum;
+use crate::game::world::{Aabb, World};
+use wgpu::util::DeviceExt as _;
+
+const MESHED_CHUNK_COLOR: [f32; 3] = [1.0, 0.85, 0.0];
+const EMPTY_CHUNK_COLOR: [f32; 3] = [0.45, 0.45, 0.45];
+const CULLED_CHUNK_COLOR: [f32; 3] = [0.9, 0.1, 0.1];
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct DebugVertex {
+    position: [f32; 3],
+    color: [f32; 3],
+}
+
+impl DebugVertex {
+    pub(crate) fn new(position: [f32; 3], color: [f32; 3]) -> Self {
+        Self { position, color }
+    }
+
+    pub(crate) fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
+
+pub(crate) struct ChunkBounds {
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+}
+
+impl ChunkBounds {
+    /// Chunks outside `culling_frustum`, when one is given, are outlined in red.
+    pub(crate) fn from_world(
+        device: &wgpu::Device,
+        world: &World,
+        culling_frustum: Option<&Frustum>,
+    ) -> Option<Self> {
+        let vertices = world
+            .chunk_data()
+            .keys()
+            .flat_map(|chunk_position| {
+                let aabb = Aabb::from_chunk(*chunk_position);
+                let color = match world.chunk_meshes().get(chunk_position) {
+                    _ if culling_frustum.is_some_and(|frustum| !frustum.intersects(&aabb)) => {
+                        CULLED_CHUNK_COLOR
+                    }
+                    Some(chunk_mesh) if chunk_mesh.index_count() > 0 => MESHED_CHUNK_COLOR,
+                    _ => EMPTY_CHUNK_COLOR,
+                };
+                aabb.edges().into_iter().flat_map(move |(start, end)| {
+                    [
+                        DebugVertex::new(start.to_array(), color),
+                        DebugVertex::new(end.to_array(), color),
+                    ]
+                })
+            })
+            .collect::<Vec<DebugVertex>>();
+
+        if vertices.is_empty() {
+            return None;
+        }
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Chunk Bounds Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        Some(Self {
+            vertex_buffer,
+            vertex_count: vertices.len() as u32,
+        })
+    }
+
+    pub(crate) fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::game::render::DepthMode;
+use anyhow::Context as _;
+use std::path::Path;
+use std::sync::mpsc;
+
+const DEPTH_CAPTURE_SHADER: wgpu::ShaderModuleDescriptor<'_> =
+    wgpu::include_wgsl!("../../../shaders/depth_capture.wgsl");
+
+/// Not every backend can copy depth formats out directly, so depth is first drawn into a
+/// color target of this format.
+const DEPTH_CAPTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Float;
+
+/// A copy of the depth buffer on its way back from the GPU, for inspecting depth precision
+/// and z-fighting.
+pub(crate) struct DepthCapture {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+}
+
+impl DepthCapture {
+    /// Records copying the depth in `depth_view` into a readable buffer. The pipeline is built
+    /// on the spot since captures are rare.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        depth_view: &wgpu::TextureView,
+    ) -> Self {
+        let depth_texture = depth_view.texture();
+        let size = depth_texture.size();
+        // Formats with stencil can only be bound through their depth aspect
+        let depth_only_view = depth_texture.create_view(&wgpu::TextureViewDescriptor {
+            aspect: wgpu::TextureAspect::DepthOnly,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Depth,
+                },
+                count: None,
+            }],
+            label: Some("depth_capture_bind_group_layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&depth_only_view),
+            }],
+            label: Some("depth_capture_bind_group"),
+        });
+
+        let pipeline = create_depth_capture_pipeline(device, &bind_group_layout);
+
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Capture Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_CAPTURE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Depth Capture Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.create_view(&wgpu::TextureViewDescriptor::default()),
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+            multiview_mask: None,
+        });
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        drop(render_pass);
+
+        // Rows of a texture copy have to start on aligned offsets
+        let padded_bytes_per_row =
+            (size.width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Depth Capture Buffer"),
+            size: u64::from(padded_bytes_per_row * size.height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            target.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+
+        Self {
+            buffer,
+            width: size.width,
+            height: size.height,
+            padded_bytes_per_row,
+        }
+    }
+
+    /// Waits for the submitted copy and writes it as a 16-bit grayscale PNG, linearized so
+    /// that black is the near plane and white the far plane.
+    pub(crate) fn save_png(
+        &self,
+        device: &wgpu::Device,
+        depth_mode: DepthMode,
+        (z_near, z_far): (f32, f32),
+        path: &Path,
+    ) -> anyhow::Result<()> {
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).ok();
+        });
+        device.poll(wgpu::PollType::wait_indefinitely())?;
+        receiver.recv()??;
+
+        let data = slice.get_mapped_range();
+        let pixels = data
+            .chunks_exact(self.padded_bytes_per_row as usize)
+            .flat_map(|row| row.chunks_exact(4).take(self.width as usize))
+            .map(|texel| {
+                let depth = bytemuck::pod_read_unaligned::<f32>(texel);
+                let brightness = linearize_depth(depth, depth_mode, z_near, z_far);
+                (brightness * f32::from(u16::MAX)).round() as u16
+            })
+            .collect::<Vec<u16>>();
+        drop(data);
+        self.buffer.unmap();
+
+        let image =
+            image::ImageBuffer::<image::Luma<u16>, _>::from_raw(self.width, self.height, pixels)
+                .context("Depth capture is smaller than its size")?;
+        image.save_with_format(path, image::ImageFormat::Png)?;
+        Ok(())
+    }
+}
+
+fn create_depth_capture_pipeline(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(DEPTH_CAPTURE_SHADER);
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Depth Capture Pipeline Layout"),
+        bind_group_layouts: &[bind_group_layout],
+        immediate_size: 0,
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Depth Capture Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: DEPTH_CAPTURE_FORMAT,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        cache: None,
+        multiview_mask: None,
+    })
+}
+
+/// Turns a stored depth back into distance from the camera, scaled to `0.0..=1.0` between the
+/// clip planes.
+fn linearize_depth(depth: f32, depth_mode: DepthMode, z_near: f32, z_far: f32) -> f32 {
+    let span = z_far - z_near;
+    let distance = match depth_mode {
+        DepthMode::Standard => z_near * z_far / (z_far - depth * span),
+        DepthMode::Reversed => z_near * z_far / (z_near + depth * span),
+    };
+    ((distance - z_near) / span).clamp(0.0, 1.0)
+}
@@ -0,0 +1,33 @@
+use crate::game::world::Aabb;
+use glam::{Mat4, Vec3, Vec4};
+
+/// The six planes bounding what a view-projection matrix can see, pointing inward, for
+/// wgpu's clip space where depth runs from 0 to 1.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    pub(crate) fn from_view_projection(view_projection: Mat4) -> Self {
+        let (x, y, z, w) = (
+            view_projection.row(0),
+            view_projection.row(1),
+            view_projection.row(2),
+            view_projection.row(3),
+        );
+        Self {
+            planes: [w + x, w - x, w + y, w - y, z, w - z],
+        }
+    }
+
+    /// Conservative: boxes near a frustum corner can pass without actually being visible.
+    pub(crate) fn intersects(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The box corner farthest along the plane normal
+            let normal = plane.truncate();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max(), aabb.min());
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
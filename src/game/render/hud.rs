@@ -0,0 +1,266 @@
+use wgpu::util::DeviceExt as _;
+
+const HUD_SHADER: wgpu::ShaderModuleDescriptor<'_> =
+    wgpu::include_wgsl!("../../../shaders/hud.wgsl");
+
+// Screen pixels per font pixel, and the font's cell including one pixel of spacing
+const PIXEL_SCALE: f32 = 3.0;
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+const CELL_WIDTH: f32 = (GLYPH_WIDTH + 1) as f32 * PIXEL_SCALE;
+const LINE_HEIGHT: f32 = (GLYPH_HEIGHT + 2) as f32 * PIXEL_SCALE;
+const MARGIN: f32 = 8.0;
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const BACKDROP_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct HudVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+
+impl HudVertex {
+    fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Lines of debug text drawn over the finished frame in a pass of its own, without a depth
+/// attachment, so the scene and its depth buffer are left as they were. Glyphs come from a
+/// built-in 3×5 pixel font drawn as one quad per lit pixel, which needs no font texture.
+pub(crate) struct DebugHud {
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: Option<wgpu::Buffer>,
+    vertex_count: u32,
+    text: String,
+    surface_size: (u32, u32),
+}
+
+impl DebugHud {
+    pub(crate) fn new(device: &wgpu::Device, color_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(HUD_SHADER);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug HUD Pipeline Layout"),
+            bind_group_layouts: &[],
+            immediate_size: 0,
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Debug HUD Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[HudVertex::buffer_layout()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            cache: None,
+            multiview_mask: None,
+        });
+
+        Self {
+            pipeline,
+            vertex_buffer: None,
+            vertex_count: 0,
+            text: String::new(),
+            surface_size: (0, 0),
+        }
+    }
+
+    /// Rebuilds the glyph quads only when the text or the surface size changed. Characters
+    /// outside the font are shown as `?`, and lowercase letters as uppercase.
+    pub(crate) fn set_text(&mut self, device: &wgpu::Device, text: &str, surface_size: (u32, u32)) {
+        if self.vertex_buffer.is_some() && text == self.text && surface_size == self.surface_size {
+            return;
+        }
+        text.clone_into(&mut self.text);
+        self.surface_size = surface_size;
+
+        let vertices = hud_vertices(text, surface_size);
+        self.vertex_count = vertices.len() as u32;
+        self.vertex_buffer = (!vertices.is_empty()).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Debug HUD Vertex Buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        });
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.vertex_buffer = None;
+        self.vertex_count = 0;
+        self.text.clear();
+    }
+
+    pub(crate) fn draw(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let Some(vertex_buffer) = &self.vertex_buffer else {
+            return;
+        };
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Debug HUD Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+            multiview_mask: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+}
+
+/// Two triangles per quad in clip space, a translucent backdrop behind the whole block of text
+/// and then one quad per lit font pixel, laid out from the top left corner.
+fn hud_vertices(text: &str, (width, height): (u32, u32)) -> Vec<HudVertex> {
+    let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+    let quad = |left: f32, top: f32, right: f32, bottom: f32, color: [f32; 4]| {
+        let (left, right) = (left / width * 2.0 - 1.0, right / width * 2.0 - 1.0);
+        let (top, bottom) = (1.0 - top / height * 2.0, 1.0 - bottom / height * 2.0);
+        [
+            [left, top],
+            [left, bottom],
+            [right, bottom],
+            [right, bottom],
+            [right, top],
+            [left, top],
+        ]
+        .map(|position| HudVertex { position, color })
+    };
+
+    let lines = text.lines().collect::<Vec<&str>>();
+    let longest_line = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    if longest_line == 0 {
+        return Vec::new();
+    }
+
+    let mut vertices = quad(
+        MARGIN - PIXEL_SCALE,
+        MARGIN - PIXEL_SCALE,
+        MARGIN + longest_line as f32 * CELL_WIDTH,
+        MARGIN + lines.len() as f32 * LINE_HEIGHT,
+        BACKDROP_COLOR,
+    )
+    .to_vec();
+    for (line_index, line) in lines.iter().enumerate() {
+        let top = MARGIN + line_index as f32 * LINE_HEIGHT;
+        for (column, character) in line.chars().enumerate() {
+            let left = MARGIN + column as f32 * CELL_WIDTH;
+            for (row, bits) in glyph(character).into_iter().enumerate() {
+                for pixel in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - pixel)) == 0 {
+                        continue;
+                    }
+                    let pixel_left = left + pixel as f32 * PIXEL_SCALE;
+                    let pixel_top = top + row as f32 * PIXEL_SCALE;
+                    vertices.extend(quad(
+                        pixel_left,
+                        pixel_top,
+                        pixel_left + PIXEL_SCALE,
+                        pixel_top + PIXEL_SCALE,
+                        TEXT_COLOR,
+                    ));
+                }
+            }
+        }
+    }
+    vertices
+}
+
+/// Rows of a 3×5 glyph from top to bottom, with the leftmost pixel in the highest bit.
+fn glyph(character: char) -> [u8; GLYPH_HEIGHT] {
+    match character.to_ascii_uppercase() {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '|' => [0b010, 0b010, 0b010, 0b010, 0b010],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        _ => [0b111, 0b001, 0b010, 0b000, 0b010],
+    }
+}
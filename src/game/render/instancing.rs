@@ -0,0 +1,109 @@
+use crate::game::mesh::{DrawRange, FaceInstance, FaceInstances, visible_ranges};
+use crate::game::render::Frustum;
+
+/// Vertices emitted per instance: two triangles covering one voxel face.
+const VERTICES_PER_FACE: u32 = 6;
+
+pub(crate) struct FaceInstanceBuffer {
+    buffer: wgpu::Buffer,
+    instance_count: u32,
+    draw_ranges: Vec<DrawRange>,
+}
+
+impl FaceInstanceBuffer {
+    pub(crate) fn from_instances(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        face_instances: &FaceInstances,
+    ) -> Option<Self> {
+        if face_instances.instance_count() == 0 {
+            return None;
+        }
+
+        let mut instance_buffer = Self {
+            buffer: create_instance_buffer(device, face_instances.instances_u8().len() as u64),
+            instance_count: 0,
+            draw_ranges: Vec::new(),
+        };
+        instance_buffer.write(device, queue, face_instances);
+        Some(instance_buffer)
+    }
+
+    pub(crate) fn write(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        face_instances: &FaceInstances,
+    ) {
+        let instances = face_instances.instances_u8();
+        if instances.len() as u64 > self.buffer.size() {
+            self.buffer = create_instance_buffer(device, instances.len() as u64);
+        }
+        queue.write_buffer(&self.buffer, 0, instances);
+        self.instance_count = face_instances.instance_count();
+        self.draw_ranges = face_instances.draw_ranges().to_vec();
+    }
+
+    /// Draws the faces of the chunks that touch `frustum`.
+    pub(crate) fn draw_culled(&self, render_pass: &mut wgpu::RenderPass<'_>, frustum: &Frustum) {
+        render_pass.set_vertex_buffer(0, self.buffer.slice(..));
+        for instances in visible_ranges(&self.draw_ranges, |aabb| frustum.intersects(aabb)) {
+            render_pass.draw(0..VERTICES_PER_FACE, instances);
+        }
+    }
+}
+
+fn create_instance_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Face Instance Buffer"),
+        size: size.next_power_of_two().max(wgpu::COPY_BUFFER_ALIGNMENT),
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+impl FaceInstance {
+    pub(crate) fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 13]>() as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 17]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
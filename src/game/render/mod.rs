@@ -1,22 +1,53 @@
 mod atlas;
+mod buffers;
 mod camera;
+mod debug;
+mod depth_capture;
+mod frustum;
+mod hud;
+mod instancing;
+mod particles;
+mod post;
+mod preview;
+mod shading;
 mod texture;
 
 pub(crate) use atlas::*;
+pub(crate) use buffers::*;
 pub(crate) use camera::*;
+pub(crate) use debug::*;
+pub(crate) use depth_capture::*;
+pub(crate) use frustum::*;
+pub(crate) use hud::*;
+pub(crate) use instancing::*;
+pub(crate) use particles::*;
+pub(crate) use post::*;
+pub(crate) use preview::*;
+pub(crate) use shading::*;
 pub(crate) use texture::*;
 
-use crate::game::mesh::{Mesh, Vertex};
+use crate::error::CairnError;
+use crate::game::chunk::CHUNK_SIZE;
+use crate::game::mesh::{FaceInstance, FaceInstances, LayeredMesh, Mesh, RenderLayer, Vertex};
 use crate::game::render::Texture;
 use crate::game::render::{Camera, CameraController};
-use crate::game::world::World;
+use crate::game::voxel::{VoxelFace, VoxelType};
+use crate::game::world::{World, WorldPosition};
+use crate::settings::Settings;
+use glam::Vec3;
+use log::{error, info, warn};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use wgpu::util::DeviceExt as _;
+use std::time::Duration;
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
+const SAMPLE_COUNT: u32 = 1;
+
 const WORLD_SHADER: wgpu::ShaderModuleDescriptor<'_> =
     wgpu::include_wgsl!("../../../shaders/voxel.wgsl");
+const DEBUG_LINE_SHADER: wgpu::ShaderModuleDescriptor<'_> =
+    wgpu::include_wgsl!("../../../shaders/debug_line.wgsl");
 
 pub(crate) struct Renderer {
     window: Arc<Window>,
@@ -24,111 +55,439 @@ pub(crate) struct Renderer {
     device: wgpu::Device,
     queue: wgpu::Queue,
     surface_config: wgpu::SurfaceConfiguration,
+    supported_present_modes: Vec<wgpu::PresentMode>,
     diffuse_texture: Texture,
+    depth_format: wgpu::TextureFormat,
+    depth_mode: DepthMode,
     depth_texture: Texture,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-    index_count: u32,
+    opaque_buffers: Option<MeshBuffers>,
+    cutout_buffers: Option<MeshBuffers>,
+    transparent_buffers: Option<MeshBuffers>,
+    face_instances: Option<FaceInstanceBuffer>,
+    is_instancing_enabled: bool,
+    horizon_skirt: Option<MeshBuffers>,
+    is_horizon_skirt_enabled: bool,
     camera: Camera,
     camera_controller: CameraController,
-    render_pipeline: wgpu::RenderPipeline,
+    shading: Shading,
+    world_pipelines: WorldPipelines,
+    unculled_world_pipelines: WorldPipelines,
+    is_face_culling_enabled: bool,
+    overdraw_pipelines: WorldPipelines,
+    is_overdraw_enabled: bool,
+    depth_capture_path: Option<PathBuf>,
+    /// Draws only this many of the nearest chunks, to isolate one area while debugging.
+    chunk_draw_limit: Option<usize>,
+    debug_line_pipeline: wgpu::RenderPipeline,
+    chunk_bounds: Option<ChunkBounds>,
+    debug_hud: DebugHud,
+    particles: ParticleSystem,
+    held_block_preview: HeldBlockPreview,
+    post_process: Option<PostProcess>,
+    is_chunk_bounds_enabled: bool,
 }
 
 impl Renderer {
-    pub(crate) async fn new(window: Arc<Window>, world: &mut World) -> anyhow::Result<Self> {
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
-            ..Default::default()
-        });
-        let surface = instance.create_surface(window.clone())?;
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await?;
-
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                label: None,
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-                experimental_features: wgpu::ExperimentalFeatures::disabled(),
-                memory_hints: Default::default(),
-                trace: wgpu::Trace::Off,
-            })
-            .await?;
+    #[expect(clippy::too_many_lines)]
+    pub(crate) async fn new(
+        window: Arc<Window>,
+        world: &mut World,
+        settings: &Settings,
+    ) -> Result<Self, CairnError> {
+        let (handedness, depth_mode) = (settings.handedness(), settings.depth_mode());
+        let (surface, adapter, device, queue) = create_surface_and_device(window.clone()).await?;
 
         let surface_config = create_surface_config(window.inner_size(), &surface, &adapter);
-
-        let texture_atlas_image = &world.texture_atlas().image();
+        let supported_present_modes = surface.get_capabilities(&adapter).present_modes;
 
         let texture_bind_group_layout = create_texture_bind_group_layout(&device);
         let diffuse_texture = Texture::create_diffuse_texture(
             &device,
             &queue,
             &texture_bind_group_layout,
-            texture_atlas_image,
+            &world.texture_atlas().image(),
             "texture_atlas",
         );
 
-        let depth_texture = Texture::new_depth_texture(&device, &surface_config, "Depth Texture");
+        let depth_format = Texture::select_depth_format(&adapter, SAMPLE_COUNT, depth_mode);
+        let depth_texture = create_depth_texture(&device, &surface_config, depth_format);
 
         let camera_bind_group_layout = create_camera_bind_group_layout(&device);
-        let camera = Camera::new(&device, &surface_config, &camera_bind_group_layout);
-        let camera_controller = CameraController::new();
+        let mut camera = Camera::new(
+            &device,
+            &surface_config,
+            &camera_bind_group_layout,
+            handedness,
+            depth_mode,
+        );
+        camera.set_clip_planes(camera.z_near(), world.view_distance());
+
+        let shading_bind_group_layout = create_shading_bind_group_layout(&device);
+        let shading = Shading::new(&device, &shading_bind_group_layout);
 
-        let world_mesh = Mesh::world(world);
-        let vertex_buffer = create_vertex_buffer(&device, world_mesh.vertices_u8());
-        let index_buffer = create_index_buffer(&device, world_mesh.indices_u8());
-        let index_count = world_mesh.index_count();
+        let post_process = settings
+            .post_processing()
+            .then(|| PostProcess::new(&device, &surface_config, &texture_bind_group_layout));
+        // The world draws into the offscreen scene instead of the surface when post-processing
+        let color_format = post_process
+            .as_ref()
+            .map_or_else(|| surface_view_format(&surface_config), |_| SCENE_FORMAT);
 
-        let render_pipeline = create_render_pipeline(
+        let world_bind_group_layouts = [
+            &texture_bind_group_layout,
+            &camera_bind_group_layout,
+            &shading_bind_group_layout,
+        ];
+        let [
+            world_pipelines,
+            unculled_world_pipelines,
+            overdraw_pipelines,
+        ] = WorldPipelines::all(
             &device,
-            &surface_config,
-            &[&texture_bind_group_layout, &camera_bind_group_layout],
+            color_format,
+            depth_format,
+            &world_bind_group_layouts,
+            handedness.front_face(),
+            depth_mode,
+        );
+        let held_block_preview =
+            HeldBlockPreview::new(&device, &camera_bind_group_layout, handedness, depth_mode);
+        let overlay_depth = depth_stencil_state(depth_format, depth_mode, false);
+        let debug_line_pipeline = create_debug_line_pipeline(
+            &device,
+            color_format,
+            overlay_depth.clone(),
+            &[&camera_bind_group_layout],
+        );
+        let debug_hud = DebugHud::new(&device, surface_view_format(&surface_config));
+        let particles = ParticleSystem::new(
+            &device,
+            color_format,
+            overlay_depth,
+            &world_bind_group_layouts,
         );
 
-        Ok(Self {
+        let mut renderer = Self {
             window,
             surface,
             device,
             queue,
             surface_config,
+            supported_present_modes,
             diffuse_texture,
+            depth_format,
+            depth_mode,
             depth_texture,
-            vertex_buffer,
-            index_buffer,
-            index_count,
+            opaque_buffers: None,
+            cutout_buffers: None,
+            transparent_buffers: None,
+            face_instances: None,
+            is_instancing_enabled: false,
+            horizon_skirt: None,
+            is_horizon_skirt_enabled: false,
             camera,
-            camera_controller,
-            render_pipeline,
-        })
+            camera_controller: CameraController::new(),
+            shading,
+            world_pipelines,
+            unculled_world_pipelines,
+            is_face_culling_enabled: true,
+            overdraw_pipelines,
+            is_overdraw_enabled: false,
+            depth_capture_path: None,
+            chunk_draw_limit: None,
+            debug_line_pipeline,
+            chunk_bounds: None,
+            debug_hud,
+            particles,
+            held_block_preview,
+            post_process,
+            is_chunk_bounds_enabled: false,
+        };
+        renderer.update_mesh(world);
+
+        Ok(renderer)
     }
 
     pub(crate) fn window(&self) -> &Window {
         &self.window
     }
 
+    /// The size of what is actually rendered to, which can briefly differ from the window's
+    /// inner size while a resize is pending.
+    pub(crate) fn surface_size(&self) -> (u32, u32) {
+        (self.surface_config.width, self.surface_config.height)
+    }
+
     pub(crate) fn camera(&self) -> &Camera {
         &self.camera
     }
 
+    pub(crate) fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
     pub(crate) fn camera_controller(&mut self) -> &mut CameraController {
         &mut self.camera_controller
     }
 
+    pub(crate) fn toggle_orbit_mode(&mut self, focus: Vec3) -> bool {
+        self.camera_controller
+            .toggle_orbit_mode(&mut self.camera, focus)
+    }
+
+    pub(crate) fn apply_settings(&mut self, settings: &Settings) {
+        self.set_fov(settings.fov_y());
+        let (sensitivity_x, sensitivity_y) = settings.mouse_sensitivity();
+        self.camera_controller.set_sensitivity_x(sensitivity_x);
+        self.camera_controller.set_sensitivity_y(sensitivity_y);
+        self.camera_controller.set_move_speed(settings.move_speed());
+        self.camera_controller
+            .set_vertical_move_speed(settings.vertical_move_speed());
+        self.camera_controller
+            .set_sprint_multiplier(settings.sprint_multiplier());
+        self.camera_controller
+            .set_sprint_fov_kick(settings.sprint_fov_kick());
+        self.camera_controller.set_turn_speed(settings.turn_speed());
+        self.camera_controller
+            .set_solid_clamp_enabled(settings.solid_clamp());
+        self.shading.set_alpha_cutoff(settings.alpha_cutoff());
+        self.shading.set_wave_amplitude(settings.wave_amplitude());
+        self.shading.set_min_brightness(settings.min_brightness());
+        self.shading
+            .set_face_shading_enabled(settings.face_shading());
+        self.shading.set_fog_density(settings.fog_density());
+        self.is_instancing_enabled = settings.instanced_faces();
+        self.is_horizon_skirt_enabled = settings.horizon_skirt();
+    }
+
+    /// Sets the vertical field of view in degrees and returns the value the camera kept after
+    /// clamping.
+    pub(crate) fn set_fov(&mut self, degrees: f32) -> f32 {
+        self.camera.set_fov_y(degrees);
+        self.camera.fov_y()
+    }
+
+    pub(crate) fn present_mode(&self) -> wgpu::PresentMode {
+        self.surface_config.present_mode
+    }
+
+    pub(crate) fn supports_present_mode(&self, present_mode: wgpu::PresentMode) -> bool {
+        matches!(
+            present_mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync
+        ) || self.supported_present_modes.contains(&present_mode)
+    }
+
+    pub(crate) fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) {
+        if !self.supports_present_mode(present_mode) {
+            warn!("Present mode is not supported by the surface: {present_mode:?}");
+            return;
+        }
+
+        self.surface_config.present_mode = present_mode;
+        self.surface.configure(&self.device, &self.surface_config);
+    }
+
     pub(crate) fn update_mesh(&mut self, world: &mut World) {
-        let world_mesh = Mesh::world(world);
-        self.vertex_buffer = create_vertex_buffer(&self.device, world_mesh.vertices_u8());
-        self.index_buffer = create_index_buffer(&self.device, world_mesh.indices_u8());
-        self.index_count = world_mesh.index_count();
+        let mut world_mesh = Mesh::world(world, self.chunk_draw_limit, self.is_instancing_enabled);
+        world_mesh.sort_transparent_faces(self.camera.exact_position());
+        self.write_world_mesh(&world_mesh);
+        if self.is_instancing_enabled {
+            self.write_face_instances(&FaceInstances::world(world, self.chunk_draw_limit));
+        } else {
+            self.face_instances = None;
+        }
+        self.write_horizon_skirt(world);
+
+        if self.is_chunk_bounds_enabled {
+            self.chunk_bounds = self.chunk_bounds(world);
+        }
+    }
+
+    fn write_world_mesh(&mut self, world_mesh: &LayeredMesh) {
+        for (mesh_buffers, render_layer) in [
+            (&mut self.opaque_buffers, RenderLayer::Opaque),
+            (&mut self.cutout_buffers, RenderLayer::Cutout),
+            (&mut self.transparent_buffers, RenderLayer::Transparent),
+        ] {
+            let mesh = world_mesh.layer(render_layer);
+            match mesh_buffers {
+                Some(buffers) if !mesh.is_empty() => {
+                    buffers.write(&self.device, &self.queue, mesh);
+                }
+                _ => *mesh_buffers = MeshBuffers::from_mesh(&self.device, &self.queue, mesh),
+            }
+        }
+    }
+
+    fn write_face_instances(&mut self, face_instances: &FaceInstances) {
+        match &mut self.face_instances {
+            Some(instance_buffer) if face_instances.instance_count() > 0 => {
+                instance_buffer.write(&self.device, &self.queue, face_instances);
+            }
+            _ => {
+                self.face_instances =
+                    FaceInstanceBuffer::from_instances(&self.device, &self.queue, face_instances);
+            }
+        }
+    }
+
+    fn write_horizon_skirt(&mut self, world: &World) {
+        let render_edge = (world.render_distance_xz() as usize * CHUNK_SIZE) as f32;
+        let is_hidden_by_fog = self.shading.hides_distance(render_edge);
+        let skirt = (self.is_horizon_skirt_enabled && !is_hidden_by_fog)
+            .then(|| Mesh::horizon_skirt(world))
+            .flatten();
+        match (&mut self.horizon_skirt, skirt) {
+            (Some(buffers), Some(skirt)) => buffers.write(&self.device, &self.queue, &skirt),
+            (horizon_skirt, skirt) => {
+                *horizon_skirt = skirt
+                    .and_then(|skirt| MeshBuffers::from_mesh(&self.device, &self.queue, &skirt));
+            }
+        }
+    }
+
+    pub(crate) fn toggle_chunk_bounds(&mut self, world: &World) {
+        self.is_chunk_bounds_enabled = !self.is_chunk_bounds_enabled;
+        self.chunk_bounds = if self.is_chunk_bounds_enabled {
+            self.chunk_bounds(world)
+        } else {
+            None
+        };
+    }
+
+    /// Freezes culling at the current pose so the chunk bounds show what it would cull from
+    /// elsewhere. Returns whether the frustum is now frozen.
+    pub(crate) fn toggle_frozen_frustum(&mut self, world: &World) -> bool {
+        let is_frozen = self.camera.toggle_frozen_frustum();
+        if self.is_chunk_bounds_enabled {
+            self.chunk_bounds = self.chunk_bounds(world);
+        }
+        is_frozen
+    }
+
+    fn chunk_bounds(&self, world: &World) -> Option<ChunkBounds> {
+        let culling_frustum = self
+            .camera
+            .is_frustum_frozen()
+            .then(|| self.camera.culling_frustum());
+        ChunkBounds::from_world(&self.device, world, culling_frustum.as_ref())
+    }
+
+    /// Drawing back faces makes faces with the wrong winding show up instead of vanishing.
+    pub(crate) fn toggle_face_culling(&mut self) -> bool {
+        self.is_face_culling_enabled = !self.is_face_culling_enabled;
+        self.is_face_culling_enabled
+    }
+
+    /// Shows how many layers are drawn over each pixel instead of the shaded scene.
+    pub(crate) fn toggle_overdraw(&mut self) -> bool {
+        self.is_overdraw_enabled = !self.is_overdraw_enabled;
+        self.is_overdraw_enabled
+    }
+
+    /// Shows `text` over the frame until it is replaced, or hides the overlay for `None`.
+    pub(crate) fn set_debug_hud_text(&mut self, text: Option<&str>) {
+        match text {
+            Some(text) => self
+                .debug_hud
+                .set_text(&self.device, text, self.surface_size()),
+            None => self.debug_hud.clear(),
+        }
+    }
+
+    /// Writes the depth buffer of the next frame to `path` as a grayscale PNG.
+    pub(crate) fn capture_depth(&mut self, path: &Path) {
+        self.depth_capture_path = Some(path.to_path_buf());
+    }
+
+    pub(crate) fn chunk_draw_limit(&self) -> Option<usize> {
+        self.chunk_draw_limit
+    }
+
+    /// Draws only the `chunk_draw_limit` chunks nearest the camera, or all loaded chunks for
+    /// `None`, and rebuilds the world buffers to match.
+    pub(crate) fn set_chunk_draw_limit(
+        &mut self,
+        world: &mut World,
+        chunk_draw_limit: Option<usize>,
+    ) {
+        self.chunk_draw_limit = chunk_draw_limit;
+        self.update_mesh(world);
+    }
+
+    /// Changes how far chunks are streamed around the camera horizontally, keeping the vertical
+    /// distances, and remeshes the world.
+    ///
+    /// Loaded chunks scale with `horizontal² × vertical`, and every one of them keeps its
+    /// voxel data and cached mesh in memory while the merged buffers hold all meshes on the
+    /// GPU at once, so large distances grow both CPU and GPU memory quickly. The buffers are
+    /// grown up front by the same factor as the chunk estimate to avoid repeated reallocation
+    /// while the larger ring streams in.
+    pub(crate) fn set_render_distance(&mut self, world: &mut World, render_distance_xz: i32) {
+        let previous_maximum = world.chunk_render_maximum().max(1) as u64;
+        world.set_horizontal_render_distance(render_distance_xz);
+        let maximum = world.chunk_render_maximum() as u64;
+
+        if maximum > previous_maximum {
+            for mesh_buffers in [
+                &mut self.opaque_buffers,
+                &mut self.cutout_buffers,
+                &mut self.transparent_buffers,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                mesh_buffers.reserve(
+                    &self.device,
+                    mesh_buffers.vertex_capacity() * maximum / previous_maximum,
+                    mesh_buffers.index_capacity() * maximum / previous_maximum,
+                );
+            }
+        }
+
+        self.camera
+            .set_clip_planes(self.camera.z_near(), world.view_distance());
+
+        if let Some(chunk_position) = world.last_update_position() {
+            world.update_chunks(chunk_position);
+            self.update_mesh(world);
+        }
     }
 
-    pub(crate) fn update(&mut self) {
-        self.camera_controller.update_camera(&mut self.camera);
+    pub(crate) fn set_held_voxel(&mut self, world: &World, voxel_type: VoxelType) {
+        self.held_block_preview
+            .set_voxel_type(&self.device, &self.queue, world, voxel_type);
+    }
+
+    /// Scatters pieces of the voxel's front texture from its center.
+    pub(crate) fn spawn_break_particles(
+        &mut self,
+        world: &World,
+        world_position: WorldPosition,
+        voxel_type: VoxelType,
+    ) {
+        let texture = world
+            .voxel_registry()
+            .get_properties(&voxel_type)
+            .textures()
+            .texture(VoxelFace::Front);
+        let center = Vec3::from(world_position.get_f32());
+        self.particles
+            .spawn_burst(center, world.texture_atlas().get_coordinates(texture));
+    }
+
+    pub(crate) fn update(&mut self, world: &World, frame_time: Duration) {
+        if self.camera.update_animation() {
+            self.camera_controller.discard_mouse_input();
+        } else {
+            self.camera_controller
+                .update_camera(&mut self.camera, world, frame_time.as_secs_f32());
+        }
         self.camera.update_buffer(&self.queue);
+        self.particles.update(&self.device, &self.camera);
+        self.shading.set_time(world.elapsed_seconds());
+        self.shading.update_buffer(&self.queue);
     }
 
     pub(crate) fn resize(&mut self, width: u32, height: u32) {
@@ -137,36 +496,81 @@ impl Renderer {
             self.surface_config.height = height;
             self.surface.configure(&self.device, &self.surface_config);
             self.depth_texture =
-                Texture::new_depth_texture(&self.device, &self.surface_config, "Depth Texture");
+                create_depth_texture(&self.device, &self.surface_config, self.depth_format);
+            if let Some(post_process) = &mut self.post_process {
+                post_process.resize(&self.device, &self.surface_config);
+            }
             self.camera.resize(width, height);
         }
     }
 
-    pub(crate) fn render(&self) -> Result<(), wgpu::SurfaceError> {
-        self.window.request_redraw();
+    /// Drawn in a pass of its own so that clearing depth keeps the world from hiding the cube.
+    fn render_held_block_preview(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        if !self.held_block_preview.has_voxel() {
+            return;
+        }
 
-        let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Held Block Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_texture.view(),
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.depth_mode.clear_value()),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+            multiview_mask: None,
+        });
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
+        render_pass.set_bind_group(0, &self.diffuse_texture.bind_group(), &[]);
+        render_pass.set_bind_group(1, self.held_block_preview.camera_bind_group(), &[]);
+        render_pass.set_bind_group(2, &self.shading.bind_group(), &[]);
+        render_pass.set_pipeline(match self.held_block_preview.render_layer() {
+            RenderLayer::Opaque => &self.world_pipelines.opaque,
+            RenderLayer::Cutout => &self.world_pipelines.cutout,
+            RenderLayer::Transparent => &self.world_pipelines.transparent,
+        });
+        let (width, height) = self.surface_size();
+        self.held_block_preview
+            .draw(&mut render_pass, width, height);
+    }
+
+    fn save_depth_capture(&self, (path, depth_capture): &(PathBuf, DepthCapture)) {
+        let clip_planes = (self.camera.z_near(), self.camera.z_far());
+        match depth_capture.save_png(&self.device, self.depth_mode, clip_planes, path) {
+            Ok(()) => info!("Saved depth buffer to {}", path.display()),
+            Err(error) => error!("Failed to save depth buffer: {error}"),
+        }
+    }
 
+    fn render_world(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
+                view,
                 depth_slice: None,
                 resolve_target: None,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
+                        r: f64::from(SKY_COLOR[0]),
+                        g: f64::from(SKY_COLOR[1]),
+                        b: f64::from(SKY_COLOR[2]),
                         a: 1.0,
                     }),
                     store: wgpu::StoreOp::Store,
@@ -175,32 +579,153 @@ impl Renderer {
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                 view: &self.depth_texture.view(),
                 depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
+                    load: wgpu::LoadOp::Clear(self.depth_mode.clear_value()),
                     store: wgpu::StoreOp::Store,
                 }),
-                stencil_ops: None,
+                stencil_ops: self
+                    .depth_format
+                    .has_stencil_aspect()
+                    .then_some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(0),
+                        store: wgpu::StoreOp::Store,
+                    }),
             }),
             occlusion_query_set: None,
             timestamp_writes: None,
             multiview_mask: None,
         });
 
-        if self.index_count > 0 {
-            render_pass.set_pipeline(&self.render_pipeline);
-            render_pass.set_bind_group(0, &self.diffuse_texture.bind_group(), &[]);
-            render_pass.set_bind_group(1, &self.camera.bind_group(), &[]);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-            render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+        render_pass.set_bind_group(0, &self.diffuse_texture.bind_group(), &[]);
+        render_pass.set_bind_group(1, &self.camera.bind_group(), &[]);
+        render_pass.set_bind_group(2, &self.shading.bind_group(), &[]);
+        let world_pipelines = if self.is_overdraw_enabled {
+            &self.overdraw_pipelines
+        } else if self.is_face_culling_enabled {
+            &self.world_pipelines
+        } else {
+            &self.unculled_world_pipelines
+        };
+        // Frozen, this keeps culling from the frozen pose so flying away shows what it rejects
+        let culling_frustum = self.camera.culling_frustum();
+        if let Some(opaque_buffers) = &self.opaque_buffers {
+            render_pass.set_pipeline(&world_pipelines.opaque);
+            opaque_buffers.draw_culled(&mut render_pass, &culling_frustum);
+        }
+        if let Some(face_instances) = &self.face_instances {
+            render_pass.set_pipeline(&world_pipelines.instanced);
+            face_instances.draw_culled(&mut render_pass, &culling_frustum);
+        }
+        // After the chunks so that the depth test rejects the parts they already cover
+        if let Some(horizon_skirt) = &self.horizon_skirt {
+            render_pass.set_pipeline(&world_pipelines.opaque);
+            horizon_skirt.draw(&mut render_pass);
+        }
+        if let Some(cutout_buffers) = &self.cutout_buffers {
+            render_pass.set_pipeline(&world_pipelines.cutout);
+            cutout_buffers.draw_culled(&mut render_pass, &culling_frustum);
+        }
+        // Last, so that everything behind has already been drawn to blend over
+        if let Some(transparent_buffers) = &self.transparent_buffers {
+            render_pass.set_pipeline(&world_pipelines.transparent);
+            transparent_buffers.draw_culled(&mut render_pass, &culling_frustum);
+        }
+        self.particles.draw(&mut render_pass);
+        if let Some(chunk_bounds) = &self.chunk_bounds {
+            render_pass.set_pipeline(&self.debug_line_pipeline);
+            render_pass.set_bind_group(0, &self.camera.bind_group(), &[]);
+            chunk_bounds.draw(&mut render_pass);
         }
-        drop(render_pass);
+    }
+
+    pub(crate) fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        self.window.request_redraw();
+        let depth_capture_path = self.depth_capture_path.take();
+
+        let output = self.surface.get_current_texture()?;
+        let output_view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(surface_view_format(&self.surface_config)),
+            ..Default::default()
+        });
+        let view = match &self.post_process {
+            Some(post_process) => post_process.scene_view(),
+            None => &output_view,
+        };
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        self.render_world(&mut encoder, view);
+        // Before the held block clears depth for itself
+        let depth_capture = depth_capture_path.map(|path| {
+            let depth_view = self.depth_texture.view();
+            (
+                path,
+                DepthCapture::new(&self.device, &mut encoder, &depth_view),
+            )
+        });
+        self.render_held_block_preview(&mut encoder, view);
+        if let Some(post_process) = &self.post_process {
+            post_process.post_process(&self.device, &mut encoder, view, &output_view);
+        }
+        // On the surface itself, after post-processing, so the text stays sharp and unaffected
+        self.debug_hud.draw(&mut encoder, &output_view);
 
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
 
+        if let Some(depth_capture) = depth_capture {
+            self.save_depth_capture(&depth_capture);
+        }
+
         Ok(())
     }
 }
+
+async fn create_surface_and_device(
+    window: Arc<Window>,
+) -> Result<
+    (
+        wgpu::Surface<'static>,
+        wgpu::Adapter,
+        wgpu::Device,
+        wgpu::Queue,
+    ),
+    CairnError,
+> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::PRIMARY,
+        ..Default::default()
+    });
+    let surface = instance
+        .create_surface(window)
+        .map_err(|error| CairnError::SurfaceCreation(error.into()))?;
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: Some(&surface),
+            force_fallback_adapter: false,
+        })
+        .await
+        .map_err(|error| CairnError::GpuUnavailable(error.into()))?;
+
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: None,
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::default(),
+            experimental_features: wgpu::ExperimentalFeatures::disabled(),
+            memory_hints: Default::default(),
+            trace: wgpu::Trace::Off,
+        })
+        .await
+        .map_err(|error| CairnError::GpuUnavailable(error.into()))?;
+
+    Ok((surface, adapter, device, queue))
+}
+
 fn create_surface_config(
     window_size: PhysicalSize<u32>,
     surface: &wgpu::Surface<'_>,
@@ -219,6 +744,24 @@ fn create_surface_config(
                 .expect("Should access preferred texture format"),
         );
 
+    // Shaders write linear color and rely on the target to encode it, so a surface without
+    // sRGB encoding is drawn through an sRGB view of itself where one exists
+    let srgb_view_format = surface_format.add_srgb_suffix();
+    let view_formats = if surface_format.is_srgb() {
+        info!("Using sRGB surface format {surface_format:?}");
+        vec![]
+    } else if srgb_view_format != surface_format {
+        info!(
+            "Surface format {surface_format:?} is not sRGB, drawing through a {srgb_view_format:?} view"
+        );
+        vec![srgb_view_format]
+    } else {
+        warn!(
+            "Surface format {surface_format:?} is not sRGB and has no sRGB view, colors will look too dark"
+        );
+        vec![]
+    };
+
     wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
         format: surface_format,
@@ -226,15 +769,224 @@ fn create_surface_config(
         height: window_size.height,
         present_mode: wgpu::PresentMode::AutoVsync,
         alpha_mode: wgpu::CompositeAlphaMode::Auto,
-        view_formats: vec![],
+        view_formats,
         desired_maximum_frame_latency: 2,
     }
 }
 
+/// What gets drawn to the surface: its sRGB view when it has one, otherwise the surface format.
+pub(crate) fn surface_view_format(
+    surface_config: &wgpu::SurfaceConfiguration,
+) -> wgpu::TextureFormat {
+    surface_config
+        .view_formats
+        .first()
+        .copied()
+        .unwrap_or(surface_config.format)
+}
+
+struct WorldPipelines {
+    opaque: wgpu::RenderPipeline,
+    cutout: wgpu::RenderPipeline,
+    /// Blended without writing depth; its faces are sorted back-to-front when meshed.
+    transparent: wgpu::RenderPipeline,
+    /// Opaque faces expanded from `FaceInstance`s rather than read from a vertex buffer.
+    instanced: wgpu::RenderPipeline,
+}
+
+struct ShaderStages<'a> {
+    vertex_entry_point: &'a str,
+    vertex_buffer: wgpu::VertexBufferLayout<'a>,
+    fragment_entry_point: &'a str,
+}
+
+impl<'a> ShaderStages<'a> {
+    fn world(fragment_entry_point: &'a str) -> Self {
+        Self {
+            vertex_entry_point: "vs_main",
+            vertex_buffer: Vertex::buffer_layout(),
+            fragment_entry_point,
+        }
+    }
+}
+
+impl WorldPipelines {
+    /// Culled for normal drawing, unculled for debugging winding and for billboards, and the
+    /// overdraw view.
+    fn all(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        front_face: wgpu::FrontFace,
+        depth_mode: DepthMode,
+    ) -> [Self; 3] {
+        let [culled, unculled] = [Some(wgpu::Face::Back), None].map(|cull_mode| {
+            Self::new(
+                device,
+                color_format,
+                depth_format,
+                bind_group_layouts,
+                front_face,
+                cull_mode,
+                depth_mode,
+            )
+        });
+        let overdraw = Self::overdraw(
+            device,
+            color_format,
+            depth_format,
+            bind_group_layouts,
+            front_face,
+        );
+        [culled, unculled, overdraw]
+    }
+
+    fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        front_face: wgpu::FrontFace,
+        cull_mode: Option<wgpu::Face>,
+        depth_mode: DepthMode,
+    ) -> Self {
+        let primitive = wgpu::PrimitiveState {
+            front_face,
+            cull_mode,
+            ..Default::default()
+        };
+        // Cutout faces such as foliage stay visible from behind without emitting both windings
+        let two_sided = wgpu::PrimitiveState {
+            cull_mode: None,
+            ..primitive
+        };
+        let pipeline = |shader_stages, primitive, label| {
+            create_render_pipeline(
+                device,
+                wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                },
+                depth_stencil_state(depth_format, depth_mode, true),
+                bind_group_layouts,
+                shader_stages,
+                primitive,
+                label,
+            )
+        };
+        let transparent = create_render_pipeline(
+            device,
+            wgpu::ColorTargetState {
+                format: color_format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+            depth_stencil_state(depth_format, depth_mode, false),
+            bind_group_layouts,
+            ShaderStages::world("fs_transparent"),
+            primitive,
+            "Transparent Pipeline",
+        );
+        let instanced_stages = ShaderStages {
+            vertex_entry_point: "vs_instanced",
+            vertex_buffer: FaceInstance::buffer_layout(),
+            fragment_entry_point: "fs_main",
+        };
+        Self {
+            opaque: pipeline(ShaderStages::world("fs_main"), primitive, "Render Pipeline"),
+            cutout: pipeline(
+                ShaderStages::world("fs_cutout"),
+                two_sided,
+                "Cutout Pipeline",
+            ),
+            transparent,
+            instanced: pipeline(instanced_stages, primitive, "Instanced Pipeline"),
+        }
+    }
+
+    /// Every fragment passes the depth test and adds a small constant to the target, so the
+    /// image brightens with the number of layers drawn over each pixel.
+    fn overdraw(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        front_face: wgpu::FrontFace,
+    ) -> Self {
+        const ADDITIVE: wgpu::BlendComponent = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let primitive = wgpu::PrimitiveState {
+            front_face,
+            cull_mode: Some(wgpu::Face::Back),
+            ..Default::default()
+        };
+        let two_sided = wgpu::PrimitiveState {
+            cull_mode: None,
+            ..primitive
+        };
+        let pipeline = |shader_stages, primitive, label| {
+            create_render_pipeline(
+                device,
+                wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState {
+                        color: ADDITIVE,
+                        alpha: ADDITIVE,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                },
+                wgpu::DepthStencilState {
+                    format: depth_format,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                },
+                bind_group_layouts,
+                shader_stages,
+                primitive,
+                label,
+            )
+        };
+        let instanced_stages = ShaderStages {
+            vertex_entry_point: "vs_instanced",
+            vertex_buffer: FaceInstance::buffer_layout(),
+            fragment_entry_point: "fs_overdraw",
+        };
+        Self {
+            opaque: pipeline(
+                ShaderStages::world("fs_overdraw"),
+                primitive,
+                "Overdraw Pipeline",
+            ),
+            cutout: pipeline(
+                ShaderStages::world("fs_overdraw"),
+                two_sided,
+                "Cutout Overdraw Pipeline",
+            ),
+            transparent: pipeline(
+                ShaderStages::world("fs_overdraw"),
+                primitive,
+                "Transparent Overdraw Pipeline",
+            ),
+            instanced: pipeline(instanced_stages, primitive, "Instanced Overdraw Pipeline"),
+        }
+    }
+}
+
 fn create_render_pipeline(
     device: &wgpu::Device,
-    config: &wgpu::SurfaceConfiguration,
+    color_target: wgpu::ColorTargetState,
+    depth_stencil: wgpu::DepthStencilState,
     bind_group_layouts: &[&wgpu::BindGroupLayout],
+    shader_stages: ShaderStages<'_>,
+    primitive: wgpu::PrimitiveState,
+    label: &str,
 ) -> wgpu::RenderPipeline {
     let shader = device.create_shader_module(WORLD_SHADER);
     let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -243,42 +995,91 @@ fn create_render_pipeline(
         immediate_size: 0,
     });
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Render Pipeline"),
+        label: Some(label),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some(shader_stages.vertex_entry_point),
+            buffers: &[shader_stages.vertex_buffer],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some(shader_stages.fragment_entry_point),
+            targets: &[Some(color_target)],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive,
+        depth_stencil: Some(depth_stencil),
+        multisample: wgpu::MultisampleState {
+            count: SAMPLE_COUNT,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        cache: None,
+        multiview_mask: None,
+    })
+}
+
+/// World geometry writes depth and passes only when strictly nearer. Overlays such as debug
+/// lines leave depth alone and also pass when equal so they stay visible on the faces they trace.
+fn depth_stencil_state(
+    depth_format: wgpu::TextureFormat,
+    depth_mode: DepthMode,
+    depth_write_enabled: bool,
+) -> wgpu::DepthStencilState {
+    wgpu::DepthStencilState {
+        format: depth_format,
+        depth_write_enabled,
+        depth_compare: depth_mode.compare(!depth_write_enabled),
+        stencil: wgpu::StencilState::default(),
+        bias: wgpu::DepthBiasState::default(),
+    }
+}
+
+fn create_debug_line_pipeline(
+    device: &wgpu::Device,
+    color_format: wgpu::TextureFormat,
+    depth_stencil: wgpu::DepthStencilState,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(DEBUG_LINE_SHADER);
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Debug Line Pipeline Layout"),
+        bind_group_layouts,
+        immediate_size: 0,
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Debug Line Pipeline"),
         layout: Some(&render_pipeline_layout),
         vertex: wgpu::VertexState {
             module: &shader,
             entry_point: Some("vs_main"),
-            buffers: &[Vertex::buffer_layout()],
+            buffers: &[DebugVertex::buffer_layout()],
             compilation_options: wgpu::PipelineCompilationOptions::default(),
         },
         fragment: Some(wgpu::FragmentState {
             module: &shader,
             entry_point: Some("fs_main"),
             targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
+                format: color_format,
                 blend: Some(wgpu::BlendState::REPLACE),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
             compilation_options: wgpu::PipelineCompilationOptions::default(),
         }),
         primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
+            topology: wgpu::PrimitiveTopology::LineList,
             strip_index_format: None,
             front_face: wgpu::FrontFace::Ccw,
-            cull_mode: Some(wgpu::Face::Back),
+            cull_mode: None,
             polygon_mode: wgpu::PolygonMode::Fill,
             unclipped_depth: false,
             conservative: false,
         },
-        depth_stencil: Some(wgpu::DepthStencilState {
-            format: Texture::DEPTH_FORMAT,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
+        depth_stencil: Some(depth_stencil),
         multisample: wgpu::MultisampleState {
-            count: 1,
+            count: SAMPLE_COUNT,
             mask: !0,
             alpha_to_coverage_enabled: false,
         },
@@ -287,6 +1088,20 @@ fn create_render_pipeline(
     })
 }
 
+fn create_depth_texture(
+    device: &wgpu::Device,
+    surface_config: &wgpu::SurfaceConfiguration,
+    depth_format: wgpu::TextureFormat,
+) -> Texture {
+    Texture::new_depth_texture(
+        device,
+        surface_config,
+        depth_format,
+        SAMPLE_COUNT,
+        "Depth Texture",
+    )
+}
+
 fn create_texture_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         entries: &[
@@ -327,32 +1142,18 @@ fn create_camera_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayo
     })
 }
 
-fn create_vertex_buffer(device: &wgpu::Device, contents: &[u8]) -> wgpu::Buffer {
-    if contents.is_empty() {
-        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer (empty)"),
-            contents: &[0u8; 1],
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-    }
-    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Vertex Buffer"),
-        contents,
-        usage: wgpu::BufferUsages::VERTEX,
-    })
-}
-
-fn create_index_buffer(device: &wgpu::Device, contents: &[u8]) -> wgpu::Buffer {
-    if contents.is_empty() {
-        return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer (empty)"),
-            contents: &[0u8; 1],
-            usage: wgpu::BufferUsages::INDEX,
-        });
-    }
-    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Index Buffer"),
-        contents,
-        usage: wgpu::BufferUsages::INDEX,
+fn create_shading_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+        label: Some("shading_bind_group_layout"),
     })
 }
@@ -0,0 +1,229 @@
+use crate::game::render::{Camera, SAMPLE_COUNT, TextureCoordinates};
+use glam::Vec3;
+use std::time::Instant;
+use wgpu::util::DeviceExt as _;
+
+const PARTICLE_SHADER: wgpu::ShaderModuleDescriptor<'_> =
+    wgpu::include_wgsl!("../../../shaders/particle.wgsl");
+
+const MAX_PARTICLES: usize = 256;
+const BREAK_PARTICLE_COUNT: usize = 24;
+const PARTICLE_GRAVITY: f32 = 18.0;
+const PARTICLE_SIZE: f32 = 0.12;
+const PARTICLE_LIFETIME: f32 = 0.8;
+// Each particle shows one cell of the voxel texture split into a grid of this many per side
+const PARTICLE_TEXTURE_DIVISIONS: f32 = 4.0;
+
+struct Particle {
+    position: Vec3,
+    velocity: Vec3,
+    age: f32,
+    lifetime: f32,
+    texture_coordinates: (f32, f32, f32, f32),
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct ParticleVertex {
+    position: [f32; 3],
+    texture_coordinates: [f32; 2],
+    opacity: f32,
+}
+
+impl ParticleVertex {
+    fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
+
+/// Short-lived billboards such as the debris of a broken voxel. They fade out over their
+/// lifetime in an alpha-blended pass of their own, drawn after the world so that they blend
+/// over it, which tests depth without writing it.
+pub(crate) struct ParticleSystem {
+    particles: Vec<Particle>,
+    rng_state: u32,
+    last_update: Instant,
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: Option<wgpu::Buffer>,
+    vertex_count: u32,
+}
+
+impl ParticleSystem {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        depth_stencil: wgpu::DepthStencilState,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+    ) -> Self {
+        let shader = device.create_shader_module(PARTICLE_SHADER);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Pipeline Layout"),
+            bind_group_layouts,
+            immediate_size: 0,
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Particle Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[ParticleVertex::buffer_layout()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            // Billboards may be wound either way depending on handedness
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(depth_stencil),
+            multisample: wgpu::MultisampleState {
+                count: SAMPLE_COUNT,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            cache: None,
+            multiview_mask: None,
+        });
+
+        Self {
+            particles: Vec::with_capacity(MAX_PARTICLES),
+            rng_state: 0x9E37_79B9,
+            last_update: Instant::now(),
+            pipeline,
+            vertex_buffer: None,
+            vertex_count: 0,
+        }
+    }
+
+    /// Once `MAX_PARTICLES` are alive, each new particle replaces the oldest one.
+    pub(crate) fn spawn_burst(&mut self, center: Vec3, texture_coordinates: &TextureCoordinates) {
+        let (u_min, u_max, v_min, v_max) = texture_coordinates.get();
+        let (u_size, v_size) = (
+            (u_max - u_min) / PARTICLE_TEXTURE_DIVISIONS,
+            (v_max - v_min) / PARTICLE_TEXTURE_DIVISIONS,
+        );
+
+        for _ in 0..BREAK_PARTICLE_COUNT {
+            let offset = Vec3::new(self.random(), self.random(), self.random()) - 0.5;
+            let u = u_min + (self.random() * PARTICLE_TEXTURE_DIVISIONS).floor() * u_size;
+            let v = v_min + (self.random() * PARTICLE_TEXTURE_DIVISIONS).floor() * v_size;
+            let particle = Particle {
+                position: center + offset * 0.8,
+                velocity: offset * 4.0 + Vec3::Y * (2.0 + self.random() * 2.0),
+                age: 0.0,
+                lifetime: PARTICLE_LIFETIME * (0.6 + self.random() * 0.8),
+                texture_coordinates: (u, u + u_size, v, v + v_size),
+            };
+
+            if self.particles.len() < MAX_PARTICLES {
+                self.particles.push(particle);
+            } else if let Some(oldest) = self
+                .particles
+                .iter_mut()
+                .max_by(|a, b| a.age.total_cmp(&b.age))
+            {
+                *oldest = particle;
+            }
+        }
+    }
+
+    pub(crate) fn update(&mut self, device: &wgpu::Device, camera: &Camera) {
+        let now = Instant::now();
+        // Clamped so that a long stall does not fling particles through the floor
+        let delta = now.duration_since(self.last_update).as_secs_f32().min(0.1);
+        self.last_update = now;
+
+        self.particles.retain_mut(|particle| {
+            particle.age += delta;
+            particle.velocity.y -= PARTICLE_GRAVITY * delta;
+            particle.position += particle.velocity * delta;
+            particle.age < particle.lifetime
+        });
+
+        let vertices = self.vertices(camera);
+        self.vertex_count = vertices.len() as u32;
+        self.vertex_buffer = (!vertices.is_empty()).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Particle Vertex Buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        });
+    }
+
+    /// Expects the texture and camera bind groups of the world pass to be set.
+    pub(crate) fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        if let Some(vertex_buffer) = &self.vertex_buffer {
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.draw(0..self.vertex_count, 0..1);
+        }
+    }
+
+    /// Two triangles per camera-facing quad, with opacity falling linearly to zero by the end
+    /// of each particle's lifetime.
+    fn vertices(&self, camera: &Camera) -> Vec<ParticleVertex> {
+        let right = camera.right() * PARTICLE_SIZE;
+        let up = right.cross(camera.forward());
+        self.particles
+            .iter()
+            .flat_map(|particle| {
+                let opacity = 1.0 - particle.age / particle.lifetime;
+                let center = particle.position;
+                let (u_min, u_max, v_min, v_max) = particle.texture_coordinates;
+                let vertex = |position: Vec3, texture_coordinates| ParticleVertex {
+                    position: position.to_array(),
+                    texture_coordinates,
+                    opacity,
+                };
+                let bottom_left = vertex(center - right - up, [u_min, v_max]);
+                let bottom_right = vertex(center + right - up, [u_max, v_max]);
+                let top_right = vertex(center + right + up, [u_max, v_min]);
+                let top_left = vertex(center - right + up, [u_min, v_min]);
+                [
+                    bottom_left,
+                    bottom_right,
+                    top_right,
+                    top_right,
+                    top_left,
+                    bottom_left,
+                ]
+            })
+            .collect()
+    }
+
+    /// Xorshift, which is plenty for scattering debris.
+    fn random(&mut self) -> f32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 17;
+        self.rng_state ^= self.rng_state << 5;
+        (self.rng_state >> 8) as f32 / (1 << 24) as f32
+    }
+}
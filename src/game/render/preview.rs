@@ -0,0 +1,100 @@
+use crate::game::mesh::{Mesh, OccludingVoxelNeighbors, RenderLayer};
+use crate::game::render::{DepthMode, Handedness, MeshBuffers};
+use crate::game::voxel::{VoxelOrientation, VoxelType};
+use crate::game::world::{World, WorldPosition};
+use glam::Vec3;
+use wgpu::util::DeviceExt as _;
+
+const PREVIEW_EYE: Vec3 = Vec3::new(1.7, 1.3, 2.1);
+const PREVIEW_FOV_Y: f32 = f32::to_radians(35.0);
+// Side length of the square preview as a fraction of the shorter window side
+const PREVIEW_SCALE: f32 = 0.16;
+const PREVIEW_MARGIN: f32 = 16.0;
+
+/// The selected voxel drawn as a small cube in the bottom-right corner, with its own fixed
+/// camera so it ignores where the player is looking.
+pub(crate) struct HeldBlockPreview {
+    buffers: Option<MeshBuffers>,
+    render_layer: RenderLayer,
+    camera_bind_group: wgpu::BindGroup,
+}
+
+impl HeldBlockPreview {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        handedness: Handedness,
+        depth_mode: DepthMode,
+    ) -> Self {
+        let view = handedness.look_at(PREVIEW_EYE, Vec3::ZERO, Vec3::Y);
+        let (z_near, z_far) = depth_mode.clip_planes(0.1, 10.0);
+        let projection = handedness.perspective(PREVIEW_FOV_Y, 1.0, z_near, z_far);
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Held Block Camera Buffer"),
+            contents: bytemuck::cast_slice(&[(projection * view).to_cols_array_2d()]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+            label: Some("held_block_camera_bind_group"),
+        });
+
+        Self {
+            buffers: None,
+            render_layer: RenderLayer::Opaque,
+            camera_bind_group,
+        }
+    }
+
+    pub(crate) fn set_voxel_type(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        world: &World,
+        voxel_type: VoxelType,
+    ) {
+        let voxel_properties = world.voxel_registry().get_properties(&voxel_type);
+        let mesh = Mesh::voxel(
+            WorldPosition::new(0, 0, 0),
+            voxel_properties,
+            voxel_properties.textures(),
+            VoxelOrientation::default(),
+            world.texture_atlas(),
+            &OccludingVoxelNeighbors::new(false, false, false, false, false, false),
+        );
+        self.buffers = MeshBuffers::from_mesh(device, queue, &mesh);
+        self.render_layer = voxel_properties.render_layer();
+    }
+
+    pub(crate) fn render_layer(&self) -> RenderLayer {
+        self.render_layer
+    }
+
+    pub(crate) fn camera_bind_group(&self) -> &wgpu::BindGroup {
+        &self.camera_bind_group
+    }
+
+    pub(crate) fn has_voxel(&self) -> bool {
+        self.buffers.is_some()
+    }
+
+    pub(crate) fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>, width: u32, height: u32) {
+        let Some(buffers) = &self.buffers else {
+            return;
+        };
+        let size = width.min(height) as f32 * PREVIEW_SCALE;
+        let (x, y) = (
+            width as f32 - size - PREVIEW_MARGIN,
+            height as f32 - size - PREVIEW_MARGIN,
+        );
+        if size < 1.0 || x < 0.0 || y < 0.0 {
+            return;
+        }
+        render_pass.set_viewport(x, y, size, size, 0.0, 1.0);
+        buffers.draw(render_pass);
+    }
+}
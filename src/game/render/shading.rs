@@ -0,0 +1,148 @@
+use crate::game::world::CHUNK_FADE_DURATION;
+use wgpu::util::DeviceExt as _;
+
+pub(crate) const ALPHA_CUTOFF: f32 = 0.5;
+pub(crate) const WAVE_AMPLITUDE: f32 = 0.06;
+/// Floor on `ambient_occlusion × light` so that enclosed spaces stay faintly visible.
+pub(crate) const MIN_BRIGHTNESS: f32 = 0.15;
+/// What the world is cleared to behind everything, and what fog fades distant faces toward.
+pub(crate) const SKY_COLOR: [f32; 3] = [0.1, 0.2, 0.3];
+// Fog at least this thick leaves nothing behind it visible
+const OPAQUE_FOG: f32 = 0.98;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShadingUniform {
+    alpha_cutoff: f32,
+    time: f32,
+    fade_duration: f32,
+    wave_amplitude: f32,
+    min_brightness: f32,
+    face_shading: f32,
+    fog_density: f32,
+    _padding: f32,
+    /// `SKY_COLOR` with an unused alpha, since a uniform `vec3` is aligned like a `vec4`.
+    fog_color: [f32; 4],
+}
+
+/// Parameters shared by the world pipelines' fragment stages.
+pub(crate) struct Shading {
+    uniform: ShadingUniform,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Shading {
+    pub(crate) fn new(device: &wgpu::Device, bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+        let uniform = ShadingUniform {
+            alpha_cutoff: ALPHA_CUTOFF,
+            time: 0.0,
+            fade_duration: CHUNK_FADE_DURATION.as_secs_f32(),
+            wave_amplitude: WAVE_AMPLITUDE,
+            min_brightness: MIN_BRIGHTNESS,
+            face_shading: 1.0,
+            fog_density: 0.0,
+            _padding: 0.0,
+            fog_color: [SKY_COLOR[0], SKY_COLOR[1], SKY_COLOR[2], 1.0],
+        };
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shading Buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("shading_bind_group"),
+        });
+
+        Self {
+            uniform,
+            buffer,
+            bind_group,
+        }
+    }
+
+    pub(crate) fn bind_group(&self) -> wgpu::BindGroup {
+        self.bind_group.clone()
+    }
+
+    /// Cutout fragments whose texture alpha falls below this value are discarded.
+    pub(crate) fn set_alpha_cutoff(&mut self, alpha_cutoff: f32) {
+        self.uniform.alpha_cutoff = alpha_cutoff.clamp(0.0, 1.0);
+    }
+
+    /// How far, in voxels, wave-animated surfaces dip below their resting height.
+    pub(crate) fn set_wave_amplitude(&mut self, wave_amplitude: f32) {
+        self.uniform.wave_amplitude = wave_amplitude.max(0.0);
+    }
+
+    /// The darkest a face can be shaded, however occluded or unlit its vertices are.
+    pub(crate) fn set_min_brightness(&mut self, min_brightness: f32) {
+        self.uniform.min_brightness = min_brightness.clamp(0.0, 1.0);
+    }
+
+    /// Darkens faces by the direction they point, for a sense of depth without any lighting.
+    pub(crate) fn set_face_shading_enabled(&mut self, is_face_shading_enabled: bool) {
+        self.uniform.face_shading = if is_face_shading_enabled { 1.0 } else { 0.0 };
+    }
+
+    /// Exponential squared fog per voxel of distance from the camera, 0 for none.
+    pub(crate) fn set_fog_density(&mut self, fog_density: f32) {
+        self.uniform.fog_density = fog_density.max(0.0);
+    }
+
+    /// Whether fog is thick enough at `distance` from the camera to hide anything there.
+    pub(crate) fn hides_distance(&self, distance: f32) -> bool {
+        fog_amount(self.uniform.fog_density, distance) >= OPAQUE_FOG
+    }
+
+    /// Seconds on the same clock as the vertices' fade start times.
+    pub(crate) fn set_time(&mut self, time: f32) {
+        self.uniform.time = time;
+    }
+
+    pub(crate) fn update_buffer(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+    }
+}
+
+/// How much of a face's color `distance` voxels away is replaced by fog, from 0 to 1, matching
+/// the shader's fog.
+fn fog_amount(fog_density: f32, distance: f32) -> f32 {
+    1.0 - (-(fog_density * distance).powi(2)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fog_thickens_with_distance_and_density() {
+        assert!(
+            fog_amount(0.0, 10_000.0) == 0.0,
+            "Zero density should leave every distance clear"
+        );
+        assert!(
+            fog_amount(0.01, 0.0) == 0.0,
+            "Nothing should be fogged at the camera"
+        );
+        assert!(
+            fog_amount(0.01, 50.0) < fog_amount(0.01, 100.0),
+            "Fog should thicken with distance"
+        );
+        assert!(
+            fog_amount(0.01, 100.0) < fog_amount(0.02, 100.0),
+            "Fog should thicken with density"
+        );
+        assert!(
+            fog_amount(0.01, 300.0) >= OPAQUE_FOG,
+            "Dense enough fog should hide what is far away"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::game::render::DepthMode;
 use image::GenericImageView as _;
 
 pub(crate) struct Texture {
@@ -6,7 +7,33 @@ pub(crate) struct Texture {
 }
 
 impl Texture {
-    pub(crate) const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+    const DEPTH_FORMAT_CANDIDATES: [wgpu::TextureFormat; 2] = [
+        wgpu::TextureFormat::Depth24PlusStencil8,
+        wgpu::TextureFormat::Depth32Float,
+    ];
+
+    /// Reversed depth only pays off with float precision, so it always gets `Depth32Float`,
+    /// which every adapter can render to.
+    pub(crate) fn select_depth_format(
+        adapter: &wgpu::Adapter,
+        sample_count: u32,
+        depth_mode: DepthMode,
+    ) -> wgpu::TextureFormat {
+        if depth_mode == DepthMode::Reversed {
+            return wgpu::TextureFormat::Depth32Float;
+        }
+
+        Self::DEPTH_FORMAT_CANDIDATES
+            .into_iter()
+            .find(|format| {
+                let features = adapter.get_texture_format_features(*format);
+                features
+                    .allowed_usages
+                    .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+                    && features.flags.sample_count_supported(sample_count)
+            })
+            .unwrap_or(wgpu::TextureFormat::Depth32Float)
+    }
 
     pub(crate) fn create_diffuse_texture(
         device: &wgpu::Device,
@@ -90,6 +117,8 @@ impl Texture {
     pub(crate) fn new_depth_texture(
         device: &wgpu::Device,
         surface_config: &wgpu::SurfaceConfiguration,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
         label: &str,
     ) -> Self {
         let size = wgpu::Extent3d {
@@ -101,9 +130,9 @@ impl Texture {
             label: Some(label),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
-            format: Self::DEPTH_FORMAT,
+            format,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
         };
@@ -0,0 +1,16 @@
+use serde::Deserialize;
+
+/// What a voxel is made of, for effects such as sounds that depend on the substance rather than
+/// the exact type.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MaterialKind {
+    #[default]
+    Generic,
+    Stone,
+    Dirt,
+    Grass,
+    Sand,
+    Foliage,
+    Water,
+}
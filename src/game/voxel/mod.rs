@@ -1,3 +1,7 @@
+mod material;
+mod orientation;
 mod registry;
 
+pub(crate) use material::*;
+pub(crate) use orientation::*;
 pub(crate) use registry::*;
@@ -0,0 +1,123 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum VoxelFace {
+    Front,
+    Back,
+    Right,
+    Left,
+    Top,
+    Bottom,
+}
+
+impl VoxelFace {
+    /// The unit direction the face points toward, outward from the voxel.
+    pub(crate) fn normal(self) -> [f32; 3] {
+        match self {
+            Self::Front => [0.0, 0.0, 1.0],
+            Self::Back => [0.0, 0.0, -1.0],
+            Self::Right => [1.0, 0.0, 0.0],
+            Self::Left => [-1.0, 0.0, 0.0],
+            Self::Top => [0.0, 1.0, 0.0],
+            Self::Bottom => [0.0, -1.0, 0.0],
+        }
+    }
+
+    /// Corner offsets from the voxel's center, wound counter-clockwise as seen from outside
+    /// like the expanded mesher's quads and the instanced shader's corner table.
+    pub(crate) fn corners(self) -> [[f32; 3]; 4] {
+        match self {
+            Self::Front => [
+                [-0.5, -0.5, 0.5],
+                [0.5, -0.5, 0.5],
+                [0.5, 0.5, 0.5],
+                [-0.5, 0.5, 0.5],
+            ],
+            Self::Back => [
+                [-0.5, -0.5, -0.5],
+                [-0.5, 0.5, -0.5],
+                [0.5, 0.5, -0.5],
+                [0.5, -0.5, -0.5],
+            ],
+            Self::Right => [
+                [0.5, -0.5, -0.5],
+                [0.5, 0.5, -0.5],
+                [0.5, 0.5, 0.5],
+                [0.5, -0.5, 0.5],
+            ],
+            Self::Left => [
+                [-0.5, -0.5, -0.5],
+                [-0.5, -0.5, 0.5],
+                [-0.5, 0.5, 0.5],
+                [-0.5, 0.5, -0.5],
+            ],
+            Self::Top => [
+                [-0.5, 0.5, -0.5],
+                [-0.5, 0.5, 0.5],
+                [0.5, 0.5, 0.5],
+                [0.5, 0.5, -0.5],
+            ],
+            Self::Bottom => [
+                [-0.5, -0.5, -0.5],
+                [0.5, -0.5, -0.5],
+                [0.5, -0.5, 0.5],
+                [-0.5, -0.5, 0.5],
+            ],
+        }
+    }
+
+    /// Fixed shading that fakes light from above and slightly to one side: top faces are
+    /// brightest, bottom faces darkest, and the two pairs of sides fall in between.
+    pub(crate) fn brightness(self) -> f32 {
+        match self {
+            Self::Top => 1.0,
+            Self::Right | Self::Left => 0.8,
+            Self::Front | Self::Back => 0.65,
+            Self::Bottom => 0.5,
+        }
+    }
+}
+
+/// The world direction that a voxel's top face points toward.
+#[repr(u16)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+pub(crate) enum VoxelOrientation {
+    #[default]
+    PositiveY,
+    NegativeY,
+    PositiveX,
+    NegativeX,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl VoxelOrientation {
+    /// Maps a face in world space to the face of the unrotated voxel that ends up there.
+    pub(crate) fn local_face(self, world_face: VoxelFace) -> VoxelFace {
+        match (self, world_face) {
+            (Self::NegativeY, VoxelFace::Top)
+            | (Self::PositiveX, VoxelFace::Left)
+            | (Self::NegativeX, VoxelFace::Right)
+            | (Self::PositiveZ, VoxelFace::Back)
+            | (Self::NegativeZ, VoxelFace::Front) => VoxelFace::Bottom,
+            (Self::NegativeY, VoxelFace::Bottom)
+            | (Self::PositiveX, VoxelFace::Right)
+            | (Self::NegativeX, VoxelFace::Left)
+            | (Self::PositiveZ, VoxelFace::Front)
+            | (Self::NegativeZ, VoxelFace::Back) => VoxelFace::Top,
+            (Self::NegativeY, VoxelFace::Front)
+            | (Self::PositiveZ, VoxelFace::Top)
+            | (Self::NegativeZ, VoxelFace::Bottom) => VoxelFace::Back,
+            (Self::NegativeY, VoxelFace::Back)
+            | (Self::PositiveZ, VoxelFace::Bottom)
+            | (Self::NegativeZ, VoxelFace::Top) => VoxelFace::Front,
+            (Self::PositiveX, VoxelFace::Top) | (Self::NegativeX, VoxelFace::Bottom) => {
+                VoxelFace::Left
+            }
+            (Self::PositiveX, VoxelFace::Bottom) | (Self::NegativeX, VoxelFace::Top) => {
+                VoxelFace::Right
+            }
+            (_, world_face) => world_face,
+        }
+    }
+}
@@ -1,7 +1,14 @@
+use crate::error::CairnError;
+use crate::game::mesh::RenderLayer;
 use crate::game::render::TextureType;
+use crate::game::voxel::{MaterialKind, VoxelFace};
+use anyhow::Context as _;
+use log::{info, warn};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
-use std::collections::HashMap;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
 use std::default::Default;
+use std::path::{Path, PathBuf};
 
 #[repr(u16)]
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
@@ -10,20 +17,85 @@ pub(crate) enum VoxelType {
     Stone,
     Dirt,
     Grass,
+    Sand,
+    Leaves,
+    GlowingOre,
+    Water,
 }
 
+impl VoxelType {
+    /// Every type except air, in id order.
+    pub(crate) fn placeable() -> Vec<Self> {
+        (1..).map_while(|id| Self::try_from(id).ok()).collect()
+    }
+
+    /// The snake case name definition files refer to the type by, e.g. `glowing_ore`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Air => "air",
+            Self::Stone => "stone",
+            Self::Dirt => "dirt",
+            Self::Grass => "grass",
+            Self::Sand => "sand",
+            Self::Leaves => "leaves",
+            Self::GlowingOre => "glowing_ore",
+            Self::Water => "water",
+        }
+    }
+
+    pub(crate) fn display_name(self) -> &'static str {
+        match self {
+            Self::Air => "Air",
+            Self::Stone => "Stone",
+            Self::Dirt => "Dirt",
+            Self::Grass => "Grass",
+            Self::Sand => "Sand",
+            Self::Leaves => "Leaves",
+            Self::GlowingOre => "Glowing Ore",
+            Self::Water => "Water",
+        }
+    }
+}
+
+/// How a voxel changes when a random tick lands on it.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub(crate) enum RandomTickRule {
+    /// Turns to grass when open to the air above and next to grass, so lawns creep over
+    /// bare dirt.
+    GrowGrass,
+    /// Turns to dirt once something occluding covers it.
+    DieWhenCovered,
+}
+
+#[derive(Hash)]
 pub(crate) struct VoxelProperties {
     textures: VoxelTextures,
+    emissive_textures: Option<VoxelTextures>,
     is_invisible: bool,
     is_occluding: bool,
+    is_solid: bool,
+    is_affected_by_gravity: bool,
+    is_alpha_cutout: bool,
+    is_translucent: bool,
+    has_surface_waves: bool,
+    random_tick_rule: Option<RandomTickRule>,
+    material: MaterialKind,
 }
 
 impl Default for VoxelProperties {
     fn default() -> Self {
         Self {
             textures: VoxelTextures::uniform(TextureType::Stone),
+            emissive_textures: None,
             is_invisible: false,
             is_occluding: true,
+            is_solid: true,
+            is_affected_by_gravity: false,
+            is_alpha_cutout: false,
+            is_translucent: false,
+            has_surface_waves: false,
+            random_tick_rule: None,
+            material: MaterialKind::Generic,
         }
     }
 }
@@ -33,35 +105,54 @@ impl VoxelProperties {
         self.is_occluding
     }
 
+    pub(crate) fn is_solid(&self) -> bool {
+        self.is_solid
+    }
+
     pub(crate) fn is_invisible(&self) -> bool {
         self.is_invisible
     }
 
-    pub(crate) fn front_texture(&self) -> TextureType {
-        self.textures.front
+    pub(crate) fn is_affected_by_gravity(&self) -> bool {
+        self.is_affected_by_gravity
     }
 
-    pub(crate) fn back_texture(&self) -> TextureType {
-        self.textures.back
+    /// Top faces are displaced by the shader's wave animation.
+    pub(crate) fn has_surface_waves(&self) -> bool {
+        self.has_surface_waves
     }
 
-    pub(crate) fn right_texture(&self) -> TextureType {
-        self.textures.right
+    pub(crate) fn random_tick_rule(&self) -> Option<RandomTickRule> {
+        self.random_tick_rule
     }
 
-    pub(crate) fn left_texture(&self) -> TextureType {
-        self.textures.left
+    pub(crate) fn material(&self) -> MaterialKind {
+        self.material
     }
 
-    pub(crate) fn top_texture(&self) -> TextureType {
-        self.textures.top
+    pub(crate) fn render_layer(&self) -> RenderLayer {
+        if self.is_translucent {
+            RenderLayer::Transparent
+        } else if self.is_alpha_cutout {
+            RenderLayer::Cutout
+        } else {
+            RenderLayer::Opaque
+        }
     }
 
-    pub(crate) fn bottom_texture(&self) -> TextureType {
-        self.textures.bottom
+    pub(crate) fn textures(&self) -> &VoxelTextures {
+        &self.textures
+    }
+
+    /// An overlay drawn at full brightness over the base texture.
+    pub(crate) fn emissive_texture(&self, face: VoxelFace) -> Option<TextureType> {
+        self.emissive_textures
+            .as_ref()
+            .map(|emissive_textures| emissive_textures.texture(face))
     }
 }
 
+#[derive(Clone, Hash, Eq, PartialEq)]
 pub(crate) struct VoxelTextures {
     front: TextureType,
     back: TextureType,
@@ -83,6 +174,17 @@ impl VoxelTextures {
         }
     }
 
+    pub(crate) fn texture(&self, face: VoxelFace) -> TextureType {
+        match face {
+            VoxelFace::Front => self.front,
+            VoxelFace::Back => self.back,
+            VoxelFace::Right => self.right,
+            VoxelFace::Left => self.left,
+            VoxelFace::Top => self.top,
+            VoxelFace::Bottom => self.bottom,
+        }
+    }
+
     pub(crate) fn top_bottom(
         top_texture: TextureType,
         bottom_texture: TextureType,
@@ -99,54 +201,453 @@ impl VoxelTextures {
     }
 }
 
+#[derive(Hash)]
 pub(crate) struct VoxelRegistry {
-    properties: HashMap<VoxelType, VoxelProperties>,
+    /// Indexed by voxel type id, so lookups in the meshing loop never hash.
+    properties: Vec<VoxelProperties>,
+    /// Only consulted while loading definition files.
+    ids_by_name: BTreeMap<&'static str, VoxelType>,
 }
 
 impl VoxelRegistry {
+    /// The properties of the voxel type with this id, if there is one.
+    pub(crate) fn get(&self, id: u16) -> Option<&VoxelProperties> {
+        self.properties.get(usize::from(id))
+    }
+
     pub(crate) fn get_properties(&self, voxel_type: &VoxelType) -> &VoxelProperties {
-        self.properties
-            .get(voxel_type)
+        self.get(u16::from(*voxel_type))
             .unwrap_or_else(|| panic!("Properties should exist for voxel type: {voxel_type:?}"))
     }
 
-    pub(crate) fn init() -> Self {
+    /// The voxel type that definition files call `name`.
+    pub(crate) fn id(&self, name: &str) -> Option<VoxelType> {
+        self.ids_by_name.get(name).copied()
+    }
+
+    /// Every placeable type with its display name and front texture, in id order, for listing
+    /// blocks in a picker.
+    pub(crate) fn definitions(
+        &self,
+    ) -> impl Iterator<Item = (VoxelType, &'static str, TextureType)> {
+        VoxelType::placeable().into_iter().map(|voxel_type| {
+            let texture = self
+                .get_properties(&voxel_type)
+                .textures()
+                .texture(VoxelFace::Front);
+            (voxel_type, voxel_type.display_name(), texture)
+        })
+    }
+
+    fn from_entries<const N: usize>(mut entries: [(VoxelType, VoxelProperties); N]) -> Self {
+        entries.sort_by_key(|(voxel_type, _)| u16::from(*voxel_type));
+        assert!(
+            entries
+                .iter()
+                .enumerate()
+                .all(|(index, (voxel_type, _))| usize::from(u16::from(*voxel_type)) == index),
+            "Every voxel type should have exactly one set of properties"
+        );
         Self {
-            properties: HashMap::from([
-                (
-                    VoxelType::Air,
-                    VoxelProperties {
-                        textures: VoxelTextures::uniform(TextureType::Air),
-                        is_invisible: true,
-                        is_occluding: false,
-                    },
-                ),
-                (
-                    VoxelType::Stone,
-                    VoxelProperties {
-                        textures: VoxelTextures::uniform(TextureType::Stone),
-                        ..Default::default()
-                    },
-                ),
-                (
-                    VoxelType::Dirt,
-                    VoxelProperties {
-                        textures: VoxelTextures::uniform(TextureType::Dirt),
-                        ..Default::default()
-                    },
-                ),
-                (
-                    VoxelType::Grass,
-                    VoxelProperties {
-                        textures: VoxelTextures::top_bottom(
-                            TextureType::GrassTop,
-                            TextureType::Dirt,
-                            TextureType::GrassSide,
-                        ),
-                        ..Default::default()
-                    },
-                ),
-            ]),
+            ids_by_name: entries
+                .iter()
+                .map(|(voxel_type, _)| (voxel_type.name(), *voxel_type))
+                .collect(),
+            properties: entries
+                .into_iter()
+                .map(|(_, properties)| properties)
+                .collect(),
+        }
+    }
+
+    pub(crate) fn init() -> Self {
+        Self::from_entries([
+            (
+                VoxelType::Air,
+                VoxelProperties {
+                    textures: VoxelTextures::uniform(TextureType::Air),
+                    is_invisible: true,
+                    is_occluding: false,
+                    ..Default::default()
+                },
+            ),
+            (
+                VoxelType::Stone,
+                VoxelProperties {
+                    textures: VoxelTextures::uniform(TextureType::Stone),
+                    material: MaterialKind::Stone,
+                    ..Default::default()
+                },
+            ),
+            (
+                VoxelType::Dirt,
+                VoxelProperties {
+                    textures: VoxelTextures::uniform(TextureType::Dirt),
+                    random_tick_rule: Some(RandomTickRule::GrowGrass),
+                    material: MaterialKind::Dirt,
+                    ..Default::default()
+                },
+            ),
+            (
+                VoxelType::Grass,
+                VoxelProperties {
+                    textures: VoxelTextures::top_bottom(
+                        TextureType::GrassTop,
+                        TextureType::Dirt,
+                        TextureType::GrassSide,
+                    ),
+                    random_tick_rule: Some(RandomTickRule::DieWhenCovered),
+                    material: MaterialKind::Grass,
+                    ..Default::default()
+                },
+            ),
+            (
+                VoxelType::Sand,
+                VoxelProperties {
+                    textures: VoxelTextures::uniform(TextureType::Sand),
+                    is_affected_by_gravity: true,
+                    material: MaterialKind::Sand,
+                    ..Default::default()
+                },
+            ),
+            (
+                VoxelType::Leaves,
+                VoxelProperties {
+                    textures: VoxelTextures::uniform(TextureType::Leaves),
+                    is_occluding: false,
+                    is_alpha_cutout: true,
+                    material: MaterialKind::Foliage,
+                    ..Default::default()
+                },
+            ),
+            (
+                VoxelType::GlowingOre,
+                VoxelProperties {
+                    textures: VoxelTextures::uniform(TextureType::Stone),
+                    emissive_textures: Some(VoxelTextures::uniform(TextureType::GlowingOreOverlay)),
+                    material: MaterialKind::Stone,
+                    ..Default::default()
+                },
+            ),
+            (
+                VoxelType::Water,
+                VoxelProperties {
+                    textures: VoxelTextures::uniform(TextureType::Water),
+                    is_occluding: false,
+                    is_translucent: true,
+                    has_surface_waves: true,
+                    material: MaterialKind::Water,
+                    ..Default::default()
+                },
+            ),
+        ])
+    }
+}
+
+/// Overrides for one voxel type's built-in properties, read from a TOML file. Fields left out
+/// keep their current value.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VoxelDefinition {
+    voxel_type: String,
+    /// Every face, unless `top_texture`, `bottom_texture` or `side_texture` say otherwise.
+    texture: Option<String>,
+    top_texture: Option<String>,
+    bottom_texture: Option<String>,
+    side_texture: Option<String>,
+    is_occluding: Option<bool>,
+    is_solid: Option<bool>,
+    is_affected_by_gravity: Option<bool>,
+    is_alpha_cutout: Option<bool>,
+    is_translucent: Option<bool>,
+    /// One of the `MaterialKind` names in lowercase, e.g. `stone`.
+    material: Option<MaterialKind>,
+}
+
+impl VoxelDefinition {
+    fn voxel_type(&self, voxel_registry: &VoxelRegistry) -> anyhow::Result<VoxelType> {
+        voxel_registry
+            .id(&self.voxel_type)
+            .with_context(|| format!("Unknown voxel type: {}", self.voxel_type))
+    }
+
+    fn apply(&self, properties: &mut VoxelProperties) -> anyhow::Result<()> {
+        let texture = |name: &Option<String>, current: TextureType| {
+            name.as_deref()
+                .or(self.texture.as_deref())
+                .map_or(Ok(current), |name| {
+                    TextureType::from_name(name).with_context(|| format!("Unknown texture: {name}"))
+                })
+        };
+        let textures = &properties.textures;
+        properties.textures = VoxelTextures::top_bottom(
+            texture(&self.top_texture, textures.texture(VoxelFace::Top))?,
+            texture(&self.bottom_texture, textures.texture(VoxelFace::Bottom))?,
+            texture(&self.side_texture, textures.texture(VoxelFace::Front))?,
+        );
+
+        for (value, flag) in [
+            (self.is_occluding, &mut properties.is_occluding),
+            (self.is_solid, &mut properties.is_solid),
+            (
+                self.is_affected_by_gravity,
+                &mut properties.is_affected_by_gravity,
+            ),
+            (self.is_alpha_cutout, &mut properties.is_alpha_cutout),
+            (self.is_translucent, &mut properties.is_translucent),
+        ] {
+            if let Some(value) = value {
+                *flag = value;
+            }
+        }
+        if let Some(material) = self.material {
+            properties.material = material;
+        }
+        Ok(())
+    }
+}
+
+impl VoxelRegistry {
+    /// The built-in registry with the `.toml` definitions in `directory` applied over it.
+    pub(crate) fn load_from_directory(directory: &Path) -> Result<Self, CairnError> {
+        Self::load_from_directories(&[directory])
+    }
+
+    /// The built-in registry with the `.toml` definitions in each directory applied over it in
+    /// turn, so a later directory's definition of a type replaces an earlier one. Files within
+    /// a directory are applied in name order. A missing directory is skipped, and when no
+    /// definitions are found at all the game still starts, with the `fallback` registry. A
+    /// directory that cannot be read or holds an invalid definition is reported with its path.
+    pub(crate) fn load_from_directories(directories: &[&Path]) -> Result<Self, CairnError> {
+        let mut registry = Self::init();
+        let mut sources = HashMap::<VoxelType, PathBuf>::new();
+        for directory in directories {
+            registry
+                .apply_definition_directory(directory, &mut sources)
+                .map_err(|error| CairnError::AssetLoad {
+                    path: directory.to_path_buf(),
+                    source: error.into(),
+                })?;
+        }
+        if sources.is_empty() {
+            warn!(
+                "No voxel definitions found in {}, falling back to air and stone",
+                directories
+                    .iter()
+                    .map(|directory| directory.display().to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+            return Ok(Self::fallback());
+        }
+        Ok(registry)
+    }
+
+    /// Applies each `.toml` definition in `directory` in name order, recording which file each
+    /// type came from in `sources`. A missing directory is skipped with a warning.
+    fn apply_definition_directory(
+        &mut self,
+        directory: &Path,
+        sources: &mut HashMap<VoxelType, PathBuf>,
+    ) -> anyhow::Result<()> {
+        let entries = match std::fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                warn!(
+                    "Voxel definitions directory {} does not exist, skipping it",
+                    directory.display()
+                );
+                return Ok(());
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let mut paths = entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<PathBuf>, _>>()?;
+        paths.retain(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        });
+        paths.sort();
+
+        for path in paths {
+            let voxel_type = self
+                .apply_definition_file(&path)
+                .with_context(|| format!("Invalid voxel definition {}", path.display()))?;
+            if let Some(source) = sources.insert(voxel_type, path.clone()) {
+                info!(
+                    "Voxel definition {} overrides {voxel_type:?} from {}",
+                    path.display(),
+                    source.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The minimal set used when no definitions could be found: air stays air and every other
+    /// voxel type is drawn and behaves as plain stone.
+    pub(crate) fn fallback() -> Self {
+        let mut registry = Self::init();
+        for (properties, id) in registry.properties.iter_mut().zip(0_u16..) {
+            if id != u16::from(VoxelType::Air) {
+                *properties = VoxelProperties {
+                    textures: VoxelTextures::uniform(TextureType::Stone),
+                    material: MaterialKind::Stone,
+                    ..Default::default()
+                };
+            }
+        }
+        registry
+    }
+
+    fn apply_definition_file(&mut self, path: &Path) -> anyhow::Result<VoxelType> {
+        let definition = toml::from_str::<VoxelDefinition>(&std::fs::read_to_string(path)?)?;
+        let voxel_type = definition.voxel_type(self)?;
+        let properties = self
+            .properties
+            .get_mut(usize::from(u16::from(voxel_type)))
+            .with_context(|| format!("No properties for voxel type {voxel_type:?}"))?;
+        definition.apply(properties)?;
+        Ok(voxel_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn definitions_set_the_material_and_otherwise_keep_it() {
+        let mut properties = VoxelProperties::default();
+        assert_eq!(
+            properties.material(),
+            MaterialKind::Generic,
+            "Voxels without a material should be generic"
+        );
+
+        let definition =
+            toml::from_str::<VoxelDefinition>("voxel_type = \"stone\"\nmaterial = \"foliage\"")
+                .expect("The definition should parse");
+        definition
+            .apply(&mut properties)
+            .expect("The definition should apply");
+        assert_eq!(
+            properties.material(),
+            MaterialKind::Foliage,
+            "The definition should set the material"
+        );
+
+        let definition = toml::from_str::<VoxelDefinition>("voxel_type = \"stone\"")
+            .expect("The definition should parse");
+        definition
+            .apply(&mut properties)
+            .expect("The definition should apply");
+        assert_eq!(
+            properties.material(),
+            MaterialKind::Foliage,
+            "A definition without a material should keep the current one"
+        );
+        assert!(
+            toml::from_str::<VoxelDefinition>("voxel_type = \"stone\"\nmaterial = \"wool\"")
+                .is_err(),
+            "Unknown materials should be rejected"
+        );
+    }
+
+    #[test]
+    fn every_voxel_type_is_found_by_name_and_id() {
+        let voxel_registry = VoxelRegistry::init();
+        for voxel_type in std::iter::once(VoxelType::Air).chain(VoxelType::placeable()) {
+            assert_eq!(
+                voxel_registry.id(voxel_type.name()),
+                Some(voxel_type),
+                "{voxel_type:?} should be found by its name"
+            );
+            assert!(
+                voxel_registry.get(u16::from(voxel_type)).is_some(),
+                "{voxel_type:?} should have properties under its id"
+            );
+        }
+        assert_eq!(
+            voxel_registry.id("Glowing Ore"),
+            None,
+            "Display names should not be accepted as names"
+        );
+        assert!(
+            voxel_registry
+                .get(VoxelType::placeable().len() as u16 + 1)
+                .is_none(),
+            "Ids past the last type should have no properties"
+        );
+    }
+
+    #[test]
+    fn shipped_definitions_load_and_match_the_built_in_voxels() {
+        let directory =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/data/definitions/voxels");
+        let voxel_registry = VoxelRegistry::load_from_directory(&directory)
+            .expect("The shipped voxel definitions should load");
+        let grass = voxel_registry.get_properties(&VoxelType::Grass);
+        assert_eq!(
+            (
+                grass.textures().texture(VoxelFace::Top),
+                grass.textures().texture(VoxelFace::Front),
+            ),
+            (TextureType::GrassTop, TextureType::GrassSide),
+            "Grass should keep its top and side textures"
+        );
+        assert_eq!(
+            voxel_registry
+                .get_properties(&VoxelType::Leaves)
+                .render_layer(),
+            RenderLayer::Cutout,
+            "Leaves should stay in the cutout layer"
+        );
+    }
+
+    #[test]
+    fn invalid_definitions_are_reported_with_their_directory() {
+        let directory = std::env::temp_dir().join(format!(
+            "cairn-invalid-voxel-definitions-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&directory).expect("The test directory should be created");
+        std::fs::write(directory.join("stone.toml"), "name = [")
+            .expect("The malformed definition should be written");
+
+        let error = VoxelRegistry::load_from_directory(&directory)
+            .err()
+            .expect("A malformed definition should fail to load");
+        std::fs::remove_dir_all(&directory).expect("The test directory should be removed");
+        assert!(
+            matches!(&error, CairnError::AssetLoad { path, .. } if *path == directory),
+            "The error should name the definitions directory, got {error}"
+        );
+    }
+
+    #[test]
+    fn missing_or_empty_definitions_fall_back_to_air_and_stone() {
+        let missing = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/data/definitions/missing");
+        // Holds only textures, so no definitions
+        let empty = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/textures/voxels");
+        for directories in [vec![missing.as_path()], vec![empty.as_path()], vec![]] {
+            let voxel_registry = VoxelRegistry::load_from_directories(&directories)
+                .expect("Missing or empty directories should not be an error");
+            assert!(
+                voxel_registry
+                    .get_properties(&VoxelType::Air)
+                    .is_invisible(),
+                "Air should stay invisible in {directories:?}"
+            );
+            for voxel_type in VoxelType::placeable() {
+                let properties = voxel_registry.get_properties(&voxel_type);
+                assert!(
+                    properties.textures().texture(VoxelFace::Top) == TextureType::Stone
+                        && properties.material() == MaterialKind::Stone,
+                    "{voxel_type:?} should fall back to stone for {directories:?}"
+                );
+            }
         }
     }
 }
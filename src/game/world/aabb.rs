@@ -0,0 +1,74 @@
+use crate::game::world::ChunkPosition;
+use glam::f32::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct Aabb {
+    min: Vec3,
+    max: Vec3,
+}
+
+impl Aabb {
+    pub(crate) fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub(crate) fn from_chunk(chunk_position: ChunkPosition) -> Self {
+        // Voxel meshes are centered on their integer coordinates
+        let min = Vec3::from(chunk_position.min_corner().get_f32());
+        let max = Vec3::from(chunk_position.max_corner().get_f32());
+        Self::new(min - 0.5, max + 0.5)
+    }
+
+    /// The smallest box containing every point, or `None` when there are none.
+    pub(crate) fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        points.into_iter().fold(None, |aabb: Option<Self>, point| {
+            Some(aabb.map_or(Self::new(point, point), |aabb| {
+                Self::new(aabb.min.min(point), aabb.max.max(point))
+            }))
+        })
+    }
+
+    pub(crate) fn min(&self) -> Vec3 {
+        self.min
+    }
+
+    pub(crate) fn max(&self) -> Vec3 {
+        self.max
+    }
+
+    pub(crate) fn union(&self, other: &Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub(crate) fn corners(&self) -> [Vec3; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(max.x, max.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+        ]
+    }
+
+    pub(crate) fn edges(&self) -> [(Vec3, Vec3); 12] {
+        let [c0, c1, c2, c3, c4, c5, c6, c7] = self.corners();
+        [
+            (c0, c1),
+            (c1, c2),
+            (c2, c3),
+            (c3, c0),
+            (c4, c5),
+            (c5, c6),
+            (c6, c7),
+            (c7, c4),
+            (c0, c4),
+            (c1, c5),
+            (c2, c6),
+            (c3, c7),
+        ]
+    }
+}
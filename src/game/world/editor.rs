@@ -0,0 +1,351 @@
+use crate::game::chunk::{CHUNK_SIZE, Chunk};
+use crate::game::voxel::{VoxelProperties, VoxelRegistry, VoxelTextures, VoxelType};
+use crate::game::world::{ChunkPosition, LocalChunkPosition, WorldPosition};
+use log::warn;
+use std::collections::{HashMap, HashSet};
+
+pub(crate) struct WorldEditor<'a> {
+    chunk_data: &'a mut HashMap<ChunkPosition, Chunk>,
+    texture_overrides: &'a mut HashMap<(ChunkPosition, LocalChunkPosition), VoxelTextures>,
+    voxel_registry: &'a VoxelRegistry,
+    touched_chunks: HashSet<ChunkPosition>,
+    changed_positions: HashSet<WorldPosition>,
+}
+
+impl<'a> WorldEditor<'a> {
+    pub(crate) fn new(
+        chunk_data: &'a mut HashMap<ChunkPosition, Chunk>,
+        texture_overrides: &'a mut HashMap<(ChunkPosition, LocalChunkPosition), VoxelTextures>,
+        voxel_registry: &'a VoxelRegistry,
+    ) -> Self {
+        Self {
+            chunk_data,
+            texture_overrides,
+            voxel_registry,
+            touched_chunks: HashSet::new(),
+            changed_positions: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn is_loaded(&self, world_position: WorldPosition) -> bool {
+        self.chunk_data
+            .contains_key(&world_position.chunk_position())
+    }
+
+    pub(crate) fn get_voxel_type(&self, world_position: WorldPosition) -> VoxelType {
+        let (chunk_position, local_chunk_position) = world_position.local_chunk_position();
+        match self.chunk_data.get(&chunk_position) {
+            Some(chunk) => chunk.get_voxel_type(local_chunk_position),
+            None => VoxelType::Air,
+        }
+    }
+
+    pub(crate) fn voxel_properties(&self, voxel_type: VoxelType) -> &VoxelProperties {
+        self.voxel_registry.get_properties(&voxel_type)
+    }
+
+    pub(crate) fn set_voxel(&mut self, world_position: WorldPosition, voxel_type: VoxelType) {
+        let (chunk_position, local_chunk_position) = world_position.local_chunk_position();
+        let Some(chunk) = self.chunk_data.get_mut(&chunk_position) else {
+            let (x, y, z) = chunk_position.get();
+            warn!("Attempted to set voxel in unloaded chunk: ({x}, {y}, {z})");
+            return;
+        };
+
+        chunk.set_voxel(local_chunk_position, voxel_type);
+        self.texture_overrides
+            .remove(&(chunk_position, local_chunk_position));
+        self.touched_chunks.insert(chunk_position);
+        self.changed_positions.insert(world_position);
+
+        // Meshes of neighboring chunks cull and shade their border faces against this voxel
+        for neighbor_chunk_position in chunk_position.surrounding() {
+            let (min_x, min_y, min_z) = neighbor_chunk_position.min_corner().get();
+            let (max_x, max_y, max_z) = neighbor_chunk_position.max_corner().get();
+            let (x, y, z) = world_position.get();
+            if (min_x - 1..=max_x + 1).contains(&x)
+                && (min_y - 1..=max_y + 1).contains(&y)
+                && (min_z - 1..=max_z + 1).contains(&z)
+            {
+                self.touched_chunks.insert(neighbor_chunk_position);
+            }
+        }
+    }
+
+    /// Places a voxel that is drawn with `textures` instead of its registry defaults. The
+    /// override lasts until the voxel is replaced or its chunk unloads.
+    pub(crate) fn set_voxel_with_textures(
+        &mut self,
+        world_position: WorldPosition,
+        voxel_type: VoxelType,
+        textures: VoxelTextures,
+    ) {
+        self.set_voxel(world_position, voxel_type);
+        if self.is_loaded(world_position) {
+            let (chunk_position, local_chunk_position) = world_position.local_chunk_position();
+            self.texture_overrides
+                .insert((chunk_position, local_chunk_position), textures);
+        }
+    }
+
+    /// Fills the inclusive box between two corners, skipping positions in unloaded chunks.
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) fn fill_box(
+        &mut self,
+        min: WorldPosition,
+        max: WorldPosition,
+        voxel_type: VoxelType,
+    ) {
+        let ((min_x, min_y, min_z), (max_x, max_y, max_z)) = (min.get(), max.get());
+        for x in min_x.min(max_x)..=min_x.max(max_x) {
+            for y in min_y.min(max_y)..=min_y.max(max_y) {
+                for z in min_z.min(max_z)..=min_z.max(max_z) {
+                    let world_position = WorldPosition::new(x, y, z);
+                    if self.is_loaded(world_position) {
+                        self.set_voxel(world_position, voxel_type);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fills every voxel whose center lies within `radius` of `center`, skipping positions in
+    /// unloaded chunks.
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) fn fill_sphere(
+        &mut self,
+        center: WorldPosition,
+        radius: i32,
+        voxel_type: VoxelType,
+    ) {
+        let radius = radius.max(0);
+        for world_position in Self::box_positions(center, radius) {
+            if world_position.distance_squared(center) <= radius * radius
+                && self.is_loaded(world_position)
+            {
+                self.set_voxel(world_position, voxel_type);
+            }
+        }
+    }
+
+    /// Sets every loaded voxel in the columns between the corners to air above `y` and to
+    /// `voxel_type` at and below it, down to the lowest loaded chunk. Returns the chunks that
+    /// were skipped because they are not loaded.
+    pub(crate) fn flatten(
+        &mut self,
+        min_xz: (i32, i32),
+        max_xz: (i32, i32),
+        y: i32,
+        voxel_type: VoxelType,
+    ) -> HashSet<ChunkPosition> {
+        let Some((min_y, max_y)) = self.loaded_y_range() else {
+            return HashSet::new();
+        };
+        self.edit_region(
+            WorldPosition::new(min_xz.0, min_y, min_xz.1),
+            WorldPosition::new(max_xz.0, max_y, max_xz.1),
+            |world_position, _| {
+                let (_, voxel_y, _) = world_position.get();
+                Some(if voxel_y > y {
+                    VoxelType::Air
+                } else {
+                    voxel_type
+                })
+            },
+        )
+    }
+
+    /// Replaces every `from` voxel in the inclusive box between two corners with `to`. Returns
+    /// the chunks that were skipped because they are not loaded.
+    pub(crate) fn replace_all(
+        &mut self,
+        min: WorldPosition,
+        max: WorldPosition,
+        from: VoxelType,
+        to: VoxelType,
+    ) -> HashSet<ChunkPosition> {
+        self.edit_region(min, max, |_, voxel_type| (voxel_type == from).then_some(to))
+    }
+
+    /// Applies `replacement` to each loaded voxel in the box, only writing voxels whose type
+    /// actually changes so untouched chunks are not remeshed.
+    fn edit_region(
+        &mut self,
+        min: WorldPosition,
+        max: WorldPosition,
+        replacement: impl Fn(WorldPosition, VoxelType) -> Option<VoxelType>,
+    ) -> HashSet<ChunkPosition> {
+        let ((min_x, min_y, min_z), (max_x, max_y, max_z)) = (min.get(), max.get());
+        let mut skipped_chunks = HashSet::new();
+        for x in min_x.min(max_x)..=min_x.max(max_x) {
+            for y in min_y.min(max_y)..=min_y.max(max_y) {
+                for z in min_z.min(max_z)..=min_z.max(max_z) {
+                    let world_position = WorldPosition::new(x, y, z);
+                    if !self.is_loaded(world_position) {
+                        skipped_chunks.insert(world_position.chunk_position());
+                        continue;
+                    }
+                    let voxel_type = self.get_voxel_type(world_position);
+                    if let Some(new_voxel_type) = replacement(world_position, voxel_type)
+                        && new_voxel_type != voxel_type
+                    {
+                        self.set_voxel(world_position, new_voxel_type);
+                    }
+                }
+            }
+        }
+        skipped_chunks
+    }
+
+    /// The lowest and highest world y covered by any loaded chunk.
+    fn loaded_y_range(&self) -> Option<(i32, i32)> {
+        let chunk_ys = self
+            .chunk_data
+            .keys()
+            .map(|chunk_position| chunk_position.get().1);
+        let (min_chunk_y, max_chunk_y) = chunk_ys.fold(None, |range, chunk_y| match range {
+            None => Some((chunk_y, chunk_y)),
+            Some((min, max)) => Some((chunk_y.min(min), chunk_y.max(max))),
+        })?;
+        let chunk_size = CHUNK_SIZE as i32;
+        Some((min_chunk_y * chunk_size, (max_chunk_y + 1) * chunk_size - 1))
+    }
+
+    fn box_positions(center: WorldPosition, radius: i32) -> impl Iterator<Item = WorldPosition> {
+        (-radius..=radius).flat_map(move |dx| {
+            (-radius..=radius)
+                .flat_map(move |dy| (-radius..=radius).map(move |dz| center.offset(dx, dy, dz)))
+        })
+    }
+
+    pub(crate) fn finish(self) -> (HashSet<ChunkPosition>, HashSet<WorldPosition>) {
+        (self.touched_chunks, self.changed_positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::render::TextureType;
+
+    fn loaded_chunks(chunk_positions: &[ChunkPosition]) -> HashMap<ChunkPosition, Chunk> {
+        chunk_positions
+            .iter()
+            .map(|chunk_position| (*chunk_position, Chunk::empty(*chunk_position)))
+            .collect()
+    }
+
+    fn count_voxels(chunk_data: &HashMap<ChunkPosition, Chunk>, voxel_type: VoxelType) -> usize {
+        chunk_data
+            .values()
+            .flat_map(|chunk| chunk.voxels())
+            .filter(|voxel| **voxel == u16::from(voxel_type))
+            .count()
+    }
+
+    #[test]
+    fn fill_box_spans_chunks_and_skips_unloaded_ones() {
+        let mut chunk_data =
+            loaded_chunks(&[ChunkPosition::new(0, 0, 0), ChunkPosition::new(1, 0, 0)]);
+        let mut texture_overrides = HashMap::new();
+        let voxel_registry = VoxelRegistry::init();
+        let mut editor = WorldEditor::new(&mut chunk_data, &mut texture_overrides, &voxel_registry);
+
+        let last = CHUNK_SIZE as i32 - 1;
+        editor.fill_box(
+            WorldPosition::new(last + 2, 1, 1),
+            WorldPosition::new(last - 1, 0, -1),
+            VoxelType::Stone,
+        );
+        assert_eq!(
+            editor.get_voxel_type(WorldPosition::new(last, 0, 0)),
+            VoxelType::Stone,
+            "The box should reach the last voxel of the first chunk"
+        );
+        assert_eq!(
+            editor.get_voxel_type(WorldPosition::new(last + 1, 1, 1)),
+            VoxelType::Stone,
+            "The box should continue into the next chunk"
+        );
+
+        let (touched_chunks, changed_positions) = editor.finish();
+        assert_eq!(
+            changed_positions.len(),
+            4 * 2 * 2,
+            "Only the voxels in loaded chunks should change"
+        );
+        assert!(
+            touched_chunks.contains(&ChunkPosition::new(0, 0, 0))
+                && touched_chunks.contains(&ChunkPosition::new(1, 0, 0)),
+            "Both chunks the box crosses should be remeshed"
+        );
+        assert_eq!(
+            count_voxels(&chunk_data, VoxelType::Stone),
+            4 * 2 * 2,
+            "Positions in the unloaded chunk below should have been skipped"
+        );
+    }
+
+    #[test]
+    fn texture_overrides_last_until_the_voxel_is_replaced() {
+        let chunk_position = ChunkPosition::new(0, 0, 0);
+        let mut chunk_data = loaded_chunks(&[chunk_position]);
+        let mut texture_overrides = HashMap::new();
+        let voxel_registry = VoxelRegistry::init();
+        let mut editor = WorldEditor::new(&mut chunk_data, &mut texture_overrides, &voxel_registry);
+
+        let world_position = WorldPosition::new(1, 2, 3);
+        let textures = VoxelTextures::uniform(TextureType::Sand);
+        editor.set_voxel_with_textures(world_position, VoxelType::Stone, textures.clone());
+        editor.set_voxel_with_textures(
+            WorldPosition::new(-1, 0, 0),
+            VoxelType::Stone,
+            textures.clone(),
+        );
+        let key = (chunk_position, LocalChunkPosition::new(1, 2, 3));
+        assert!(
+            editor.texture_overrides.get(&key) == Some(&textures),
+            "The placed voxel should carry its textures"
+        );
+        assert_eq!(
+            editor.texture_overrides.len(),
+            1,
+            "A voxel in an unloaded chunk should not leave an override behind"
+        );
+
+        editor.set_voxel(world_position, VoxelType::Dirt);
+        assert!(
+            editor.texture_overrides.is_empty(),
+            "Replacing the voxel should drop its override"
+        );
+    }
+
+    #[test]
+    fn fill_sphere_fills_voxels_within_the_radius() {
+        let mut chunk_data = loaded_chunks(&[ChunkPosition::new(0, 0, 0)]);
+        let mut texture_overrides = HashMap::new();
+        let voxel_registry = VoxelRegistry::init();
+        let mut editor = WorldEditor::new(&mut chunk_data, &mut texture_overrides, &voxel_registry);
+
+        let center = WorldPosition::new(5, 5, 5);
+        editor.fill_sphere(center, 2, VoxelType::Stone);
+        assert_eq!(
+            editor.get_voxel_type(center.offset(0, 2, 0)),
+            VoxelType::Stone,
+            "A voxel exactly one radius away should be filled"
+        );
+        assert_eq!(
+            editor.get_voxel_type(center.offset(2, 1, 0)),
+            VoxelType::Air,
+            "A voxel beyond the radius should be left alone"
+        );
+        drop(editor.finish());
+
+        // 1 center, 6 at distance 1, 12 at √2, 8 at √3 and 6 at 2
+        assert_eq!(
+            count_voxels(&chunk_data, VoxelType::Stone),
+            33,
+            "The sphere should contain every voxel center within its radius"
+        );
+    }
+}
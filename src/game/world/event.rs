@@ -0,0 +1,11 @@
+use crate::game::voxel::{MaterialKind, VoxelType};
+use crate::game::world::{ChunkPosition, WorldPosition};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum ChunkEvent {
+    Loaded(ChunkPosition),
+    Unloaded(ChunkPosition),
+    /// A voxel was removed by `World::break_voxel`, as opposed to being overwritten by an edit.
+    /// The material lets listeners such as audio react without looking the type up.
+    VoxelBroken(WorldPosition, VoxelType, MaterialKind),
+}
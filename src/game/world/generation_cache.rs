@@ -0,0 +1,50 @@
+use crate::game::chunk::Chunk;
+use crate::game::world::ChunkPosition;
+use std::collections::HashMap;
+
+/// Unedited chunks kept after unloading, so that crossing back over a boundary reuses them
+/// instead of generating them again. The least recently stored chunk is evicted once full.
+pub(crate) struct GenerationCache {
+    capacity: usize,
+    chunks: HashMap<(u64, ChunkPosition), (Chunk, u64)>,
+    clock: u64,
+}
+
+impl GenerationCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            chunks: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Removes and returns the chunk generated from `seed` at `chunk_position`, if still held.
+    pub(crate) fn take(&mut self, seed: u64, chunk_position: ChunkPosition) -> Option<Chunk> {
+        self.chunks
+            .remove(&(seed, chunk_position))
+            .map(|(chunk, _)| chunk)
+    }
+
+    /// Only chunks exactly as generated belong here; edited ones would come back without their
+    /// edits.
+    pub(crate) fn insert(&mut self, seed: u64, chunk_position: ChunkPosition, chunk: Chunk) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.chunks.len() >= self.capacity
+            && let Some(oldest) = self
+                .chunks
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key)
+        {
+            self.chunks.remove(&oldest);
+        }
+
+        self.clock += 1;
+        self.chunks
+            .insert((seed, chunk_position), (chunk, self.clock));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::game::mesh::LayeredMesh;
+use crate::game::world::ChunkPosition;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+
+type FinishedMesh = (ChunkPosition, u64, Option<LayeredMesh>);
+
+/// Chunk meshes built on the rayon pool while frames keep rendering. At most `capacity` jobs
+/// are in flight, and each is tagged with the chunk's mesh generation when it was queued so
+/// that a result for a chunk edited or unloaded in the meantime can be told apart and dropped.
+pub(crate) struct MeshQueue {
+    capacity: usize,
+    in_flight: HashMap<ChunkPosition, Arc<AtomicBool>>,
+    /// Counted before each send, so it never falls below the results waiting in the channel.
+    finished_count: Arc<AtomicUsize>,
+    sender: Sender<FinishedMesh>,
+    // Only ever locked by the owning world; the mutex just makes the queue `Sync`
+    receiver: Mutex<Receiver<FinishedMesh>>,
+}
+
+impl MeshQueue {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            capacity: capacity.max(1),
+            in_flight: HashMap::new(),
+            finished_count: Arc::new(AtomicUsize::new(0)),
+            sender,
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    /// Jobs queued or running whose results have not been received yet.
+    pub(crate) fn depth(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    pub(crate) fn free_slots(&self) -> usize {
+        self.capacity.saturating_sub(self.in_flight.len())
+    }
+
+    /// Whether any results are waiting to be received.
+    pub(crate) fn has_finished(&self) -> bool {
+        self.finished_count.load(Ordering::Acquire) > 0
+    }
+
+    pub(crate) fn contains(&self, chunk_position: &ChunkPosition) -> bool {
+        self.in_flight.contains_key(chunk_position)
+    }
+
+    pub(crate) fn submit(
+        &mut self,
+        chunk_position: ChunkPosition,
+        generation: u64,
+        build: impl FnOnce() -> LayeredMesh + Send + 'static,
+    ) {
+        let is_cancelled = Arc::new(AtomicBool::new(false));
+        self.in_flight
+            .insert(chunk_position, Arc::clone(&is_cancelled));
+        let sender = self.sender.clone();
+        let finished_count = Arc::clone(&self.finished_count);
+        rayon::spawn(move || {
+            let chunk_mesh = (!is_cancelled.load(Ordering::Relaxed)).then(build);
+            finished_count.fetch_add(1, Ordering::Release);
+            // The queue only goes away with its world, when nobody wants the mesh anymore
+            sender.send((chunk_position, generation, chunk_mesh)).ok();
+        });
+    }
+
+    /// Lets a job that has not started yet skip building. The chunk stays in flight until its
+    /// result comes back, so it is not queued twice.
+    pub(crate) fn cancel(&self, chunk_position: &ChunkPosition) {
+        if let Some(is_cancelled) = self.in_flight.get(chunk_position) {
+            is_cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn cancel_all(&self) {
+        self.in_flight
+            .values()
+            .for_each(|is_cancelled| is_cancelled.store(true, Ordering::Relaxed));
+    }
+
+    /// Meshes finished since the last call, with their generations. Cancelled jobs are
+    /// left out.
+    pub(crate) fn receive(&mut self) -> Vec<(ChunkPosition, u64, LayeredMesh)> {
+        let finished = self
+            .receiver
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .try_iter()
+            .collect::<Vec<FinishedMesh>>();
+        self.finished_count
+            .fetch_sub(finished.len(), Ordering::AcqRel);
+        finished
+            .into_iter()
+            .filter_map(|(chunk_position, generation, chunk_mesh)| {
+                self.in_flight.remove(&chunk_position);
+                chunk_mesh.map(|chunk_mesh| (chunk_position, generation, chunk_mesh))
+            })
+            .collect()
+    }
+}
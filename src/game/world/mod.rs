@@ -1,98 +1,733 @@
-use rayon::iter::ParallelIterator;
+mod aabb;
+mod editor;
+mod event;
+mod fnv;
+mod generation_cache;
+mod mesh_queue;
 mod position;
+mod rng;
 
+pub(crate) use aabb::*;
+pub(crate) use editor::*;
+pub(crate) use event::*;
+pub(crate) use fnv::*;
+pub(crate) use generation_cache::*;
+pub(crate) use mesh_queue::*;
 pub(crate) use position::*;
+pub(crate) use rng::*;
 
-use crate::game::chunk::Chunk;
-use crate::game::mesh::{Mesh, OccludingVoxelNeighbors};
+use crate::game::chunk::{CHUNK_SIZE, Chunk, TERRAIN_HEIGHT};
+use crate::game::mesh::{LayeredMesh, Mesh, MeshCache, OccludingVoxelNeighbors};
 use crate::game::render::TextureAtlas;
-use crate::game::voxel::{VoxelRegistry, VoxelType};
-use rayon::iter::IntoParallelRefIterator as _;
+use crate::game::voxel::{MaterialKind, RandomTickRule, VoxelRegistry, VoxelTextures, VoxelType};
+use glam::Vec3;
+use log::warn;
+use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
-use std::f32::consts::PI;
+use std::fmt;
+use std::hash::{Hash as _, Hasher as _};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-const RENDER_DISTANCE_XZ: i32 = 6;
-const RENDER_DISTANCE_Y: i32 = 3;
-const CHUNK_RENDER_MAXIMUM: usize =
-    (PI * RENDER_DISTANCE_XZ.pow(2) as f32 * (2 * RENDER_DISTANCE_Y + 1) as f32).ceil() as usize;
+pub(crate) const RENDER_DISTANCE_XZ: i32 = 6;
+pub(crate) const RENDER_DISTANCE_Y: i32 = 3;
+pub(crate) const WORLD_SEED: u64 = 0;
+pub(crate) const CHUNK_FADE_DURATION: Duration = Duration::from_millis(500);
+// About 16 MiB of unloaded chunks at two bytes per voxel
+const GENERATION_CACHE_CAPACITY: usize = 256;
+/// Voxels picked in every loaded chunk each tick for their random tick rule.
+const RANDOM_TICKS_PER_CHUNK: u64 = 3;
+/// Keeps the hash that picks random tick positions independent of the one generation uses.
+const RANDOM_TICK_SALT: u64 = 0x7E47_0AE5_5EED_0002;
+
+/// The region around the camera whose chunks are kept loaded.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RenderShape {
+    /// Circular in XZ with the radius `render_distance_xz`, spanning `render_distance_up` chunks
+    /// above and `render_distance_down` below.
+    #[default]
+    Cylinder,
+    /// Every chunk within `render_distance_xz` in all three axes; the vertical distances are unused.
+    Sphere,
+}
+
+/// Approximate memory held by loaded chunks and their meshes.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct MemoryReport {
+    chunk_count: usize,
+    chunk_data_bytes: usize,
+    mesh_count: usize,
+    chunk_mesh_bytes: usize,
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MEBIBYTE: f32 = 1024.0 * 1024.0;
+        write!(
+            formatter,
+            "{:.1} MiB in {} chunks + {:.1} MiB in {} meshes",
+            self.chunk_data_bytes as f32 / MEBIBYTE,
+            self.chunk_count,
+            self.chunk_mesh_bytes as f32 / MEBIBYTE,
+            self.mesh_count,
+        )
+    }
+}
 
 pub(crate) struct World {
-    voxel_registry: VoxelRegistry,
-    texture_atlas: TextureAtlas,
+    seed: u64,
+    epoch: Instant,
+    voxel_registry: Arc<VoxelRegistry>,
+    texture_atlas: Arc<TextureAtlas>,
+    render_distance_xz: i32,
+    render_distance_up: i32,
+    render_distance_down: i32,
+    render_shape: RenderShape,
+    chunk_load_budget: usize,
     last_update_position: Option<ChunkPosition>,
+    pending_chunk_loads: Vec<ChunkPosition>,
     chunk_data: HashMap<ChunkPosition, Chunk>,
-    chunk_meshes: HashMap<ChunkPosition, Mesh>,
+    /// Loaded chunks changed since generation, which must not go into the generation cache.
+    edited_chunks: HashSet<ChunkPosition>,
+    generation_cache: GenerationCache,
+    texture_overrides: HashMap<(ChunkPosition, LocalChunkPosition), VoxelTextures>,
+    chunk_meshes: HashMap<ChunkPosition, LayeredMesh>,
+    /// Bumped whenever a chunk's mesh is invalidated, so background meshes built before then
+    /// can be recognized as stale.
+    mesh_generations: HashMap<ChunkPosition, u64>,
+    mesh_generation: u64,
+    mesh_queue: Option<MeshQueue>,
+    /// When each loaded chunk's first mesh was stored, so chunks fade in once they can be
+    /// drawn rather than when their voxels were generated.
+    chunk_fade_starts: HashMap<ChunkPosition, f32>,
+    fading_chunks: HashSet<ChunkPosition>,
+    is_chunk_flashing_enabled: bool,
+    occluding_chunks: HashSet<ChunkPosition>,
+    mesh_cache: Option<MeshCache>,
+    pending_voxel_updates: HashSet<WorldPosition>,
+    chunk_events: Vec<ChunkEvent>,
+    tick_count: u64,
 }
 
 impl World {
-    pub(crate) fn new() -> Self {
-        let voxel_registry = VoxelRegistry::init();
-        let texture_atlas = TextureAtlas::init();
+    /// Generation must be a pure function of the seed and chunk position so that two worlds
+    /// built from the same seed are identical.
+    pub(crate) fn new_with_seed(seed: u64) -> Self {
+        Self::with_assets(
+            seed,
+            Arc::new(VoxelRegistry::init()),
+            Arc::new(TextureAtlas::init()),
+        )
+    }
+
+    fn with_assets(
+        seed: u64,
+        voxel_registry: Arc<VoxelRegistry>,
+        texture_atlas: Arc<TextureAtlas>,
+    ) -> Self {
         Self {
+            seed,
+            epoch: Instant::now(),
             voxel_registry,
             texture_atlas,
+            render_distance_xz: RENDER_DISTANCE_XZ,
+            render_distance_up: RENDER_DISTANCE_Y,
+            render_distance_down: RENDER_DISTANCE_Y,
+            render_shape: RenderShape::default(),
+            chunk_load_budget: usize::MAX,
             last_update_position: None,
+            pending_chunk_loads: Vec::new(),
             chunk_data: HashMap::new(),
+            edited_chunks: HashSet::new(),
+            generation_cache: GenerationCache::new(GENERATION_CACHE_CAPACITY),
+            texture_overrides: HashMap::new(),
             chunk_meshes: HashMap::new(),
+            mesh_generations: HashMap::new(),
+            mesh_generation: 0,
+            mesh_queue: None,
+            chunk_fade_starts: HashMap::new(),
+            fading_chunks: HashSet::new(),
+            is_chunk_flashing_enabled: false,
+            occluding_chunks: HashSet::new(),
+            mesh_cache: None,
+            pending_voxel_updates: HashSet::new(),
+            chunk_events: Vec::new(),
+            tick_count: 0,
         }
     }
 
     pub(crate) fn update_chunks(&mut self, origin_chunk_position: ChunkPosition) {
         self.last_update_position = Some(origin_chunk_position);
 
-        let chunks_in_range_vec = Self::determine_chunks_in_range(origin_chunk_position);
+        let chunks_in_range_vec = self.determine_chunks_in_range(origin_chunk_position);
         let chunks_in_range_set = chunks_in_range_vec
             .par_iter()
             .copied()
             .collect::<HashSet<ChunkPosition>>();
         self.unload_out_of_range_chunks(&chunks_in_range_set);
-        self.load_in_range_chunks(&chunks_in_range_vec);
+        self.queue_in_range_chunks(&chunks_in_range_vec);
+        self.load_pending_chunks();
     }
 
-    fn determine_chunks_in_range(origin_chunk_position: ChunkPosition) -> Vec<ChunkPosition> {
-        let (origin_x, origin_y, origin_z) = origin_chunk_position.get();
-        let (min_x, max_x) = (origin_x - RENDER_DISTANCE_XZ, origin_x + RENDER_DISTANCE_XZ);
-        let (min_y, max_y) = (origin_y - RENDER_DISTANCE_Y, origin_y + RENDER_DISTANCE_Y);
-        let (min_z, max_z) = (origin_z - RENDER_DISTANCE_XZ, origin_z + RENDER_DISTANCE_XZ);
+    pub(crate) fn set_chunk_load_budget(&mut self, chunk_load_budget: usize) {
+        self.chunk_load_budget = chunk_load_budget.max(1);
+    }
 
-        let mut chunks_in_range = Vec::with_capacity(CHUNK_RENDER_MAXIMUM);
-        for x in min_x..=max_x {
-            let distance_x_squared = (x - origin_x).pow(2);
-            if distance_x_squared > RENDER_DISTANCE_XZ.pow(2) {
-                continue;
+    pub(crate) fn has_pending_chunk_loads(&self) -> bool {
+        !self.pending_chunk_loads.is_empty()
+    }
+
+    pub(crate) fn load_pending_chunks(&mut self) {
+        for _ in 0..self.chunk_load_budget {
+            let Some(chunk_position) = self.pending_chunk_loads.pop() else {
+                break;
+            };
+            if let Entry::Vacant(entry) = self.chunk_data.entry(chunk_position) {
+                let chunk = self
+                    .generation_cache
+                    .take(self.seed, chunk_position)
+                    .unwrap_or_else(|| Chunk::generate(chunk_position, self.seed));
+                let chunk = entry.insert(chunk);
+                if chunk.is_fully_occluding(&self.voxel_registry) {
+                    self.occluding_chunks.insert(chunk_position);
+                }
+                self.chunk_events.push(ChunkEvent::Loaded(chunk_position));
             }
+        }
+    }
+
+    /// Loads `render_distance_y` chunks both above and below the camera.
+    pub(crate) fn set_render_distance(&mut self, render_distance_xz: i32, render_distance_y: i32) {
+        self.set_horizontal_render_distance(render_distance_xz);
+        self.set_vertical_render_distance(render_distance_y, render_distance_y);
+    }
+
+    pub(crate) fn set_horizontal_render_distance(&mut self, render_distance_xz: i32) {
+        self.render_distance_xz = render_distance_xz.max(0);
+    }
+
+    /// Above ground most of the interesting chunks are below the camera, so fewer can be loaded
+    /// above it.
+    pub(crate) fn set_vertical_render_distance(
+        &mut self,
+        render_distance_up: i32,
+        render_distance_down: i32,
+    ) {
+        self.render_distance_up = render_distance_up.max(0);
+        self.render_distance_down = render_distance_down.max(0);
+    }
+
+    pub(crate) fn set_render_shape(&mut self, render_shape: RenderShape) {
+        self.render_shape = render_shape;
+    }
+
+    /// Exactly how many chunks are in range of any origin. The shape's volume would fall short
+    /// of the chunk positions inside it at small distances, e.g. π against 5 at a radius of 1.
+    pub(crate) fn chunk_render_maximum(&self) -> usize {
+        let radius_squared = self.render_distance_xz.pow(2);
+        let row = |remaining_squared: i32| (2 * remaining_squared.isqrt() + 1) as usize;
+        let disk = |disk_radius_squared: i32| {
+            let disk_radius = disk_radius_squared.isqrt();
+            (-disk_radius..=disk_radius)
+                .map(|x| row(disk_radius_squared - x.pow(2)))
+                .sum::<usize>()
+        };
+        match self.render_shape {
+            RenderShape::Cylinder => {
+                let (up, down) = self.vertical_render_distance();
+                disk(radius_squared) * (up + down + 1) as usize
+            }
+            RenderShape::Sphere => (-self.render_distance_xz..=self.render_distance_xz)
+                .map(|y| disk(radius_squared - y.pow(2)))
+                .sum(),
+        }
+    }
+
+    pub(crate) fn render_distance_xz(&self) -> i32 {
+        self.render_distance_xz
+    }
+
+    /// The distance from the camera to the far corner of the farthest chunk that can be loaded.
+    pub(crate) fn view_distance(&self) -> f32 {
+        let horizontal = (self.render_distance_xz + 1) as f32;
+        let (up, down) = self.vertical_render_distance();
+        let vertical = (up.max(down) + 1) as f32;
+        (horizontal.powi(2) * 2.0 + vertical.powi(2)).sqrt() * CHUNK_SIZE as f32
+    }
+
+    /// Chunks loaded above and below the camera's chunk.
+    fn vertical_render_distance(&self) -> (i32, i32) {
+        match self.render_shape {
+            RenderShape::Cylinder => (self.render_distance_up, self.render_distance_down),
+            RenderShape::Sphere => (self.render_distance_xz, self.render_distance_xz),
+        }
+    }
+
+    /// Whether `chunk_position` is within the render distance of `origin` for the current shape.
+    fn is_in_range(&self, origin: ChunkPosition, chunk_position: ChunkPosition) -> bool {
+        let (x, y, z) = (chunk_position - origin).get();
+        let (render_distance_up, render_distance_down) = self.vertical_render_distance();
+        let distance_y_squared = match self.render_shape {
+            RenderShape::Cylinder => 0,
+            RenderShape::Sphere => y.pow(2),
+        };
+        (-render_distance_down..=render_distance_up).contains(&y)
+            && x.pow(2) + distance_y_squared + z.pow(2) <= self.render_distance_xz.pow(2)
+    }
+
+    fn determine_chunks_in_range(
+        &self,
+        origin_chunk_position: ChunkPosition,
+    ) -> Vec<ChunkPosition> {
+        let (origin_x, origin_y, origin_z) = origin_chunk_position.get();
+        let render_distance_xz = self.render_distance_xz;
+        let (render_distance_up, render_distance_down) = self.vertical_render_distance();
+        let (min_x, max_x) = (origin_x - render_distance_xz, origin_x + render_distance_xz);
+        let (min_y, max_y) = (
+            origin_y - render_distance_down,
+            origin_y + render_distance_up,
+        );
+        let (min_z, max_z) = (origin_z - render_distance_xz, origin_z + render_distance_xz);
 
+        let mut chunks_in_range = Vec::with_capacity(self.chunk_render_maximum());
+        for x in min_x..=max_x {
             for y in min_y..=max_y {
                 for z in min_z..=max_z {
-                    let distance_z_squared = (z - origin_z).pow(2);
-                    let distance_squared = distance_x_squared + distance_z_squared;
-                    if distance_squared <= RENDER_DISTANCE_XZ.pow(2) {
-                        chunks_in_range.push(ChunkPosition::new(x, y, z));
+                    let chunk_position = ChunkPosition::new(x, y, z);
+                    if self.is_in_range(origin_chunk_position, chunk_position) {
+                        chunks_in_range.push(chunk_position);
                     }
                 }
             }
         }
 
         chunks_in_range.sort_by_cached_key(|chunk_position| {
-            let (x, y, z) = chunk_position.get();
-            (x - origin_x).pow(2) + (y - origin_y).pow(2) + (z - origin_z).pow(2)
+            let (x, y, z) = (*chunk_position - origin_chunk_position).get();
+            x.pow(2) + y.pow(2) + z.pow(2)
         });
 
         chunks_in_range
     }
 
-    fn load_in_range_chunks(&mut self, chunks_in_range: &[ChunkPosition]) {
-        for chunk_position in chunks_in_range {
-            if !self.chunk_data.contains_key(chunk_position) {
-                self.chunk_data
-                    .insert(*chunk_position, Chunk::dev_chunk(*chunk_position));
+    fn queue_in_range_chunks(&mut self, chunks_in_range: &[ChunkPosition]) {
+        // Stored farthest-first so the nearest chunk is popped off the end first
+        self.pending_chunk_loads = chunks_in_range
+            .iter()
+            .rev()
+            .filter(|chunk_position| !self.chunk_data.contains_key(chunk_position))
+            .copied()
+            .collect();
+    }
+
+    fn unload_out_of_range_chunks(&mut self, chunks_in_range: &HashSet<ChunkPosition>) {
+        let out_of_range_chunks = self
+            .chunk_data
+            .keys()
+            .filter(|chunk_position| !chunks_in_range.contains(chunk_position))
+            .copied()
+            .collect::<Vec<ChunkPosition>>();
+
+        let mut unloaded_occluding_chunks = Vec::new();
+        for chunk_position in out_of_range_chunks {
+            let Some(chunk) = self.chunk_data.remove(&chunk_position) else {
+                continue;
+            };
+            self.chunk_events.push(ChunkEvent::Unloaded(chunk_position));
+            self.mesh_generations.remove(&chunk_position);
+            if let Some(mesh_queue) = &self.mesh_queue {
+                mesh_queue.cancel(&chunk_position);
+            }
+            if self.occluding_chunks.remove(&chunk_position) {
+                unloaded_occluding_chunks.push(chunk_position);
+            }
+            // Edits are not persisted yet, so an edited chunk is simply dropped
+            if !self.edited_chunks.remove(&chunk_position) {
+                self.generation_cache
+                    .insert(self.seed, chunk_position, chunk);
             }
         }
+
+        self.texture_overrides
+            .retain(|(chunk_position, _), _textures| chunks_in_range.contains(chunk_position));
+        self.chunk_fade_starts
+            .retain(|chunk_position, _fade_start| chunks_in_range.contains(chunk_position));
+
+        // Neighbors may have been skipped as buried and now have an exposed face
+        for chunk_position in unloaded_occluding_chunks {
+            self.invalidate_chunk_meshes(chunk_position.neighbors());
+        }
     }
 
-    fn unload_out_of_range_chunks(&mut self, chunks_in_range: &HashSet<ChunkPosition>) {
+    pub(crate) fn drain_events(&mut self) -> Vec<ChunkEvent> {
+        std::mem::take(&mut self.chunk_events)
+    }
+
+    pub(crate) fn edit_batch(&mut self, edit: impl FnOnce(&mut WorldEditor<'_>)) {
+        let mut editor = WorldEditor::new(
+            &mut self.chunk_data,
+            &mut self.texture_overrides,
+            &self.voxel_registry,
+        );
+        edit(&mut editor);
+
+        let (touched_chunks, changed_positions) = editor.finish();
+        self.occluding_chunks
+            .retain(|chunk_position| !touched_chunks.contains(chunk_position));
+        self.occluding_chunks.extend(
+            touched_chunks
+                .iter()
+                .filter(|chunk_position| {
+                    self.chunk_data
+                        .get(chunk_position)
+                        .is_some_and(|chunk| chunk.is_fully_occluding(&self.voxel_registry))
+                })
+                .copied(),
+        );
+        self.invalidate_chunk_meshes(touched_chunks);
+        self.edited_chunks
+            .extend(changed_positions.iter().map(WorldPosition::chunk_position));
+        self.pending_voxel_updates.extend(changed_positions);
+    }
+
+    /// Levels the columns between the corners at `y`, filling with `voxel_type` up to it and
+    /// clearing everything above, as a single batch.
+    pub(crate) fn flatten(
+        &mut self,
+        min_xz: (i32, i32),
+        max_xz: (i32, i32),
+        y: i32,
+        voxel_type: VoxelType,
+    ) {
+        let mut skipped_chunks = HashSet::new();
+        self.edit_batch(|editor| {
+            skipped_chunks = editor.flatten(min_xz, max_xz, y, voxel_type);
+        });
+        Self::log_skipped_chunks("flatten", &skipped_chunks);
+    }
+
+    /// Places a voxel drawn with `textures` in place of its registry defaults, as a single
+    /// batch.
+    pub(crate) fn place_voxel_with_textures(
+        &mut self,
+        world_position: WorldPosition,
+        voxel_type: VoxelType,
+        textures: VoxelTextures,
+    ) {
+        self.edit_batch(|editor| {
+            editor.set_voxel_with_textures(world_position, voxel_type, textures);
+        });
+    }
+
+    /// Swaps every `from` voxel in the inclusive box between two corners for `to`, as a single
+    /// batch.
+    pub(crate) fn replace_all(
+        &mut self,
+        min: WorldPosition,
+        max: WorldPosition,
+        from: VoxelType,
+        to: VoxelType,
+    ) {
+        let mut skipped_chunks = HashSet::new();
+        self.edit_batch(|editor| skipped_chunks = editor.replace_all(min, max, from, to));
+        Self::log_skipped_chunks("replace", &skipped_chunks);
+    }
+
+    fn log_skipped_chunks(operation: &str, skipped_chunks: &HashSet<ChunkPosition>) {
+        if !skipped_chunks.is_empty() {
+            warn!(
+                "Skipped {} unloaded chunks in {operation}",
+                skipped_chunks.len()
+            );
+        }
+    }
+
+    /// Replaces a visible voxel with air, reporting what it was through `drain_events`.
+    pub(crate) fn break_voxel(&mut self, world_position: WorldPosition) -> bool {
+        let voxel_type = self.get_voxel_type(world_position);
+        if self
+            .voxel_registry
+            .get_properties(&voxel_type)
+            .is_invisible()
+        {
+            return false;
+        }
+
+        let material = self.get_material(world_position);
+        self.edit_batch(|editor| editor.set_voxel(world_position, VoxelType::Air));
+        self.chunk_events.push(ChunkEvent::VoxelBroken(
+            world_position,
+            voxel_type,
+            material,
+        ));
+        true
+    }
+
+    /// The first visible voxel along the ray. It walks from voxel to voxel across each boundary
+    /// the ray crosses (Amanatides and Woo), so a voxel the ray only clips is never skipped.
+    pub(crate) fn raycast(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Option<WorldPosition> {
+        let direction = direction.normalize_or_zero();
+        if direction == Vec3::ZERO {
+            return None;
+        }
+
+        // Voxels are centered on integer coordinates, so this puts their boundaries on integers
+        let start = origin + 0.5;
+        let mut cell = start.floor().as_ivec3();
+        let step = direction.signum().as_ivec3();
+        // Ray distance between two boundaries on each axis, and to the next one from here
+        let boundary_spacing = direction.recip().abs();
+        let mut next_boundary = Vec3::select(
+            direction.cmpeq(Vec3::ZERO),
+            Vec3::INFINITY,
+            Vec3::select(
+                direction.cmpgt(Vec3::ZERO),
+                cell.as_vec3() + 1.0 - start,
+                start - cell.as_vec3(),
+            ) * boundary_spacing,
+        );
+
+        let mut distance = 0.0;
+        while distance <= max_distance {
+            let world_position = WorldPosition::new(cell.x, cell.y, cell.z);
+            let voxel_type = self.get_voxel_type(world_position);
+            if !self
+                .voxel_registry
+                .get_properties(&voxel_type)
+                .is_invisible()
+            {
+                return Some(world_position);
+            }
+
+            let axis = next_boundary.min_position();
+            distance = next_boundary[axis];
+            cell[axis] += step[axis];
+            next_boundary[axis] += boundary_spacing[axis];
+        }
+        None
+    }
+
+    /// Advances the simulation by one fixed step. `App` calls this at a fixed rate independent
+    /// of the frame rate, so anything time-based here should count ticks rather than read the
+    /// clock.
+    pub(crate) fn tick(&mut self) {
+        self.apply_gravity();
+        self.apply_random_ticks();
+        self.tick_count += 1;
+    }
+
+    fn apply_gravity(&mut self) {
+        if self.pending_voxel_updates.is_empty() {
+            return;
+        }
+
+        // A change can release the voxel resting on top of it, so check that one as well
+        let mut candidates = std::mem::take(&mut self.pending_voxel_updates)
+            .into_iter()
+            .flat_map(|world_position| [world_position, world_position.top()])
+            .collect::<Vec<WorldPosition>>();
+        candidates.sort_unstable_by_key(|world_position| {
+            let (x, y, z) = world_position.get();
+            (y, x, z)
+        });
+        candidates.dedup();
+
+        self.edit_batch(|editor| {
+            for world_position in candidates {
+                let voxel_type = editor.get_voxel_type(world_position);
+                if !editor.voxel_properties(voxel_type).is_affected_by_gravity() {
+                    continue;
+                }
+
+                let below = world_position.bottom();
+                if !editor.is_loaded(below) || editor.get_voxel_type(below) != VoxelType::Air {
+                    continue;
+                }
+
+                editor.set_voxel(below, voxel_type);
+                editor.set_voxel(world_position, VoxelType::Air);
+            }
+        });
+    }
+
+    /// Picks `RANDOM_TICKS_PER_CHUNK` voxels in every loaded chunk and applies the random tick
+    /// rule of their type. The picks depend only on the seed, the tick count and the chunk, so
+    /// the same world ticked the same number of times changes the same way.
+    fn apply_random_ticks(&mut self) {
+        let chunk_volume = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as u32;
+        let first_draw = self.tick_count * RANDOM_TICKS_PER_CHUNK;
+        let mut candidates = self
+            .chunk_data
+            .keys()
+            .flat_map(|&chunk_position| {
+                let (chunk_x, chunk_y, chunk_z) = chunk_position.get();
+                let seed = self.seed ^ RANDOM_TICK_SALT;
+                (first_draw..first_draw + RANDOM_TICKS_PER_CHUNK).map(move |draw| {
+                    let index = (hash_to_rng(seed ^ draw, chunk_x, chunk_y, chunk_z) % chunk_volume)
+                        as usize;
+                    LocalChunkPosition::new(
+                        index % CHUNK_SIZE,
+                        index / CHUNK_SIZE % CHUNK_SIZE,
+                        index / (CHUNK_SIZE * CHUNK_SIZE),
+                    )
+                    .world_position(chunk_position)
+                })
+            })
+            .filter(|&world_position| {
+                let voxel_type = self.get_voxel_type(world_position);
+                let properties = self.voxel_registry.get_properties(&voxel_type);
+                properties.random_tick_rule().is_some()
+            })
+            .collect::<Vec<WorldPosition>>();
+        if candidates.is_empty() {
+            return;
+        }
+        // Rules read their neighbors, so edits are applied in a fixed order
+        candidates.sort_unstable_by_key(|world_position| {
+            let (x, y, z) = world_position.get();
+            (y, x, z)
+        });
+
+        self.edit_batch(|editor| {
+            for world_position in candidates {
+                let voxel_type = editor.get_voxel_type(world_position);
+                let above = editor.get_voxel_type(world_position.top());
+                let next_voxel_type = match editor.voxel_properties(voxel_type).random_tick_rule() {
+                    Some(RandomTickRule::GrowGrass) => (above == VoxelType::Air
+                        && is_next_to_grass(editor, world_position))
+                    .then_some(VoxelType::Grass),
+                    Some(RandomTickRule::DieWhenCovered) => editor
+                        .voxel_properties(above)
+                        .is_occluding()
+                        .then_some(VoxelType::Dirt),
+                    None => None,
+                };
+                if let Some(next_voxel_type) = next_voxel_type {
+                    editor.set_voxel(world_position, next_voxel_type);
+                }
+            }
+        });
+    }
+
+    pub(crate) fn memory_report(&self) -> MemoryReport {
+        MemoryReport {
+            chunk_count: self.chunk_data.len(),
+            chunk_data_bytes: self.chunk_data.values().map(Chunk::voxel_bytes).sum(),
+            mesh_count: self.chunk_meshes.len(),
+            chunk_mesh_bytes: self.chunk_meshes.values().map(LayeredMesh::byte_len).sum(),
+        }
+    }
+
+    /// Loaded chunks with no mesh yet, either newly loaded or dropped by an edit to them or a
+    /// neighbor, sorted by position.
+    pub(crate) fn pending_remesh_chunks(&self) -> Vec<ChunkPosition> {
+        let mut pending_remesh_chunks = self
+            .chunk_data
+            .keys()
+            .filter(|chunk_position| !self.chunk_meshes.contains_key(chunk_position))
+            .copied()
+            .collect::<Vec<ChunkPosition>>();
+        pending_remesh_chunks.sort_by_key(|chunk_position| chunk_position.get());
+        pending_remesh_chunks
+    }
+
+    /// How many loaded chunks have no mesh yet, without collecting them.
+    pub(crate) fn pending_remesh_count(&self) -> usize {
         self.chunk_data
-            .retain(|pos, _chunk| chunks_in_range.contains(pos));
+            .keys()
+            .filter(|chunk_position| !self.chunk_meshes.contains_key(chunk_position))
+            .count()
+    }
+
+    /// Also true once a chunk merged in as fading has finished fading, so that it moves back
+    /// into the opaque layer.
+    ///
+    /// With background meshing, chunks already in flight only count once their mesh arrives.
+    /// Queued chunks beyond the queue's capacity still count, so the queue is topped up.
+    pub(crate) fn has_pending_meshes(&self) -> bool {
+        let has_unmeshed_chunks = match &self.mesh_queue {
+            Some(mesh_queue) => {
+                mesh_queue.has_finished()
+                    || self.chunk_data.keys().any(|chunk_position| {
+                        !self.chunk_meshes.contains_key(chunk_position)
+                            && !mesh_queue.contains(chunk_position)
+                    })
+            }
+            None => self
+                .chunk_data
+                .keys()
+                .any(|chunk_position| !self.chunk_meshes.contains_key(chunk_position)),
+        };
+        has_unmeshed_chunks
+            || self
+                .fading_chunks
+                .iter()
+                .any(|chunk_position| self.chunk_fade_start(*chunk_position).is_none())
+    }
+
+    /// Seconds since the world was created, the time base shared with the shader.
+    pub(crate) fn elapsed_seconds(&self) -> f32 {
+        self.epoch.elapsed().as_secs_f32()
+    }
+
+    /// When the chunk started fading in, if its first mesh arrived less than
+    /// `CHUNK_FADE_DURATION` ago. Fading chunks are drawn through the transparent pipeline,
+    /// sorted with the rest of its faces.
+    pub(crate) fn chunk_fade_start(&self, chunk_position: ChunkPosition) -> Option<f32> {
+        self.chunk_fade_starts
+            .get(&chunk_position)
+            .copied()
+            .filter(|fade_start| {
+                self.elapsed_seconds() - fade_start < CHUNK_FADE_DURATION.as_secs_f32()
+            })
+    }
+
+    pub(crate) fn set_fading_chunks(&mut self, fading_chunks: HashSet<ChunkPosition>) {
+        self.fading_chunks = fading_chunks;
+    }
+
+    /// Tints chunks as they load and the chunks at the edge of the range, which are the next to
+    /// unload, so that streaming at the edge of the render distance can be watched.
+    pub(crate) fn toggle_chunk_flashes(&mut self) -> bool {
+        self.is_chunk_flashing_enabled = !self.is_chunk_flashing_enabled;
+        self.is_chunk_flashing_enabled
+    }
+
+    pub(crate) fn is_chunk_flashing_enabled(&self) -> bool {
+        self.is_chunk_flashing_enabled
+    }
+
+    /// Whether chunk flashes are enabled and the chunk is at the edge of the range around the
+    /// last update position, so moving a chunk away from it would unload it.
+    pub(crate) fn is_chunk_about_to_unload(&self, chunk_position: ChunkPosition) -> bool {
+        self.is_chunk_flashing_enabled
+            && self.last_update_position.is_some_and(|origin| {
+                chunk_position
+                    .neighbors()
+                    .iter()
+                    .any(|neighbor| !self.is_in_range(origin, *neighbor))
+            })
+    }
+
+    /// Whether all six neighbors are loaded and made entirely of occluding voxels, so none of
+    /// the chunk's faces can be seen. Checking whole neighbors rather than just the shared
+    /// boundary keeps this a set lookup; it only misses buried chunks next to hollow neighbors.
+    pub(crate) fn is_chunk_buried(&self, chunk_position: ChunkPosition) -> bool {
+        chunk_position
+            .neighbors()
+            .iter()
+            .all(|neighbor_position| self.occluding_chunks.contains(neighbor_position))
+    }
+
+    pub(crate) fn seed(&self) -> u64 {
+        self.seed
     }
 
     pub(crate) fn voxel_registry(&self) -> &VoxelRegistry {
@@ -103,22 +738,289 @@ impl World {
         &self.texture_atlas
     }
 
+    /// Must be called before any chunk is meshed, since meshes bake in atlas coordinates.
+    /// Like the texture atlas, only takes effect for chunks loaded and meshed afterwards.
+    pub(crate) fn set_voxel_registry(&mut self, voxel_registry: VoxelRegistry) {
+        self.voxel_registry = Arc::new(voxel_registry);
+    }
+
+    pub(crate) fn set_texture_atlas(&mut self, texture_atlas: TextureAtlas) {
+        self.texture_atlas = Arc::new(texture_atlas);
+    }
+
     pub(crate) fn chunk_data(&self) -> &HashMap<ChunkPosition, Chunk> {
         &self.chunk_data
     }
 
-    pub(crate) fn chunk_meshes(&self) -> &HashMap<ChunkPosition, Mesh> {
+    pub(crate) fn chunk_meshes(&self) -> &HashMap<ChunkPosition, LayeredMesh> {
         &self.chunk_meshes
     }
 
-    pub(crate) fn insert_chunk_mesh(&mut self, chunk_position: &ChunkPosition, chunk_mesh: Mesh) {
+    /// Stores a chunk's mesh, starting its fade-in if it is the first mesh since it loaded.
+    pub(crate) fn insert_chunk_mesh(
+        &mut self,
+        chunk_position: &ChunkPosition,
+        chunk_mesh: LayeredMesh,
+    ) {
+        let now = self.elapsed_seconds();
+        self.chunk_fade_starts.entry(*chunk_position).or_insert(now);
         self.chunk_meshes.insert(*chunk_position, chunk_mesh);
     }
 
+    /// Textures placed on a single voxel in place of its registry defaults.
+    pub(crate) fn texture_override(
+        &self,
+        chunk_position: ChunkPosition,
+        local_chunk_position: LocalChunkPosition,
+    ) -> Option<&VoxelTextures> {
+        self.texture_overrides
+            .get(&(chunk_position, local_chunk_position))
+    }
+
+    /// Drops every chunk mesh so that the next `Renderer::update_mesh` rebuilds all loaded
+    /// chunks, for comparing meshing changes without streaming the world in again. Meshes in
+    /// the on-disk cache are still reused.
+    pub(crate) fn invalidate_all_meshes(&mut self) {
+        self.chunk_meshes.clear();
+        self.mesh_generations.clear();
+        if let Some(mesh_queue) = &self.mesh_queue {
+            mesh_queue.cancel_all();
+        }
+    }
+
+    /// Drops the chunks' meshes and marks any background meshes of them still in flight as
+    /// stale.
+    fn invalidate_chunk_meshes(
+        &mut self,
+        chunk_positions: impl IntoIterator<Item = ChunkPosition>,
+    ) {
+        self.mesh_generation += 1;
+        for chunk_position in chunk_positions {
+            self.chunk_meshes.remove(&chunk_position);
+            self.mesh_generations
+                .insert(chunk_position, self.mesh_generation);
+            if let Some(mesh_queue) = &self.mesh_queue {
+                mesh_queue.cancel(&chunk_position);
+            }
+        }
+    }
+
+    /// Moves chunk meshing off the main thread. Frames keep rendering while chunks are meshed,
+    /// and chunks appear once their meshes arrive.
+    pub(crate) fn set_background_meshing(&mut self, is_enabled: bool) {
+        self.mesh_queue = is_enabled.then(|| MeshQueue::new(rayon::current_num_threads() * 2));
+    }
+
+    pub(crate) fn is_background_meshing_enabled(&self) -> bool {
+        self.mesh_queue.is_some()
+    }
+
+    /// Background meshes queued or running, zero when meshing on the main thread.
+    pub(crate) fn mesh_queue_depth(&self) -> usize {
+        self.mesh_queue.as_ref().map_or(0, MeshQueue::depth)
+    }
+
+    /// Stores the background meshes that finished since the last call, dropping those whose
+    /// chunk was edited or unloaded since they were queued, then queues the nearest chunks
+    /// still missing a mesh while there is room.
+    pub(crate) fn update_mesh_queue(&mut self) {
+        let Some(mut mesh_queue) = self.mesh_queue.take() else {
+            return;
+        };
+
+        for (chunk_position, generation, chunk_mesh) in mesh_queue.receive() {
+            if self.mesh_generations.get(&chunk_position) == Some(&generation) {
+                self.insert_chunk_mesh(&chunk_position, chunk_mesh);
+            }
+        }
+
+        let mut unqueued_chunks = self
+            .pending_remesh_chunks()
+            .into_iter()
+            .filter(|chunk_position| !mesh_queue.contains(chunk_position))
+            .collect::<Vec<ChunkPosition>>();
+        let (origin_x, origin_y, origin_z) = self
+            .last_update_position
+            .as_ref()
+            .map_or((0, 0, 0), ChunkPosition::get);
+        unqueued_chunks.sort_by_key(|chunk_position| {
+            let (x, y, z) = chunk_position.get();
+            (x - origin_x).pow(2) + (y - origin_y).pow(2) + (z - origin_z).pow(2)
+        });
+        for chunk_position in unqueued_chunks.into_iter().take(mesh_queue.free_slots()) {
+            let generation = if let Some(&generation) = self.mesh_generations.get(&chunk_position) {
+                generation
+            } else {
+                self.mesh_generation += 1;
+                self.mesh_generations
+                    .insert(chunk_position, self.mesh_generation);
+                self.mesh_generation
+            };
+            let neighborhood = self.neighborhood(chunk_position);
+            mesh_queue.submit(chunk_position, generation, move || {
+                Mesh::load_or_build_chunk(&neighborhood, chunk_position)
+            });
+        }
+
+        self.mesh_queue = Some(mesh_queue);
+    }
+
+    /// A world holding only what meshing the chunk reads: the chunk, the chunks around it, and
+    /// its texture overrides, sharing this world's registry and atlas.
+    fn neighborhood(&self, chunk_position: ChunkPosition) -> Self {
+        let mut neighborhood = Self::with_assets(
+            self.seed,
+            Arc::clone(&self.voxel_registry),
+            Arc::clone(&self.texture_atlas),
+        );
+        neighborhood.mesh_cache.clone_from(&self.mesh_cache);
+        for position in chunk_position.surrounding() {
+            if let Some(chunk) = self.chunk_data.get(&position) {
+                neighborhood.chunk_data.insert(position, chunk.clone());
+            }
+            if self.occluding_chunks.contains(&position) {
+                neighborhood.occluding_chunks.insert(position);
+            }
+        }
+        neighborhood.texture_overrides.extend(
+            self.texture_overrides
+                .iter()
+                .filter(|((override_chunk_position, _), _)| {
+                    *override_chunk_position == chunk_position
+                })
+                .map(|(key, textures)| (*key, textures.clone())),
+        );
+        neighborhood
+    }
+
+    /// Rebuilds one chunk's mesh right away, without consulting the mesh cache, and stores it
+    /// in place of the old one. Since the chunk no longer counts as pending, follow up with
+    /// `Renderer::update_mesh` to upload it.
+    pub(crate) fn remesh_chunk(&mut self, chunk_position: ChunkPosition) -> LayeredMesh {
+        let chunk_mesh = if self.is_chunk_buried(chunk_position) {
+            LayeredMesh::default()
+        } else {
+            let content_hash = self
+                .mesh_cache
+                .as_ref()
+                .map(|_| self.chunk_content_hash(chunk_position));
+            Mesh::build_chunk(self, chunk_position, content_hash)
+        };
+        self.insert_chunk_mesh(&chunk_position, chunk_mesh.clone());
+        chunk_mesh
+    }
+
+    pub(crate) fn mesh_cache(&self) -> Option<&MeshCache> {
+        self.mesh_cache.as_ref()
+    }
+
+    pub(crate) fn set_mesh_cache(&mut self, mesh_cache: MeshCache) {
+        self.mesh_cache = Some(mesh_cache);
+    }
+
+    /// Hashes everything a chunk's mesh depends on: its position, its voxels, the voxels of
+    /// the chunks around it, which decide face culling and shading along the borders, and the
+    /// registry and atlas layout the mesh bakes in. The hash keys the on-disk mesh cache, so it uses FNV-1a.
+    pub(crate) fn chunk_content_hash(&self, chunk_position: ChunkPosition) -> u64 {
+        let mut hasher = FnvHasher::default();
+        chunk_position.get().hash(&mut hasher);
+        for neighbor_position in chunk_position.surrounding() {
+            self.chunk_data
+                .get(&neighbor_position)
+                .map(Chunk::voxels)
+                .hash(&mut hasher);
+        }
+
+        let mut texture_overrides = self
+            .texture_overrides
+            .iter()
+            .filter(|((override_chunk_position, _), _)| *override_chunk_position == chunk_position)
+            .map(|((_, local_chunk_position), textures)| (local_chunk_position.get(), textures))
+            .collect::<Vec<_>>();
+        texture_overrides.sort_unstable_by_key(|(local_chunk_position, _)| *local_chunk_position);
+        texture_overrides.hash(&mut hasher);
+
+        self.voxel_registry.hash(&mut hasher);
+        for (texture, coordinates) in self.texture_atlas.layout() {
+            texture.hash(&mut hasher);
+            <[f32; 4]>::from(coordinates)
+                .map(f32::to_bits)
+                .hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+
+    /// A hash of the generated voxels in the cube of chunks within `radius` of `origin`, for
+    /// checking that generation still produces the same world. Chunks are generated afresh
+    /// rather than read from the loaded ones, so edits, load order and rendering have no effect,
+    /// and FNV-1a is used instead of the std hasher so the value is stable across Rust releases.
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) fn content_hash_around(&self, origin: ChunkPosition, radius: i32) -> u64 {
+        let radius = radius.max(0);
+        let offsets = (-radius..=radius).flat_map(|x| {
+            (-radius..=radius).flat_map(move |y| (-radius..=radius).map(move |z| (x, y, z)))
+        });
+        let mut hasher = FnvHasher::default();
+        for (x, y, z) in offsets {
+            let chunk = Chunk::generate(origin.offset(x, y, z), self.seed);
+            for voxel in chunk.voxels() {
+                hasher.write(&voxel.to_le_bytes());
+            }
+        }
+        hasher.finish()
+    }
+
+    /// The `limit` loaded chunk positions nearest to the last update position, or every loaded
+    /// position in no particular order without a limit.
+    pub(crate) fn nearest_chunk_positions(&self, limit: Option<usize>) -> Vec<ChunkPosition> {
+        let mut chunk_positions = self
+            .chunk_data
+            .keys()
+            .copied()
+            .collect::<Vec<ChunkPosition>>();
+        if let Some(limit) = limit {
+            let origin = self
+                .last_update_position
+                .unwrap_or_else(|| ChunkPosition::new(0, 0, 0));
+            chunk_positions.sort_by_cached_key(|chunk_position| {
+                let (x, y, z) = (*chunk_position - origin).get();
+                x.pow(2) + y.pow(2) + z.pow(2)
+            });
+            chunk_positions.truncate(limit);
+        }
+        chunk_positions
+    }
+
     pub(crate) fn last_update_position(&self) -> Option<ChunkPosition> {
         self.last_update_position
     }
 
+    /// The height of the highest non-air voxel in the column at `x`, `z` within the loaded
+    /// chunks. Where nothing of the column is loaded, this is the height the generator fills
+    /// up to, since the terrain is flat.
+    pub(crate) fn surface_height(&self, x: i32, z: i32) -> i32 {
+        let (column_x, _, column_z) = WorldPosition::new(x, 0, z).chunk_position().get();
+        let column_chunk_ys = self
+            .chunk_data
+            .keys()
+            .map(ChunkPosition::get)
+            .filter(|&(chunk_x, _, chunk_z)| chunk_x == column_x && chunk_z == column_z)
+            .map(|(_, chunk_y, _)| chunk_y)
+            .collect::<Vec<i32>>();
+        let (Some(lowest_chunk_y), Some(highest_chunk_y)) =
+            (column_chunk_ys.iter().min(), column_chunk_ys.iter().max())
+        else {
+            return TERRAIN_HEIGHT as i32 - 1;
+        };
+
+        let chunk_size = CHUNK_SIZE as i32;
+        (lowest_chunk_y * chunk_size..(highest_chunk_y + 1) * chunk_size)
+            .rev()
+            .find(|&y| self.get_voxel_type(WorldPosition::new(x, y, z)) != VoxelType::Air)
+            .unwrap_or(TERRAIN_HEIGHT as i32 - 1)
+    }
+
     pub(crate) fn get_voxel_type(&self, world_position: WorldPosition) -> VoxelType {
         let (chunk_position, local_chunk_position) = world_position.local_chunk_position();
         match self.chunk_data.get(&chunk_position) {
@@ -134,6 +1036,17 @@ impl World {
             .is_occluding()
     }
 
+    /// Whether the voxel blocks movement, unlike air or water.
+    pub(crate) fn get_is_solid(&self, world_position: WorldPosition) -> bool {
+        let voxel_type = self.get_voxel_type(world_position);
+        self.voxel_registry.get_properties(&voxel_type).is_solid()
+    }
+
+    pub(crate) fn get_material(&self, world_position: WorldPosition) -> MaterialKind {
+        let voxel_type = self.get_voxel_type(world_position);
+        self.voxel_registry.get_properties(&voxel_type).material()
+    }
+
     pub(crate) fn get_occluding_neighbors(
         &self,
         world_position: WorldPosition,
@@ -166,3 +1079,298 @@ impl World {
         OccludingVoxelNeighbors::new(front, back, right, left, top, bottom)
     }
 }
+
+/// Whether grass is beside `world_position`, level with it or one voxel above or below.
+fn is_next_to_grass(editor: &WorldEditor<'_>, world_position: WorldPosition) -> bool {
+    let sides = [
+        world_position.front(),
+        world_position.back(),
+        world_position.left(),
+        world_position.right(),
+    ];
+    (-1..=1).any(|dy| {
+        sides
+            .iter()
+            .any(|side| editor.get_voxel_type(side.offset(0, dy, 0)) == VoxelType::Grass)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::render::TextureType;
+
+    fn empty_world(chunk_positions: &[ChunkPosition]) -> World {
+        let mut world = World::new_with_seed(0);
+        world.chunk_data = chunk_positions
+            .iter()
+            .map(|chunk_position| (*chunk_position, Chunk::empty(*chunk_position)))
+            .collect();
+        world
+    }
+
+    fn load_around(world: &mut World, origin: ChunkPosition) {
+        world.update_chunks(origin);
+        while world.has_pending_chunk_loads() {
+            world.load_pending_chunks();
+        }
+    }
+
+    #[test]
+    fn generation_does_not_depend_on_load_order() {
+        let origin = ChunkPosition::new(0, 0, 0);
+        let mut direct = World::new_with_seed(42);
+        direct.set_render_distance(2, 1);
+        load_around(&mut direct, origin);
+
+        // Loads some of the same chunks from the other side first, and unloads some on the way
+        let mut detoured = World::new_with_seed(42);
+        detoured.set_render_distance(2, 1);
+        for detour in [
+            ChunkPosition::new(3, 1, -2),
+            ChunkPosition::new(-1, 0, 4),
+            origin,
+        ] {
+            load_around(&mut detoured, detour);
+        }
+
+        assert_eq!(
+            direct.chunk_data.len(),
+            detoured.chunk_data.len(),
+            "Both worlds should end up with the same chunks loaded"
+        );
+        let differing_chunks = direct
+            .chunk_data
+            .iter()
+            .filter(|(chunk_position, chunk)| {
+                detoured
+                    .chunk_data
+                    .get(chunk_position)
+                    .is_none_or(|other| other.voxels() != chunk.voxels())
+            })
+            .map(|(chunk_position, _)| *chunk_position)
+            .collect::<Vec<ChunkPosition>>();
+        assert!(
+            differing_chunks.is_empty(),
+            "Chunks should generate the same voxels in either order, but {differing_chunks:?} differ"
+        );
+    }
+
+    #[test]
+    fn raycast_hits_a_voxel_the_ray_only_clips() {
+        let mut world = empty_world(&[ChunkPosition::new(-1, -1, -1)]);
+        let target = WorldPosition::new(-3, -2, -4);
+        world.edit_batch(|editor| editor.set_voxel(target, VoxelType::Stone));
+
+        // Passes 0.02 inside the voxel's corner, which fixed steps of 0.05 could step over
+        let origin = Vec3::new(-3.48, -1.52, -1.0);
+        let direction = Vec3::new(0.0, 0.0, -1.0);
+        assert_eq!(
+            world.raycast(origin, direction, 4.0),
+            Some(target),
+            "The ray should stop at the voxel it clips"
+        );
+        assert_eq!(
+            world.raycast(origin, direction, 2.0),
+            None,
+            "The voxel should be out of reach of a shorter ray"
+        );
+        assert_eq!(
+            world.raycast(origin + Vec3::X * 0.04, direction, 4.0),
+            Some(target),
+            "A ray just inside the other side should hit it too"
+        );
+        assert_eq!(
+            world.raycast(origin - Vec3::X * 0.04, direction, 4.0),
+            None,
+            "A ray passing just outside the voxel should miss it"
+        );
+    }
+
+    #[test]
+    fn raycast_finds_the_nearest_voxel_on_a_diagonal() {
+        let mut world = empty_world(&[ChunkPosition::new(0, 0, 0)]);
+        let (near, far) = (WorldPosition::new(3, 3, 3), WorldPosition::new(5, 5, 5));
+        world.edit_batch(|editor| {
+            editor.set_voxel(far, VoxelType::Stone);
+            editor.set_voxel(near, VoxelType::Stone);
+        });
+
+        assert_eq!(
+            world.raycast(Vec3::new(1.0, 1.0, 1.0), Vec3::ONE, 10.0),
+            Some(near),
+            "The ray should stop at the first voxel it enters"
+        );
+        assert_eq!(
+            world.raycast(Vec3::new(1.0, 1.0, 1.0), Vec3::ZERO, 10.0),
+            None,
+            "A ray without a direction should hit nothing"
+        );
+    }
+
+    #[test]
+    fn placed_texture_overrides_are_kept_until_the_voxel_changes() {
+        let chunk_position = ChunkPosition::new(0, 0, 0);
+        let mut world = empty_world(&[chunk_position]);
+        let world_position = WorldPosition::new(3, 4, 5);
+        let (_, local_chunk_position) = world_position.local_chunk_position();
+        let textures = VoxelTextures::uniform(TextureType::Sand);
+
+        world.place_voxel_with_textures(world_position, VoxelType::Stone, textures.clone());
+        assert_eq!(
+            world.get_voxel_type(world_position),
+            VoxelType::Stone,
+            "The voxel should be placed"
+        );
+        assert!(
+            world.texture_override(chunk_position, local_chunk_position) == Some(&textures),
+            "The placed voxel should be drawn with its own textures"
+        );
+
+        world.break_voxel(world_position);
+        assert!(
+            world
+                .texture_override(chunk_position, local_chunk_position)
+                .is_none(),
+            "Breaking the voxel should drop its textures"
+        );
+    }
+
+    #[test]
+    fn flatten_and_replace_all_edit_across_chunk_borders() {
+        let mut world = empty_world(&[ChunkPosition::new(0, 0, 0), ChunkPosition::new(1, 0, 0)]);
+        let floating = WorldPosition::new(31, 10, 2);
+        world.edit_batch(|editor| editor.set_voxel(floating, VoxelType::Dirt));
+
+        // Reaches into the unloaded chunk at z = -1, which is skipped
+        world.flatten((30, -2), (33, 3), 4, VoxelType::Stone);
+        for (x, y, z) in [(30, 0, 0), (31, 4, 3), (32, 4, 2), (33, 2, 1)] {
+            assert_eq!(
+                world.get_voxel_type(WorldPosition::new(x, y, z)),
+                VoxelType::Stone,
+                "({x}, {y}, {z}) should be filled up to the flattened height"
+            );
+        }
+        assert_eq!(
+            world.get_voxel_type(floating),
+            VoxelType::Air,
+            "Voxels above the flattened height should be cleared"
+        );
+        assert_eq!(
+            world.get_voxel_type(WorldPosition::new(34, 4, 2)),
+            VoxelType::Air,
+            "Columns outside the area should be untouched"
+        );
+
+        world.replace_all(
+            WorldPosition::new(31, 3, 0),
+            WorldPosition::new(32, 4, 3),
+            VoxelType::Stone,
+            VoxelType::Grass,
+        );
+        assert_eq!(
+            world.get_voxel_type(WorldPosition::new(32, 4, 1)),
+            VoxelType::Grass,
+            "Matching voxels in the box should be replaced across the border"
+        );
+        assert_eq!(
+            world.get_voxel_type(WorldPosition::new(31, 2, 1)),
+            VoxelType::Stone,
+            "Voxels below the box should be kept"
+        );
+        assert_eq!(
+            world.get_voxel_type(WorldPosition::new(30, 4, 1)),
+            VoxelType::Stone,
+            "Voxels beside the box should be kept"
+        );
+    }
+
+    #[test]
+    fn content_hash_around_a_fixed_seed_is_unchanged() {
+        // Update this only for a deliberate change to terrain generation
+        const EXPECTED_HASH: u64 = 0x34b4_094d_5356_ed76;
+        let origin = ChunkPosition::new(0, 0, 0);
+        let mut world = World::new_with_seed(42);
+        let content_hash = world.content_hash_around(origin, 1);
+        assert_eq!(
+            content_hash, EXPECTED_HASH,
+            "Seed 42 should generate the same world as before, got {content_hash:#x}"
+        );
+
+        world.set_render_distance(1, 1);
+        load_around(&mut world, origin);
+        world.edit_batch(|editor| editor.set_voxel(WorldPosition::new(0, 0, 0), VoxelType::Sand));
+        assert_eq!(
+            world.content_hash_around(origin, 1),
+            content_hash,
+            "Loaded chunks and edits should not affect the hash"
+        );
+        assert_ne!(
+            World::new_with_seed(43).content_hash_around(origin, 1),
+            content_hash,
+            "Another seed should generate a different world"
+        );
+    }
+
+    #[test]
+    fn chunk_render_maximum_covers_every_chunk_in_range() {
+        let origins = [
+            ChunkPosition::new(0, 0, 0),
+            ChunkPosition::new(-7, 3, 12),
+            ChunkPosition::new(100, -40, -3),
+        ];
+        for render_shape in [RenderShape::Cylinder, RenderShape::Sphere] {
+            for (render_distance_xz, render_distance_up, render_distance_down) in
+                [(1, 1, 1), (2, 0, 3), (5, 2, 2), (9, 4, 1)]
+            {
+                let mut world = World::new_with_seed(0);
+                world.set_render_shape(render_shape);
+                world.set_render_distance(render_distance_xz, render_distance_up);
+                world.set_vertical_render_distance(render_distance_up, render_distance_down);
+                let chunk_render_maximum = world.chunk_render_maximum();
+                for origin in origins {
+                    let chunks_in_range = world.determine_chunks_in_range(origin).len();
+                    assert!(
+                        chunks_in_range <= chunk_render_maximum,
+                        "{chunks_in_range} chunks around {origin:?} should fit the estimate of \
+                        {chunk_render_maximum} for {render_shape:?} at distance \
+                        {render_distance_xz}, {render_distance_up} up, {render_distance_down} down"
+                    );
+                    assert_eq!(
+                        chunks_in_range, chunk_render_maximum,
+                        "The estimate should be exact so the vector is never oversized"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn only_chunks_at_the_edge_of_the_range_are_about_to_unload() {
+        let origin = ChunkPosition::new(0, 0, 0);
+        let mut world = World::new_with_seed(0);
+        world.set_render_distance(2, 1);
+        world.update_chunks(origin);
+        assert!(
+            !world.is_chunk_about_to_unload(ChunkPosition::new(2, 0, 0)),
+            "No chunk should be flagged while chunk flashes are off"
+        );
+
+        world.toggle_chunk_flashes();
+        for (chunk_position, is_about_to_unload) in [
+            (ChunkPosition::new(0, 0, 0), false),
+            (ChunkPosition::new(1, 0, 0), false),
+            (ChunkPosition::new(1, 0, -1), true),
+            (ChunkPosition::new(2, 0, 0), true),
+            (ChunkPosition::new(-1, 0, 1), true),
+            (ChunkPosition::new(0, 1, 0), true),
+            (ChunkPosition::new(0, -1, 1), true),
+        ] {
+            assert_eq!(
+                world.is_chunk_about_to_unload(chunk_position),
+                is_about_to_unload,
+                "{chunk_position:?} should be flagged only if a neighbor is out of range"
+            );
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use crate::game::chunk::CHUNK_SIZE;
+use glam::Vec3;
+use std::ops::{Add, Sub};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 pub(crate) struct WorldPosition {
     x: i32,
     y: i32,
@@ -12,10 +14,30 @@ impl WorldPosition {
         Self { x, y, z }
     }
 
+    /// The voxel containing `position`. Voxels are centered on integer coordinates, so each
+    /// spans half a unit either side and -0.4 belongs to voxel 0 while -0.6 belongs to -1.
+    pub(crate) fn from_vec3(position: Vec3) -> Self {
+        let (x, y, z) = (position + 0.5).floor().as_ivec3().into();
+        Self::new(x, y, z)
+    }
+
+    pub(crate) fn get(&self) -> (i32, i32, i32) {
+        (self.x, self.y, self.z)
+    }
+
     pub(crate) fn get_f32(&self) -> (f32, f32, f32) {
         (self.x as f32, self.y as f32, self.z as f32)
     }
 
+    pub(crate) fn offset(&self, dx: i32, dy: i32, dz: i32) -> Self {
+        Self::new(self.x + dx, self.y + dy, self.z + dz)
+    }
+
+    pub(crate) fn distance_squared(&self, other: Self) -> i32 {
+        let (x, y, z) = (*self - other).get();
+        x.pow(2) + y.pow(2) + z.pow(2)
+    }
+
     pub(crate) fn chunk_position(&self) -> ChunkPosition {
         let (x, y, z) = (
             self.x.div_euclid(CHUNK_SIZE as i32),
@@ -25,70 +47,41 @@ impl WorldPosition {
         ChunkPosition::new(x, y, z)
     }
 
+    /// Relies on `chunk_position` rounding toward negative infinity, so that negative
+    /// coordinates still land in `0..CHUNK_SIZE` of the chunk below rather than wrapping.
     pub(crate) fn local_chunk_position(&self) -> (ChunkPosition, LocalChunkPosition) {
         let chunk_position = self.chunk_position();
-        let (chunk_x, chunk_y, chunk_z) = chunk_position.get();
-        let (x, y, z) = (
-            self.x - chunk_x * CHUNK_SIZE as i32,
-            self.y - chunk_y * CHUNK_SIZE as i32,
-            self.z - chunk_z * CHUNK_SIZE as i32,
-        );
-        (
-            chunk_position,
-            LocalChunkPosition::new(x as usize, y as usize, z as usize),
-        )
+        let (x, y, z) = (*self - chunk_position.min_corner()).get();
+        let local_chunk_position = LocalChunkPosition::new(x as usize, y as usize, z as usize);
+        (chunk_position, local_chunk_position)
     }
 
     pub(crate) fn front(&self) -> Self {
-        Self {
-            x: self.x,
-            y: self.y,
-            z: self.z + 1,
-        }
+        self.offset(0, 0, 1)
     }
 
     pub(crate) fn back(&self) -> Self {
-        Self {
-            x: self.x,
-            y: self.y,
-            z: self.z - 1,
-        }
+        self.offset(0, 0, -1)
     }
 
     pub(crate) fn right(&self) -> Self {
-        Self {
-            x: self.x + 1,
-            y: self.y,
-            z: self.z,
-        }
+        self.offset(1, 0, 0)
     }
 
     pub(crate) fn left(&self) -> Self {
-        Self {
-            x: self.x - 1,
-            y: self.y,
-            z: self.z,
-        }
+        self.offset(-1, 0, 0)
     }
 
     pub(crate) fn top(&self) -> Self {
-        Self {
-            x: self.x,
-            y: self.y + 1,
-            z: self.z,
-        }
+        self.offset(0, 1, 0)
     }
 
     pub(crate) fn bottom(&self) -> Self {
-        Self {
-            x: self.x,
-            y: self.y - 1,
-            z: self.z,
-        }
+        self.offset(0, -1, 0)
     }
 }
 
-#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 pub(crate) struct ChunkPosition {
     x: i32,
     y: i32,
@@ -103,9 +96,46 @@ impl ChunkPosition {
     pub(crate) fn get(&self) -> (i32, i32, i32) {
         (self.x, self.y, self.z)
     }
+
+    pub(crate) fn offset(&self, dx: i32, dy: i32, dz: i32) -> Self {
+        Self::new(self.x + dx, self.y + dy, self.z + dz)
+    }
+
+    pub(crate) fn neighbors(&self) -> [Self; 6] {
+        [
+            self.offset(0, 0, 1),
+            self.offset(0, 0, -1),
+            self.offset(1, 0, 0),
+            self.offset(-1, 0, 0),
+            self.offset(0, 1, 0),
+            self.offset(0, -1, 0),
+        ]
+    }
+
+    /// This chunk and the 26 that share a face, edge or corner with it, whose border voxels
+    /// its mesh culls and shades against.
+    pub(crate) fn surrounding(&self) -> impl Iterator<Item = Self> {
+        let chunk_position = *self;
+        (-1..=1).flat_map(move |dx| {
+            (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| chunk_position.offset(dx, dy, dz)))
+        })
+    }
+
+    pub(crate) fn min_corner(&self) -> WorldPosition {
+        WorldPosition::new(
+            self.x * CHUNK_SIZE as i32,
+            self.y * CHUNK_SIZE as i32,
+            self.z * CHUNK_SIZE as i32,
+        )
+    }
+
+    pub(crate) fn max_corner(&self) -> WorldPosition {
+        let last = CHUNK_SIZE as i32 - 1;
+        self.min_corner().offset(last, last, last)
+    }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Hash, Eq, PartialEq)]
 pub(crate) struct LocalChunkPosition {
     x: usize,
     y: usize,
@@ -127,11 +157,115 @@ impl LocalChunkPosition {
 
     pub(crate) fn world_position(&self, chunk_position: ChunkPosition) -> WorldPosition {
         let (local_x, local_y, local_z) = self.get_i32();
-        let (offset_x, offset_y, offset_z) = (
-            chunk_position.x * CHUNK_SIZE as i32,
-            chunk_position.y * CHUNK_SIZE as i32,
-            chunk_position.z * CHUNK_SIZE as i32,
+        chunk_position
+            .min_corner()
+            .offset(local_x, local_y, local_z)
+    }
+}
+
+impl Add for WorldPosition {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.offset(other.x, other.y, other.z)
+    }
+}
+
+impl Sub for WorldPosition {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.offset(-other.x, -other.y, -other.z)
+    }
+}
+
+impl Add for ChunkPosition {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.offset(other.x, other.y, other.z)
+    }
+}
+
+impl Sub for ChunkPosition {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.offset(-other.x, -other.y, -other.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_vec3_rounds_to_the_containing_voxel() {
+        let cases = [
+            (Vec3::new(0.0, 0.0, 0.0), WorldPosition::new(0, 0, 0)),
+            (Vec3::new(0.49, -0.49, 0.4), WorldPosition::new(0, 0, 0)),
+            (Vec3::new(0.5, -0.5, 1.5), WorldPosition::new(1, 0, 2)),
+            (
+                Vec3::new(-0.51, -0.6, -1.49),
+                WorldPosition::new(-1, -1, -1),
+            ),
+            (
+                Vec3::new(-1.5, 31.5, -32.5),
+                WorldPosition::new(-1, 32, -32),
+            ),
+        ];
+        for (position, expected) in cases {
+            assert_eq!(
+                WorldPosition::from_vec3(position),
+                expected,
+                "{position} should lie in voxel {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn local_chunk_positions_lie_within_the_chunk_and_round_trip() {
+        let size = CHUNK_SIZE as i32;
+        let coordinates = [
+            -2 * size - 1,
+            -size,
+            -size + 1,
+            -1,
+            0,
+            1,
+            size - 1,
+            size,
+            3 * size,
+        ];
+        for &x in &coordinates {
+            for &y in &coordinates {
+                for &z in &coordinates {
+                    let world_position = WorldPosition::new(x, y, z);
+                    let (chunk_position, local_chunk_position) =
+                        world_position.local_chunk_position();
+                    assert!(
+                        <[usize; 3]>::from(local_chunk_position.get())
+                            .iter()
+                            .all(|coordinate| *coordinate < CHUNK_SIZE),
+                        "Local coordinates of {world_position:?} should lie within the chunk"
+                    );
+                    assert_eq!(
+                        local_chunk_position.world_position(chunk_position),
+                        world_position,
+                        "Local coordinates should convert back to the same world position"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn from_vec3_finds_the_chunk_below_zero() {
+        let chunk_position = WorldPosition::from_vec3(Vec3::new(-0.6, 0.0, -0.4)).chunk_position();
+        assert_eq!(
+            chunk_position,
+            ChunkPosition::new(-1, 0, 0),
+            "A point just past the -x edge of voxel 0 should be in the chunk below zero"
         );
-        WorldPosition::new(local_x + offset_x, local_y + offset_y, local_z + offset_z)
     }
 }
@@ -0,0 +1,18 @@
+/// Mixes a seed and a voxel coordinate into 32 random bits with the splitmix64 finalizer.
+///
+/// The result depends on nothing but its arguments, so generation that draws from it gives the
+/// same voxels however and in whatever order chunks are loaded.
+pub(crate) fn hash_to_rng(seed: u64, x: i32, y: i32, z: i32) -> u32 {
+    let mut hash = seed;
+    for coordinate in [x, y, z] {
+        hash = mix(hash ^ u64::from(coordinate.cast_unsigned()));
+    }
+    (hash >> 32) as u32
+}
+
+fn mix(value: u64) -> u64 {
+    let mut value = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
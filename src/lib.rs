@@ -1,6 +1,16 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod error;
+mod export;
 mod game;
+mod options;
+mod settings;
 
 pub use app::App;
+pub use error::CairnError;
+pub use export::RegionExport;
+pub use options::LaunchOptions;
@@ -1,16 +1,19 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
-use cairn::App;
-use winit::event_loop::EventLoop;
+use cairn::{App, LaunchOptions};
 
 fn main() -> anyhow::Result<()> {
     env_logger::Builder::from_default_env()
         .filter_level(log::LevelFilter::Info)
         .init();
 
-    let event_loop = EventLoop::with_user_event().build()?;
-    let mut app = App::default();
-    event_loop.run_app(&mut app)?;
+    let launch_options = LaunchOptions::from_args(std::env::args().skip(1))?;
+    if let Some(region_export) = launch_options.region_export() {
+        region_export.run()?;
+        return Ok(());
+    }
+
+    App::with_launch_options(launch_options)?.run()?;
 
     Ok(())
 }
@@ -0,0 +1,174 @@
+use crate::export::RegionExport;
+use crate::game::world::{ChunkPosition, RENDER_DISTANCE_XZ, RENDER_DISTANCE_Y, WORLD_SEED};
+use anyhow::{Context as _, bail};
+use std::path::{Path, PathBuf};
+
+// Horizontal and vertical render distances for `--preset low` and `--preset high`, either side
+// of the defaults used by `--preset medium`
+const LOW_PRESET_RENDER_DISTANCE: (i32, i32) = (3, 2);
+const HIGH_PRESET_RENDER_DISTANCE: (i32, i32) = (10, 4);
+
+const USAGE: &str = "Usage: cairn [--seed <u64>] [--render-distance <xz>[,<y>]] [--preset <low|medium|high>] [--present-mode <mode>] [--texture-pack <zip>] [--voxel-definitions <directory>]... [--icon <png>] [--export-region <x,y,z>..<x,y,z> <directory>]";
+
+/// Command-line overrides applied on top of the saved settings for a single run.
+#[derive(Clone, Debug, Default)]
+pub struct LaunchOptions {
+    seed: Option<u64>,
+    render_distance: Option<(i32, Option<i32>)>,
+    present_mode: Option<wgpu::PresentMode>,
+    texture_pack: Option<PathBuf>,
+    voxel_definitions: Vec<PathBuf>,
+    icon: Option<PathBuf>,
+    region_export: Option<RegionExport>,
+}
+
+impl LaunchOptions {
+    /// Parses flags such as `std::env::args().skip(1)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending argument when a flag is unknown, is missing its
+    /// value, or has a value that does not parse.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        let mut export_region = None;
+
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .with_context(|| format!("Missing value for {flag}\n{USAGE}"))
+            };
+            match flag.as_str() {
+                "--seed" => {
+                    let value = value()?;
+                    options.seed = Some(
+                        value
+                            .parse()
+                            .with_context(|| format!("Invalid seed: {value}"))?,
+                    );
+                }
+                "--render-distance" => {
+                    options.render_distance = Some(parse_render_distance(&value()?)?);
+                }
+                "--preset" => {
+                    let value = value()?;
+                    let (render_distance_xz, render_distance_y) = match value.as_str() {
+                        "low" => LOW_PRESET_RENDER_DISTANCE,
+                        "medium" => (RENDER_DISTANCE_XZ, RENDER_DISTANCE_Y),
+                        "high" => HIGH_PRESET_RENDER_DISTANCE,
+                        _ => bail!("Unknown preset: {value}\n{USAGE}"),
+                    };
+                    options.render_distance = Some((render_distance_xz, Some(render_distance_y)));
+                }
+                "--present-mode" => {
+                    options.present_mode = Some(parse_present_mode(&value()?)?);
+                }
+                "--texture-pack" => options.texture_pack = Some(PathBuf::from(value()?)),
+                "--voxel-definitions" => {
+                    options.voxel_definitions.push(PathBuf::from(value()?));
+                }
+                "--icon" => options.icon = Some(PathBuf::from(value()?)),
+                "--export-region" => {
+                    let (min, max) = parse_chunk_region(&value()?)?;
+                    export_region = Some((min, max, PathBuf::from(value()?)));
+                }
+                _ => bail!("Unknown argument: {flag}\n{USAGE}"),
+            }
+        }
+
+        options.region_export = export_region.map(|(min, max, directory)| {
+            RegionExport::new(options.seed.unwrap_or(WORLD_SEED), min, max, directory)
+        });
+        Ok(options)
+    }
+
+    /// Replaces the embedded window icon with an image file, e.g. for a rebranded build.
+    #[must_use]
+    pub fn with_icon(mut self, icon: impl Into<PathBuf>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    /// Set when the run should export chunks instead of opening a window.
+    pub fn region_export(&self) -> Option<&RegionExport> {
+        self.region_export.as_ref()
+    }
+
+    pub(crate) fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// The horizontal render distance and, when given, the vertical one.
+    pub(crate) fn render_distance(&self) -> Option<(i32, Option<i32>)> {
+        self.render_distance
+    }
+
+    pub(crate) fn present_mode(&self) -> Option<wgpu::PresentMode> {
+        self.present_mode
+    }
+
+    pub(crate) fn texture_pack(&self) -> Option<&Path> {
+        self.texture_pack.as_deref()
+    }
+
+    /// Definition directories in the order they are applied, later ones overriding earlier.
+    pub(crate) fn voxel_definitions(&self) -> Vec<&Path> {
+        self.voxel_definitions
+            .iter()
+            .map(PathBuf::as_path)
+            .collect()
+    }
+
+    pub(crate) fn icon(&self) -> Option<&Path> {
+        self.icon.as_deref()
+    }
+}
+
+fn parse_render_distance(value: &str) -> anyhow::Result<(i32, Option<i32>)> {
+    let parse = |distance: &str| {
+        distance
+            .parse::<i32>()
+            .ok()
+            .filter(|distance| *distance >= 0)
+            .with_context(|| format!("Invalid render distance: {value}"))
+    };
+    match value.split_once(',') {
+        Some((render_distance_xz, render_distance_y)) => {
+            Ok((parse(render_distance_xz)?, Some(parse(render_distance_y)?)))
+        }
+        None => Ok((parse(value)?, None)),
+    }
+}
+
+fn parse_chunk_region(value: &str) -> anyhow::Result<(ChunkPosition, ChunkPosition)> {
+    let parse_position = |position: &str| {
+        let coordinates = position
+            .split(',')
+            .map(str::parse::<i32>)
+            .collect::<Result<Vec<i32>, _>>()
+            .ok();
+        match coordinates.as_deref() {
+            Some(&[x, y, z]) => Ok(ChunkPosition::new(x, y, z)),
+            _ => bail!("Invalid chunk region: {value}\n{USAGE}"),
+        }
+    };
+    let (min, max) = value
+        .split_once("..")
+        .with_context(|| format!("Invalid chunk region: {value}\n{USAGE}"))?;
+    Ok((parse_position(min)?, parse_position(max)?))
+}
+
+fn parse_present_mode(value: &str) -> anyhow::Result<wgpu::PresentMode> {
+    Ok(match value {
+        "auto-vsync" => wgpu::PresentMode::AutoVsync,
+        "auto-no-vsync" => wgpu::PresentMode::AutoNoVsync,
+        "fifo" => wgpu::PresentMode::Fifo,
+        "fifo-relaxed" => wgpu::PresentMode::FifoRelaxed,
+        "immediate" => wgpu::PresentMode::Immediate,
+        "mailbox" => wgpu::PresentMode::Mailbox,
+        _ => bail!(
+            "Unknown present mode: {value} (expected auto-vsync, auto-no-vsync, fifo, fifo-relaxed, immediate or mailbox)"
+        ),
+    })
+}
@@ -0,0 +1,242 @@
+use crate::game::render::{
+    ALPHA_CUTOFF, CAMERA_FOV_Y, CAMERA_MOVE_SPEED, CAMERA_SPAWN_HEIGHT, CAMERA_SPRINT_FOV_KICK,
+    CAMERA_SPRINT_MULTIPLIER, CAMERA_TURN_SPEED, DepthMode, Handedness, MIN_BRIGHTNESS,
+    MOUSE_SENSITIVITY, WAVE_AMPLITUDE,
+};
+use crate::game::world::{RENDER_DISTANCE_XZ, RENDER_DISTANCE_Y, RenderShape, WORLD_SEED};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct Settings {
+    mouse_sensitivity: f32,
+    /// Overrides `mouse_sensitivity` for horizontal movement.
+    mouse_sensitivity_x: Option<f32>,
+    /// Overrides `mouse_sensitivity` for vertical movement.
+    mouse_sensitivity_y: Option<f32>,
+    fov_y: f32,
+    move_speed: f32,
+    vertical_move_speed: f32,
+    sprint_multiplier: f32,
+    /// Degrees the field of view widens by while sprinting.
+    sprint_fov_kick: f32,
+    /// Radians per second turned by the arrow keys.
+    turn_speed: f32,
+    /// Stops the flying camera from entering solid voxels.
+    solid_clamp: bool,
+    /// Voxels above the terrain surface that the camera starts at.
+    spawn_height: f32,
+    render_distance_xz: i32,
+    render_distance_y: i32,
+    /// Overrides `render_distance_y` above the camera.
+    render_distance_up: Option<i32>,
+    /// Overrides `render_distance_y` below the camera.
+    render_distance_down: Option<i32>,
+    render_shape: RenderShape,
+    /// Chunks generated per update while streaming, to spread loading over several frames.
+    /// Unlimited when unset.
+    chunk_load_budget: Option<usize>,
+    seed: u64,
+    handedness: Handedness,
+    depth_mode: DepthMode,
+    /// Draws the world offscreen and copies it to the window through a post-processing pass.
+    post_processing: bool,
+    /// Texture alpha below which cutout faces such as leaves are see-through.
+    alpha_cutoff: f32,
+    wave_amplitude: f32,
+    /// The darkest that ambient occlusion and lighting together can shade a face.
+    min_brightness: f32,
+    /// Shades each face by the direction it points, brightest on top and darkest underneath.
+    face_shading: bool,
+    /// Draws opaque faces as instances of one quad instead of expanded vertices.
+    instanced_faces: bool,
+    /// Draws a ring of distant land past the loaded chunks to hide the render-distance edge. It is
+    /// skipped when fog already hides that edge.
+    horizon_skirt: bool,
+    /// How quickly distant faces fade into the sky color, 0 for no fog.
+    fog_density: f32,
+    /// Meshes chunks on worker threads so that streaming never stalls a frame.
+    background_meshing: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            mouse_sensitivity: MOUSE_SENSITIVITY,
+            mouse_sensitivity_x: None,
+            mouse_sensitivity_y: None,
+            fov_y: CAMERA_FOV_Y,
+            move_speed: CAMERA_MOVE_SPEED,
+            vertical_move_speed: CAMERA_MOVE_SPEED,
+            sprint_multiplier: CAMERA_SPRINT_MULTIPLIER,
+            sprint_fov_kick: CAMERA_SPRINT_FOV_KICK,
+            turn_speed: CAMERA_TURN_SPEED,
+            solid_clamp: false,
+            spawn_height: CAMERA_SPAWN_HEIGHT,
+            render_distance_xz: RENDER_DISTANCE_XZ,
+            render_distance_y: RENDER_DISTANCE_Y,
+            render_distance_up: None,
+            render_distance_down: None,
+            render_shape: RenderShape::default(),
+            chunk_load_budget: None,
+            seed: WORLD_SEED,
+            handedness: Handedness::default(),
+            depth_mode: DepthMode::default(),
+            post_processing: false,
+            alpha_cutoff: ALPHA_CUTOFF,
+            wave_amplitude: WAVE_AMPLITUDE,
+            min_brightness: MIN_BRIGHTNESS,
+            face_shading: true,
+            instanced_faces: false,
+            horizon_skirt: false,
+            fog_density: 0.0,
+            background_meshing: false,
+        }
+    }
+}
+
+impl Settings {
+    pub(crate) fn load(path: &Path) -> Self {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                info!(
+                    "Using default settings, could not read {}: {error}",
+                    path.display()
+                );
+                return Self::default();
+            }
+        };
+
+        toml::from_str(&contents).unwrap_or_else(|error| {
+            warn!(
+                "Using default settings, could not parse {}: {error}",
+                path.display()
+            );
+            Self::default()
+        })
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Horizontal and vertical mouse sensitivity, each falling back to `mouse_sensitivity`.
+    pub(crate) fn mouse_sensitivity(&self) -> (f32, f32) {
+        (
+            self.mouse_sensitivity_x.unwrap_or(self.mouse_sensitivity),
+            self.mouse_sensitivity_y.unwrap_or(self.mouse_sensitivity),
+        )
+    }
+
+    pub(crate) fn fov_y(&self) -> f32 {
+        self.fov_y
+    }
+
+    pub(crate) fn move_speed(&self) -> f32 {
+        self.move_speed
+    }
+
+    pub(crate) fn vertical_move_speed(&self) -> f32 {
+        self.vertical_move_speed
+    }
+
+    pub(crate) fn sprint_multiplier(&self) -> f32 {
+        self.sprint_multiplier
+    }
+
+    pub(crate) fn sprint_fov_kick(&self) -> f32 {
+        self.sprint_fov_kick
+    }
+
+    pub(crate) fn turn_speed(&self) -> f32 {
+        self.turn_speed
+    }
+
+    pub(crate) fn solid_clamp(&self) -> bool {
+        self.solid_clamp
+    }
+
+    pub(crate) fn spawn_height(&self) -> f32 {
+        self.spawn_height
+    }
+
+    pub(crate) fn render_distance_xz(&self) -> i32 {
+        self.render_distance_xz
+    }
+
+    pub(crate) fn set_render_distance_xz(&mut self, render_distance_xz: i32) {
+        self.render_distance_xz = render_distance_xz;
+    }
+
+    pub(crate) fn render_distance_y(&self) -> i32 {
+        self.render_distance_y
+    }
+
+    /// Chunks loaded above and below the camera, each falling back to `render_distance_y`.
+    pub(crate) fn vertical_render_distance(&self, render_distance_y: i32) -> (i32, i32) {
+        (
+            self.render_distance_up.unwrap_or(render_distance_y),
+            self.render_distance_down.unwrap_or(render_distance_y),
+        )
+    }
+
+    pub(crate) fn render_shape(&self) -> RenderShape {
+        self.render_shape
+    }
+
+    pub(crate) fn chunk_load_budget(&self) -> Option<usize> {
+        self.chunk_load_budget
+    }
+
+    pub(crate) fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub(crate) fn handedness(&self) -> Handedness {
+        self.handedness
+    }
+
+    pub(crate) fn depth_mode(&self) -> DepthMode {
+        self.depth_mode
+    }
+
+    pub(crate) fn post_processing(&self) -> bool {
+        self.post_processing
+    }
+
+    pub(crate) fn alpha_cutoff(&self) -> f32 {
+        self.alpha_cutoff
+    }
+
+    pub(crate) fn wave_amplitude(&self) -> f32 {
+        self.wave_amplitude
+    }
+
+    pub(crate) fn min_brightness(&self) -> f32 {
+        self.min_brightness
+    }
+
+    pub(crate) fn face_shading(&self) -> bool {
+        self.face_shading
+    }
+
+    pub(crate) fn instanced_faces(&self) -> bool {
+        self.instanced_faces
+    }
+
+    pub(crate) fn horizon_skirt(&self) -> bool {
+        self.horizon_skirt
+    }
+
+    pub(crate) fn fog_density(&self) -> f32 {
+        self.fog_density
+    }
+
+    pub(crate) fn background_meshing(&self) -> bool {
+        self.background_meshing
+    }
+}